@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+
+use crate::discovery;
+use crate::formula::Formula;
+use crate::hotspot::{HotspotStats, IndexWeights, MiRange, PathType, ZeroMi};
+
+/// Rows rolled up from the files in addition to the files themselves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation {
+    Dirs,
+}
+
+impl ValueEnum for Aggregation {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Aggregation::Dirs]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Aggregation::Dirs => {
+                PossibleValue::new("dirs").help("One more row per directory containing files")
+            }
+        })
+    }
+}
+
+/// Roll up the stats of the files into each directory containing them, up to
+/// `root`: sizes, changes, debt comments, risks and the metrics of the
+/// plugins are summed, complexities are the maximum of the files and the
+/// maintainability index is the minimum.
+///
+/// The test ratio compares the lines of code of the tests with the ones of the
+/// other files of the directory, see [`discovery::is_test_file`].
+pub(crate) fn directories(
+    files: &[HotspotStats],
+    root: &Path,
+    formula: Option<&Formula>,
+    weights: IndexWeights,
+    mi_range: MiRange,
+    zero_mi: ZeroMi,
+) -> Vec<HotspotStats> {
+    let mut directories: BTreeMap<PathBuf, HotspotStats> = BTreeMap::new();
+    // Lines of code of the files known by the coverage report
+    let mut covered_loc: BTreeMap<PathBuf, u32> = BTreeMap::new();
+    // Functions of the files whose type annotations are known
+    let mut annotated_nom: BTreeMap<PathBuf, u32> = BTreeMap::new();
+    let mut test_loc: BTreeMap<PathBuf, u32> = BTreeMap::new();
+    let mut files_count: BTreeMap<PathBuf, u32> = BTreeMap::new();
+
+    for file in files {
+        let ancestors = Path::new(&file.path)
+            .ancestors()
+            .skip(1)
+            .take_while(|directory| directory.starts_with(root));
+
+        for directory in ancestors {
+            *files_count.entry(directory.to_path_buf()).or_default() += 1;
+            if file.coverage.is_some() {
+                *covered_loc.entry(directory.to_path_buf()).or_default() += file.loc;
+            }
+            if file.type_annotations.is_some() {
+                *annotated_nom.entry(directory.to_path_buf()).or_default() += file.nom;
+            }
+            if discovery::is_test_file(Path::new(&file.path)) {
+                *test_loc.entry(directory.to_path_buf()).or_default() += file.loc;
+            }
+
+            directories
+                .entry(directory.to_path_buf())
+                .and_modify(|stats| merge(stats, file))
+                .or_insert_with(|| HotspotStats {
+                    path: display_directory(directory),
+                    path_type: PathType::Directory,
+                    function: None,
+                    start_line: None,
+                    end_line: None,
+                    comments_percentage: file.comments_percentage * file.loc as f64,
+                    duplication: file.duplication * file.loc as f64,
+                    duplication_partners: vec![],
+                    last_author: None,
+                    primary_owner: None,
+                    coverage: file.coverage.map(|coverage| coverage * file.loc as f64),
+                    knowledge_loss: file
+                        .knowledge_loss
+                        .map(|knowledge_loss| knowledge_loss * file.changes_count as f64),
+                    type_annotations: file
+                        .type_annotations
+                        .map(|type_annotations| type_annotations * file.nom as f64),
+                    ..file.clone()
+                });
+        }
+    }
+
+    directories
+        .into_iter()
+        .map(|(directory, mut stats)| {
+            // Comments, duplication and coverage were weighted by the lines of
+            // code of each file
+            (stats.comments_percentage, stats.duplication) = match stats.loc {
+                0 => (0.0, 0.0),
+                loc => (
+                    stats.comments_percentage / loc as f64,
+                    stats.duplication / loc as f64,
+                ),
+            };
+            stats.cyclomatic_average /= files_count[&directory] as f64;
+            stats.coverage = match covered_loc.get(&directory) {
+                Some(0) | None => stats.coverage,
+                Some(&loc) => stats.coverage.map(|coverage| coverage / loc as f64),
+            };
+            // Knowledge loss was weighted by the changes count
+            if stats.changes_count > 0 {
+                stats.knowledge_loss = stats
+                    .knowledge_loss
+                    .map(|knowledge_loss| knowledge_loss / stats.changes_count as f64);
+            }
+            // Type annotations were weighted by the number of functions
+            stats.type_annotations = match annotated_nom.get(&directory) {
+                Some(0) | None => stats.type_annotations,
+                Some(&nom) => stats
+                    .type_annotations
+                    .map(|type_annotations| type_annotations / nom as f64),
+            };
+            let test_loc = test_loc.get(&directory).copied().unwrap_or_default();
+            stats.test_ratio = match stats.loc - test_loc {
+                0 => None,
+                production_loc => Some(test_loc as f64 / production_loc as f64),
+            };
+            stats.hotspot_index = match formula {
+                Some(formula) => formula.evaluate(&stats),
+                None => weights.hotspot_index(&stats, mi_range, zero_mi),
+            };
+
+            stats
+        })
+        .collect()
+}
+
+fn merge(directory: &mut HotspotStats, file: &HotspotStats) {
+    directory.halstead_volume += file.halstead_volume;
+    directory.cyclomatic_complexity = directory
+        .cyclomatic_complexity
+        .max(file.cyclomatic_complexity);
+    directory.cyclomatic_average += file.cyclomatic_average;
+    directory.cyclomatic_total += file.cyclomatic_total;
+    directory.max_nesting = directory.max_nesting.max(file.max_nesting);
+    directory.nom += file.nom;
+    directory.longest_function = directory.longest_function.max(file.longest_function);
+    directory.loc += file.loc;
+    directory.comments_percentage += file.comments_percentage * file.loc as f64;
+    directory.duplication += file.duplication * file.loc as f64;
+    directory.debt_comments += file.debt_comments;
+    directory.maintainability_index = directory
+        .maintainability_index
+        .min(file.maintainability_index);
+    directory.changes_count += file.changes_count;
+    directory.changes_30d += file.changes_30d;
+    directory.changes_90d += file.changes_90d;
+    directory.changes_365d += file.changes_365d;
+    directory.lines_changed += file.lines_changed;
+    directory.feature_changes += file.feature_changes;
+    directory.fix_changes += file.fix_changes;
+    directory.refactor_changes += file.refactor_changes;
+    directory.chore_changes += file.chore_changes;
+    directory.author_entropy = directory.author_entropy.max(file.author_entropy);
+    directory.knowledge_loss = sum_options(
+        directory.knowledge_loss,
+        file.knowledge_loss
+            .map(|knowledge_loss| knowledge_loss * file.changes_count as f64),
+    );
+    directory.coverage = sum_options(
+        directory.coverage,
+        file.coverage.map(|coverage| coverage * file.loc as f64),
+    );
+    directory.risk = sum_options(directory.risk, file.risk);
+    directory.test_loc = match (directory.test_loc, file.test_loc) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    };
+    directory.fan_in = directory.fan_in.max(file.fan_in);
+    directory.fan_out = directory.fan_out.max(file.fan_out);
+    directory.centrality = match (directory.centrality, file.centrality) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+    directory.type_annotations = sum_options(
+        directory.type_annotations,
+        file.type_annotations
+            .map(|type_annotations| type_annotations * file.nom as f64),
+    );
+    for (name, value) in &file.plugins {
+        *directory.plugins.entry(name.clone()).or_default() += value;
+    }
+    directory.first_commit = match (directory.first_commit, file.first_commit) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    if let Some(monthly_changes) = &file.monthly_changes {
+        let months = directory.monthly_changes.get_or_insert_with(BTreeMap::new);
+        for (month, count) in monthly_changes {
+            *months.entry(month.clone()).or_default() += count;
+        }
+    }
+}
+
+/// The sum of the known values, unknown when none is.
+fn sum_options(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+fn display_directory(directory: &Path) -> String {
+    match directory.as_os_str().is_empty() {
+        true => ".".to_string(),
+        false => directory.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, loc: u32, maintainability_index: f64, changes_count: u32) -> HotspotStats {
+        HotspotStats {
+            path: path.to_string(),
+            path_type: PathType::File,
+            function: None,
+            start_line: None,
+            end_line: None,
+            halstead_volume: 10.0,
+            cyclomatic_complexity: loc as f64,
+            cyclomatic_average: loc as f64,
+            cyclomatic_total: loc as f64,
+            max_nesting: 1,
+            nom: 2,
+            longest_function: loc,
+            loc,
+            comments_percentage: 10.0,
+            maintainability_index,
+            changes_count,
+            changes_30d: 0,
+            changes_90d: 0,
+            changes_365d: 0,
+            lines_changed: 0,
+            feature_changes: 0,
+            fix_changes: 0,
+            refactor_changes: 0,
+            chore_changes: 0,
+            author_entropy: 0.0,
+            knowledge_loss: None,
+            monthly_changes: None,
+            first_commit: None,
+            last_author: None,
+            primary_owner: None,
+            hotspot_index: 0.0,
+            coverage: None,
+            risk: None,
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: 0.0,
+            duplication_partners: vec![],
+            debt_comments: 0,
+            type_annotations: None,
+            test_ratio: None,
+            test_loc: None,
+            fan_in: None,
+            fan_out: None,
+            centrality: None,
+            plugins: Default::default(),
+            severity: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_directories() {
+        // ARRANGE
+        let files = [
+            file("pkg/a.py", 10, 80.0, 1),
+            file("pkg/sub/b.py", 30, 40.0, 3),
+            file("main.py", 5, 100.0, 2),
+        ];
+
+        // ACT
+        let actual = directories(
+            &files,
+            Path::new(""),
+            None,
+            IndexWeights::default(),
+            MiRange::Raw,
+            ZeroMi::Infinity,
+        );
+
+        // ASSERT
+        let directory = |path: &str, loc, mi: f64, changes_count, nom| HotspotStats {
+            path: path.to_string(),
+            path_type: PathType::Directory,
+            halstead_volume: 10.0 * nom as f64 / 2.0,
+            nom,
+            comments_percentage: 10.0,
+            hotspot_index: changes_count as f64 / (mi / 100.0),
+            test_ratio: Some(0.0),
+            plugins: Default::default(),
+            ..file(path, loc, mi, changes_count)
+        };
+        let expected = vec![
+            HotspotStats {
+                cyclomatic_complexity: 30.0,
+                cyclomatic_average: 15.0,
+                longest_function: 30,
+                ..directory(".", 45, 40.0, 6, 6)
+            },
+            HotspotStats {
+                cyclomatic_complexity: 30.0,
+                cyclomatic_average: 20.0,
+                longest_function: 30,
+                ..directory("pkg", 40, 40.0, 4, 4)
+            },
+            directory("pkg/sub", 30, 40.0, 3, 2),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_directories_test_ratio() {
+        // ARRANGE
+        let files = [
+            file("pkg/a.py", 40, 80.0, 1),
+            file("pkg/test_a.py", 10, 80.0, 1),
+            file("tests/test_b.py", 30, 80.0, 1),
+            file("tests/conftest.py", 5, 80.0, 1),
+        ];
+
+        // ACT
+        let actual = directories(
+            &files,
+            Path::new(""),
+            None,
+            IndexWeights::default(),
+            MiRange::Raw,
+            ZeroMi::Infinity,
+        )
+        .into_iter()
+        .map(|stats| (stats.path, stats.test_ratio))
+        .collect::<Vec<_>>();
+
+        // ASSERT
+        let expected = vec![
+            (".".to_string(), Some(45.0 / 40.0)),
+            ("pkg".to_string(), Some(10.0 / 40.0)),
+            ("tests".to_string(), None),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_directories_below_root() {
+        // ARRANGE
+        let files = [file("pkg/sub/b.py", 30, 40.0, 3)];
+
+        // ACT
+        let actual = directories(
+            &files,
+            Path::new("pkg"),
+            None,
+            IndexWeights::default(),
+            MiRange::Raw,
+            ZeroMi::Infinity,
+        )
+        .into_iter()
+        .map(|stats| stats.path)
+        .collect::<Vec<_>>();
+
+        // ASSERT
+        assert_eq!(actual, vec!["pkg", "pkg/sub"]);
+    }
+}
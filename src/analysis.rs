@@ -0,0 +1,576 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use chrono::NaiveDate;
+use tracing::info;
+
+use crate::cache::{self, default_cache_dir};
+use crate::commit_type::CommitTypes;
+use crate::coverage::Coverage;
+use crate::discovery::StubFiles;
+use crate::error::{Error, Result};
+use crate::formula::Formula;
+use crate::git::{self, Worktree};
+use crate::history::{ChurnUnit, IgnoreCommits, SinceTag};
+use crate::hotspot::{ChurnWeight, MiRange, MiVariant, TechDebtHotspots, ZeroMi};
+use crate::overrides::Overrides;
+use crate::ownership::ActiveAuthors;
+use crate::plugins::Plugin;
+use crate::severity::SeverityCutoffs;
+
+const CACHE_DIRNAME: &str = "tech_debt_hotspot";
+const HISTORY_SUFFIX: &str = ".history.ndjson";
+const WORKTREES_DIRNAME: &str = "worktrees";
+
+/// Entry point to run an analysis from the library.
+pub struct Analysis;
+
+impl Analysis {
+    pub fn builder() -> AnalysisBuilder {
+        AnalysisBuilder::default()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct AnalysisBuilder {
+    directory: Option<PathBuf>,
+    excludes: Vec<PathBuf>,
+    since: Option<NaiveDate>,
+    since_tag: Option<SinceTag>,
+    churn_unit: ChurnUnit,
+    churn_weight: ChurnWeight,
+    centrality_weight: bool,
+    ignore_commits: Option<IgnoreCommits>,
+    files: Option<Vec<PathBuf>>,
+    cache: bool,
+    cache_dir: Option<PathBuf>,
+    progress: bool,
+    formula: Option<Formula>,
+    coverage: Option<PathBuf>,
+    debt_markers: Option<Vec<String>>,
+    plugins: Vec<PathBuf>,
+    mi_variant: MiVariant,
+    mi_range: MiRange,
+    zero_mi: ZeroMi,
+    revision: Option<String>,
+    follow_symlinks: bool,
+    stubs: StubFiles,
+    default_excludes: Option<bool>,
+    tracked_only: bool,
+    include_untracked: bool,
+    max_file_size: Option<u64>,
+    nan_safe: bool,
+    severity_cutoffs: Option<Overrides<SeverityCutoffs>>,
+    commit_types: Option<CommitTypes>,
+    active_authors: Option<ActiveAuthors>,
+    monthly_changes: bool,
+}
+
+impl AnalysisBuilder {
+    /// The directory to analyse, defaults to the current directory.
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Skip a file or directory, repeatable.
+    pub fn exclude(mut self, exclude: impl Into<PathBuf>) -> Self {
+        self.excludes.push(exclude.into());
+        self
+    }
+
+    /// Only count the changes committed since the given date.
+    pub fn since(mut self, since: NaiveDate) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Count the merges or pull requests changing the files instead of the
+    /// commits, defaults to the commits.
+    pub fn churn_unit(mut self, churn_unit: ChurnUnit) -> Self {
+        self.churn_unit = churn_unit;
+        self
+    }
+
+    /// Compute the hotspot index with the lines added and deleted instead of
+    /// the changes count, ignored with a formula.
+    pub fn churn_weight(mut self, churn_weight: ChurnWeight) -> Self {
+        self.churn_weight = churn_weight;
+        self
+    }
+
+    /// Multiply the hotspot index by the centrality of the module of each
+    /// file in the import graph, ignored with a formula and when streaming.
+    pub fn centrality_weight(mut self, enabled: bool) -> Self {
+        self.centrality_weight = enabled;
+        self
+    }
+
+    /// Leave commits out of the changes count and the ownership, e.g. the
+    /// reformatting of the code.
+    pub fn ignore_commits(mut self, ignore_commits: IgnoreCommits) -> Self {
+        self.ignore_commits = Some(ignore_commits);
+        self
+    }
+
+    /// Only count the changes committed after the tag, e.g. since the last
+    /// release.
+    pub fn since_tag(mut self, since_tag: SinceTag) -> Self {
+        self.since_tag = Some(since_tag);
+        self
+    }
+
+    /// Analyse the given files instead of walking the directory.
+    pub fn files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = Some(files);
+        self
+    }
+
+    /// Cache the metrics and changes count so later runs only parse the
+    /// files which changed, in the cache directory of the user or the `.git`
+    /// directory of the repository without one.
+    ///
+    /// The analyses of a revision are not cached, their worktree being
+    /// removed after each run.
+    pub fn cache(mut self, enabled: bool) -> Self {
+        self.cache = enabled;
+        self
+    }
+
+    /// Store the caches in the given directory instead of the
+    /// [`default_cache_dir`](crate::default_cache_dir), e.g. a directory
+    /// persisted between CI runs.
+    pub fn cache_dir(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(directory.into());
+        self
+    }
+
+    /// Skip the files larger than the given number of bytes.
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Replace the NaN or infinite metrics, e.g. of functions without code,
+    /// with the ones of an empty file.
+    pub fn nan_safe(mut self, enabled: bool) -> Self {
+        self.nan_safe = enabled;
+        self
+    }
+
+    /// Follow the symbolic links inside the directory, skipped by default.
+    pub fn follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    /// Analyse the `.pyi` stub files along the modules, or alone, skipped
+    /// by default.
+    pub fn stubs(mut self, stubs: StubFiles) -> Self {
+        self.stubs = stubs;
+        self
+    }
+
+    /// Skip the virtual environments, dependencies and build outputs of
+    /// `DEFAULT_EXCLUDES`, enabled by default.
+    pub fn default_excludes(mut self, enabled: bool) -> Self {
+        self.default_excludes = Some(enabled);
+        self
+    }
+
+    /// Analyse the files tracked by git instead of walking the directory,
+    /// leaving out the ignored and untracked files.
+    pub fn tracked_only(mut self, enabled: bool) -> Self {
+        self.tracked_only = enabled;
+        self
+    }
+
+    /// Add the untracked files which are not ignored to the tracked ones,
+    /// so new files appear in the report with no changes.
+    pub fn include_untracked(mut self, enabled: bool) -> Self {
+        self.include_untracked = enabled;
+        self
+    }
+
+    /// Bucket the hotspot index into severities with the given cutoffs,
+    /// which can be overridden below some directories relative to the
+    /// analysed directory.
+    pub fn severity_cutoffs(mut self, cutoffs: impl Into<Overrides<SeverityCutoffs>>) -> Self {
+        self.severity_cutoffs = Some(cutoffs.into());
+        self
+    }
+
+    /// Break the changes count down by commit type with the given patterns,
+    /// the conventional commit prefixes by default.
+    pub fn commit_types(mut self, commit_types: CommitTypes) -> Self {
+        self.commit_types = Some(commit_types);
+        self
+    }
+
+    /// Measure the share of the changes made by the authors who are not
+    /// active, unknown by default.
+    pub fn active_authors(mut self, active_authors: ActiveAuthors) -> Self {
+        self.active_authors = Some(active_authors);
+        self
+    }
+
+    /// Count the changes of each of the last 12 months, disabled by
+    /// default.
+    pub fn monthly_changes(mut self, enabled: bool) -> Self {
+        self.monthly_changes = enabled;
+        self
+    }
+
+    /// Show progress bars on stderr, disabled by default.
+    pub fn progress(mut self, enabled: bool) -> Self {
+        self.progress = enabled;
+        self
+    }
+
+    /// Compute the hotspot index with the given formula, see [`Formula`].
+    pub fn formula(mut self, formula: Formula) -> Self {
+        self.formula = Some(formula);
+        self
+    }
+
+    /// Join the line coverage of a coverage.py XML (Cobertura) or lcov
+    /// report, see [`Coverage::load`].
+    pub fn coverage(mut self, report: impl Into<PathBuf>) -> Self {
+        self.coverage = Some(report.into());
+        self
+    }
+
+    /// Count the given markers in the comments instead of the
+    /// [`DEFAULT_DEBT_MARKERS`](crate::DEFAULT_DEBT_MARKERS).
+    pub fn debt_markers(mut self, markers: Vec<String>) -> Self {
+        self.debt_markers = Some(markers);
+        self
+    }
+
+    /// Add the metrics of the WebAssembly plugins to the ones of each file,
+    /// see [`Plugin`].
+    pub fn plugins(mut self, plugins: Vec<PathBuf>) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Compute the maintainability index with the given formula, defaults to
+    /// the one of Visual Studio.
+    pub fn mi_variant(mut self, mi_variant: MiVariant) -> Self {
+        self.mi_variant = mi_variant;
+        self
+    }
+
+    /// Clamp the maintainability index of the rows to 0-100 or rescale it to
+    /// 0-1, defaults to the raw value.
+    pub fn mi_range(mut self, mi_range: MiRange) -> Self {
+        self.mi_range = mi_range;
+        self
+    }
+
+    /// The hotspot index of the files with a maintainability index of 0,
+    /// defaults to infinity.
+    pub fn zero_mi(mut self, zero_mi: ZeroMi) -> Self {
+        self.zero_mi = zero_mi;
+        self
+    }
+
+    /// Analyse the files and history of a git revision instead of the
+    /// working tree, checking it out in a temporary worktree.
+    pub fn revision(mut self, revision: impl Into<String>) -> Self {
+        self.revision = Some(revision.into());
+        self
+    }
+
+    pub fn build(self) -> Result<TechDebtHotspots> {
+        let mut directory = canonicalise(self.directory.unwrap_or_else(|| PathBuf::from(".")))?;
+        let mut excludes = self
+            .excludes
+            .into_iter()
+            .map(canonicalise)
+            .collect::<Result<Vec<_>>>()?;
+        let mut worktree = None;
+
+        if let Some(revision) = &self.revision {
+            let checkout = checkout(&directory, revision)?;
+
+            directory = checkout.relocate(&directory)?;
+            excludes = excludes
+                .iter()
+                .map(|path| checkout.relocate(path))
+                .collect::<Result<_>>()?;
+            worktree = Some(checkout.worktree);
+        }
+
+        let mut hotspots = TechDebtHotspots::new(&directory, None, self.since.as_ref())?
+            .with_progress(self.progress)
+            .with_follow_symlinks(self.follow_symlinks)
+            .with_nan_safe(self.nan_safe)
+            .with_monthly_changes(self.monthly_changes)
+            .with_stubs(self.stubs)
+            .with_default_excludes(self.default_excludes.unwrap_or(true))
+            .with_tracked_only(self.tracked_only)
+            .with_include_untracked(self.include_untracked)
+            .with_churn_unit(self.churn_unit)
+            .with_churn_weight(self.churn_weight)
+            .with_centrality_weight(self.centrality_weight)
+            .with_mi_variant(self.mi_variant)
+            .with_mi_range(self.mi_range)
+            .with_zero_mi(self.zero_mi);
+
+        for exclude in excludes {
+            hotspots = hotspots.with_exclude(exclude);
+        }
+
+        if let Some(since_tag) = self.since_tag {
+            let tag = match since_tag {
+                SinceTag::Tag(tag) => tag,
+                SinceTag::Latest(pattern) => git::latest_tag(&directory, pattern.as_deref())?,
+            };
+            info!("Counting the changes since the tag {tag}");
+            hotspots = hotspots.with_since_tag(tag);
+        }
+
+        if let Some(worktree) = worktree {
+            hotspots = hotspots.with_worktree(worktree);
+        }
+
+        if let Some(files) = self.files {
+            hotspots = hotspots.with_files(files);
+        }
+
+        if let Some(formula) = self.formula {
+            hotspots = hotspots.with_formula(formula);
+        }
+
+        if let Some(bytes) = self.max_file_size {
+            hotspots = hotspots.with_max_file_size(bytes);
+        }
+
+        if let Some(cutoffs) = self.severity_cutoffs {
+            hotspots = hotspots.with_severity_cutoffs(cutoffs);
+        }
+
+        if let Some(commit_types) = self.commit_types {
+            hotspots = hotspots.with_commit_types(commit_types);
+        }
+
+        if let Some(ignore_commits) = self.ignore_commits {
+            hotspots = hotspots.with_ignore_commits(ignore_commits);
+        }
+
+        if let Some(active_authors) = self.active_authors {
+            hotspots = hotspots.with_active_authors(active_authors);
+        }
+
+        if let Some(markers) = self.debt_markers {
+            hotspots = hotspots.with_debt_markers(markers);
+        }
+
+        if !self.plugins.is_empty() {
+            let plugins = self
+                .plugins
+                .iter()
+                .map(|path| Plugin::load(path))
+                .collect::<Result<Vec<_>>>()?;
+            hotspots = hotspots.with_plugins(plugins);
+        }
+
+        if let Some(report) = self.coverage {
+            hotspots = hotspots.with_coverage(Coverage::load(&report)?);
+        }
+
+        if self.cache && self.revision.is_none() {
+            hotspots =
+                hotspots.with_cache(&cache_directory(self.cache_dir.as_deref(), &directory)?);
+        }
+
+        Ok(hotspots)
+    }
+
+    /// Remove the cache of the repository of the directory, returning the
+    /// path of the removed cache if there was one.
+    pub fn clear_cache(self) -> Result<Option<PathBuf>> {
+        let directory = canonicalise(self.directory.clone().unwrap_or_else(|| PathBuf::from(".")))?;
+        let cache = cache_directory(self.cache_dir.as_deref(), &directory)?;
+
+        if !cache.exists() {
+            return Ok(None);
+        }
+
+        fs::remove_dir_all(&cache).map_err(|source| Error::Io {
+            path: cache.clone(),
+            source,
+        })?;
+
+        Ok(Some(cache))
+    }
+
+    /// The default history of the runs recorded with `--record`, next to
+    /// the cache of the repository of the directory so clearing the cache
+    /// keeps it.
+    pub fn history_file(&self) -> Result<PathBuf> {
+        let directory = canonicalise(self.directory.clone().unwrap_or_else(|| PathBuf::from(".")))?;
+        let cache = cache_directory(self.cache_dir.as_deref(), &directory)?;
+        let mut name = cache.file_name().unwrap_or_default().to_os_string();
+        name.push(HISTORY_SUFFIX);
+
+        Ok(cache.with_file_name(name))
+    }
+
+    /// Build the analysis and collect the stats of every file.
+    pub fn run(self) -> Result<TechDebtHotspots> {
+        let mut hotspots = self.build()?;
+        hotspots.collect()?;
+
+        Ok(hotspots)
+    }
+}
+
+/// A worktree of a revision with the root of the repository it was checked
+/// out from.
+struct Checkout {
+    repository: PathBuf,
+    worktree: Worktree,
+}
+
+impl Checkout {
+    /// The path in the worktree matching a path of the repository.
+    fn relocate(&self, path: &Path) -> Result<PathBuf> {
+        let relative =
+            path.strip_prefix(&self.repository)
+                .map_err(|_| Error::OutsideGitRepository {
+                    path: path.to_path_buf(),
+                    git_base_path: self.repository.clone(),
+                })?;
+
+        Ok(self.worktree.path.join(relative))
+    }
+}
+
+fn checkout(directory: &Path, revision: &str) -> Result<Checkout> {
+    let repository = canonicalise(PathBuf::from(
+        git::run(directory, ["rev-parse", "--show-toplevel"])?.trim(),
+    ))?;
+    let name = format!(
+        "{}-{}",
+        revision.replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+        process::id()
+    );
+    let path = git::git_dir(directory)?
+        .join(CACHE_DIRNAME)
+        .join(WORKTREES_DIRNAME)
+        .join(name);
+
+    Ok(Checkout {
+        worktree: Worktree::add(directory, revision, path)?,
+        repository,
+    })
+}
+
+/// The cache of the repository of the directory among the caches, in the
+/// `.git` directory without a cache directory.
+fn cache_directory(cache_dir: Option<&Path>, directory: &Path) -> Result<PathBuf> {
+    let git_dir = git::git_dir(directory)?;
+
+    Ok(
+        match cache_dir.map(Path::to_path_buf).or_else(default_cache_dir) {
+            Some(cache_dir) => cache::repository_dir(&cache_dir, &git_dir),
+            None => git_dir.join(CACHE_DIRNAME),
+        },
+    )
+}
+
+fn canonicalise(path: PathBuf) -> Result<PathBuf> {
+    path.canonicalize()
+        .map_err(|source| Error::Io { path, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn git(directory: &Path, args: &[&str]) {
+        Command::new("git")
+            .current_dir(directory)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_run() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path();
+        fs::create_dir(directory.join("vendored")).unwrap();
+        fs::write(directory.join("a.py"), "def foo():\n    return 1\n").unwrap();
+        fs::write(directory.join("vendored/b.py"), "import os\n").unwrap();
+        git(directory, &["init"]);
+        git(directory, &["add", "--all"]);
+        git(directory, &["commit", "-m", "Commit"]);
+
+        // ACT
+        let hotspots = Analysis::builder()
+            .directory(directory)
+            .exclude(directory.join("vendored"))
+            .run()
+            .unwrap();
+
+        // ASSERT
+        let actual = hotspots
+            .stats()
+            .into_iter()
+            .map(|stats| (stats.path, stats.changes_count))
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, [("a.py".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_build_missing_directory() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("missing");
+
+        // ACT
+        let actual = Analysis::builder().directory(&directory).build();
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::Io { path, .. }) if path == directory));
+    }
+
+    #[test]
+    fn test_run_revision() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path();
+        git(directory, &["init"]);
+        fs::write(directory.join("a.py"), "x = 1\n").unwrap();
+        git(directory, &["add", "--all"]);
+        git(directory, &["commit", "-m", "First"]);
+        fs::write(directory.join("b.py"), "y = 1\n").unwrap();
+        git(directory, &["add", "--all"]);
+        git(directory, &["commit", "-m", "Second"]);
+
+        // ACT
+        let hotspots = Analysis::builder()
+            .directory(directory)
+            .revision("HEAD~1")
+            .run()
+            .unwrap();
+
+        // ASSERT
+        let actual = hotspots
+            .stats()
+            .into_iter()
+            .map(|stats| (stats.path, stats.changes_count))
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, [("a.py".to_string(), 1)]);
+    }
+}
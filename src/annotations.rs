@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use rustpython_parser::ast::{self, Stmt};
+use rustpython_parser::source_code::LineIndex;
+
+/// Whether the signature of each function is fully type annotated, by the
+/// line of its `def`.
+///
+/// A signature is annotated when all its parameters and its return type are,
+/// except the `self` or `cls` parameter of methods and the return type of
+/// `__init__`, which type checkers infer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Annotations {
+    pub functions: HashMap<usize, bool>,
+}
+
+impl Annotations {
+    pub fn new(suite: &[Stmt], source_code: &str) -> Annotations {
+        let mut visitor = Visitor {
+            source_code,
+            line_index: LineIndex::from_source_text(source_code),
+            annotations: Annotations::default(),
+        };
+
+        visitor.body(suite, false);
+
+        visitor.annotations
+    }
+
+    /// Percentage of the functions with an annotated signature, `None`
+    /// without functions.
+    pub fn percentage(&self) -> Option<f64> {
+        let annotated = self.functions.values().filter(|annotated| **annotated);
+
+        match self.functions.len() {
+            0 => None,
+            total => Some(annotated.count() as f64 / total as f64 * 100.0),
+        }
+    }
+}
+
+struct Visitor<'a> {
+    source_code: &'a str,
+    line_index: LineIndex,
+    annotations: Annotations,
+}
+
+impl Visitor<'_> {
+    fn body(&mut self, body: &[Stmt], in_class: bool) {
+        for stmt in body {
+            self.stmt(stmt, in_class);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, in_class: bool) {
+        match stmt {
+            Stmt::FunctionDef(ast::StmtFunctionDef {
+                range,
+                name,
+                args,
+                returns,
+                body,
+                ..
+            })
+            | Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef {
+                range,
+                name,
+                args,
+                returns,
+                body,
+                ..
+            }) => {
+                let line = self
+                    .line_index
+                    .source_location(range.start(), self.source_code)
+                    .row
+                    .to_usize();
+                let annotated = is_annotated(name.as_str(), args, returns.is_some(), in_class);
+
+                self.annotations.functions.insert(line, annotated);
+                self.body(body, false);
+            }
+            Stmt::ClassDef(ast::StmtClassDef { body, .. }) => self.body(body, true),
+            Stmt::If(ast::StmtIf { body, orelse, .. })
+            | Stmt::For(ast::StmtFor { body, orelse, .. })
+            | Stmt::AsyncFor(ast::StmtAsyncFor { body, orelse, .. })
+            | Stmt::While(ast::StmtWhile { body, orelse, .. }) => {
+                self.body(body, in_class);
+                self.body(orelse, in_class);
+            }
+            Stmt::With(ast::StmtWith { body, .. })
+            | Stmt::AsyncWith(ast::StmtAsyncWith { body, .. }) => self.body(body, in_class),
+            Stmt::Try(ast::StmtTry {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            })
+            | Stmt::TryStar(ast::StmtTryStar {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            }) => {
+                self.body(body, in_class);
+                for ast::ExceptHandler::ExceptHandler(handler) in handlers {
+                    self.body(&handler.body, in_class);
+                }
+                self.body(orelse, in_class);
+                self.body(finalbody, in_class);
+            }
+            Stmt::Match(ast::StmtMatch { cases, .. }) => {
+                for case in cases {
+                    self.body(&case.body, in_class);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_annotated(name: &str, args: &ast::Arguments, returns: bool, method: bool) -> bool {
+    let positional = args.posonlyargs.iter().chain(&args.args);
+    let skipped = match positional.clone().next() {
+        Some(first) if method && matches!(first.def.arg.as_str(), "self" | "cls") => 1,
+        _ => 0,
+    };
+
+    let parameters_annotated = positional
+        .skip(skipped)
+        .chain(&args.kwonlyargs)
+        .map(|arg| &arg.def)
+        .chain(args.vararg.as_deref())
+        .chain(args.kwarg.as_deref())
+        .all(|arg| arg.annotation.is_some());
+
+    parameters_annotated && (returns || name == "__init__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustpython_parser::Parse;
+
+    #[test]
+    fn test_annotations_new() {
+        // ARRANGE
+        let source_code = "def f(a: int, *args: str, b: int = 1, **kwargs: str) -> int:
+    def g(a):
+        return a
+    return a
+
+
+class C:
+    def __init__(self, a: int):
+        self.a = a
+
+    @classmethod
+    def create(cls) -> 'C':
+        return cls(1)
+
+    def m(self, b) -> int:
+        return b
+
+
+def h(self) -> None:
+    pass
+";
+        let suite = ast::Suite::parse(source_code, "module.py").unwrap();
+
+        // ACT
+        let actual = Annotations::new(&suite, source_code);
+
+        // ASSERT
+        let expected = HashMap::from([
+            (1, true),
+            (2, false),
+            (8, true),
+            (12, true),
+            (15, false),
+            (19, false),
+        ]);
+
+        assert_eq!(actual.functions, expected);
+        assert_eq!(actual.percentage(), Some(50.0));
+    }
+
+    #[test]
+    fn test_annotations_without_functions() {
+        // ARRANGE
+        let suite = ast::Suite::parse("x: int = 1\n", "module.py").unwrap();
+
+        // ACT
+        let actual = Annotations::new(&suite, "x: int = 1\n");
+
+        // ASSERT
+        assert_eq!(actual.percentage(), None);
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use xxhash_rust::xxh3::{xxh3_64, xxh3_64_with_seed};
+
+use crate::commit_type::Churn;
+use crate::error::{Error, Result};
+use crate::hotspot::{FileStats, MiVariant};
+
+/// Directory of the caches of every repository in the cache directory of
+/// the user.
+const CACHE_DIRNAME: &str = "tech_debt_hotspot";
+const METRICS_FILENAME: &str = "metrics.json";
+const CHURN_FILENAME: &str = "churn.json";
+
+#[derive(Serialize, Deserialize)]
+struct CachedMetrics {
+    hash: u64,
+    stats: FileStats,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedChurn {
+    key: String,
+    churn: HashMap<PathBuf, Churn>,
+}
+
+/// On-disk cache of the per-file metrics, keyed by the hash of the file
+/// content, and of the changes count, keyed by `HEAD`, the `--since` window
+/// and the commit types.
+///
+/// A cache which cannot be read, including one written by a version of the
+/// tool with different metrics, is discarded rather than reported as an error.
+#[derive(Default)]
+pub(crate) struct Cache {
+    directory: PathBuf,
+    metrics: HashMap<PathBuf, CachedMetrics>,
+    churn: Option<CachedChurn>,
+}
+
+impl Cache {
+    pub fn load(directory: &Path) -> Self {
+        Cache {
+            directory: directory.to_path_buf(),
+            metrics: read_json(&directory.join(METRICS_FILENAME)).unwrap_or_default(),
+            churn: read_json(&directory.join(CHURN_FILENAME)),
+        }
+    }
+
+    /// Hash of the content of a file seeded with the maintainability index
+    /// variant and whether the NaN or infinite metrics are replaced, the
+    /// metrics depending on all of them.
+    pub fn hash(source_code: &[u8], mi_variant: MiVariant, nan_safe: bool) -> u64 {
+        xxh3_64_with_seed(source_code, mi_variant as u64 | (nan_safe as u64) << 8)
+    }
+
+    pub fn metrics(&self, path: &Path, hash: u64) -> Option<&FileStats> {
+        self.metrics
+            .get(path)
+            .filter(|cached| cached.hash == hash)
+            .map(|cached| &cached.stats)
+    }
+
+    pub fn store_metrics(&mut self, path: &Path, hash: u64, stats: FileStats) {
+        self.metrics
+            .insert(path.to_path_buf(), CachedMetrics { hash, stats });
+    }
+
+    pub fn churn(&self, key: &str) -> Option<&HashMap<PathBuf, Churn>> {
+        self.churn
+            .as_ref()
+            .filter(|cached| cached.key == key)
+            .map(|cached| &cached.churn)
+    }
+
+    pub fn store_churn(&mut self, key: String, churn: HashMap<PathBuf, Churn>) {
+        self.churn = Some(CachedChurn { key, churn });
+    }
+
+    /// Drop the metrics of the files which were not seen during this run.
+    pub fn retain_paths(&mut self, paths: &[PathBuf]) {
+        let paths: HashSet<&PathBuf> = paths.iter().collect();
+        self.metrics.retain(|path, _| paths.contains(path));
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.directory).map_err(|source| Error::Io {
+            path: self.directory.clone(),
+            source,
+        })?;
+
+        write_json(&self.directory.join(METRICS_FILENAME), &self.metrics)?;
+
+        if let Some(churn) = &self.churn {
+            write_json(&self.directory.join(CHURN_FILENAME), churn)?;
+        }
+
+        debug!("Saved cache to {}", self.directory.display());
+
+        Ok(())
+    }
+}
+
+/// The directory of the caches of every repository, `tech_debt_hotspot` in
+/// the cache directory of the user, e.g. `$XDG_CACHE_HOME` or `~/.cache` on
+/// Linux. None without a home directory.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|directory| directory.join(CACHE_DIRNAME))
+}
+
+/// The directory of the cache of a repository among the caches, named after
+/// the repository and told apart from the ones with the same name by the
+/// hash of its `.git` directory.
+pub(crate) fn repository_dir(cache_dir: &Path, git_dir: &Path) -> PathBuf {
+    let name = git_dir
+        .parent()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let hash = xxh3_64(git_dir.as_os_str().as_encoded_bytes());
+
+    cache_dir.join(format!("{name}-{hash:016x}"))
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let content = fs::read(path).ok()?;
+
+    match serde_json::from_slice(&content) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!(path = %path.display(), reason = %error, "Discarding the cache");
+            None
+        }
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let content = serde_json::to_vec(value).map_err(|error| Error::Io {
+        path: path.to_path_buf(),
+        source: error.into(),
+    })?;
+
+    fs::write(path, content).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_cache_roundtrip() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let path = PathBuf::from("/repo/module.py");
+        let stats = FileStats {
+            path: path.clone(),
+            halstead_volume: 190.19550008653874,
+            loc: 14,
+            ..Default::default()
+        };
+
+        let churn = Churn {
+            changes_count: 3,
+            fix: 2,
+            ..Default::default()
+        };
+
+        let mut cache = Cache::load(temp_dir.path());
+        cache.store_metrics(&path, 42, stats.clone());
+        cache.store_churn(
+            "head".to_string(),
+            HashMap::from([(path.clone(), churn.clone())]),
+        );
+        cache.save().unwrap();
+
+        // ACT
+        let actual = Cache::load(temp_dir.path());
+
+        // ASSERT
+        assert_eq!(actual.metrics(&path, 42), Some(&stats));
+        assert_eq!(actual.metrics(&path, 43), None);
+        assert_eq!(actual.churn("head").unwrap().get(&path), Some(&churn));
+        assert_eq!(actual.churn("other"), None);
+    }
+
+    #[test]
+    fn test_repository_dir() {
+        // ACT
+        let actual = repository_dir(Path::new("/cache"), Path::new("/src/api/.git"));
+        let other = repository_dir(Path::new("/cache"), Path::new("/work/api/.git"));
+
+        // ASSERT
+        assert!(actual.starts_with("/cache"));
+        assert!(actual
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("api-"));
+        assert_ne!(actual, other);
+    }
+}
@@ -0,0 +1,358 @@
+//! The rows of a report as an Arrow record batch, written to the columnar
+//! formats.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::builder::{ListBuilder, StringBuilder, UInt32Builder};
+use arrow_array::types::Date32Type;
+use arrow_array::{
+    ArrayRef, Date32Array, Float64Array, Int64Array, RecordBatch, StringArray, UInt32Array,
+    UInt64Array,
+};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use clap::ValueEnum;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+
+use crate::columns::Column;
+use crate::hotspot::HotspotStats;
+
+/// The rows with every column, whatever the columns of the tabular outputs,
+/// so the schema is the same across runs, followed by a column per metric of
+/// the plugins.
+pub(crate) fn record_batch(
+    stats: &[HotspotStats],
+    metadata: HashMap<String, String>,
+) -> Result<RecordBatch, ArrowError> {
+    let columns = columns(stats);
+    let fields = columns
+        .iter()
+        .map(|column| Field::new(column.name(), data_type(column), is_nullable(column)))
+        .collect::<Vec<_>>();
+    let arrays = columns
+        .iter()
+        .map(|column| array(column, stats))
+        .collect::<Vec<_>>();
+
+    RecordBatch::try_new(
+        Arc::new(Schema::new(fields).with_metadata(metadata)),
+        arrays,
+    )
+}
+
+/// Every column followed by a column per metric of the plugins of the rows.
+pub(crate) fn columns(stats: &[HotspotStats]) -> Vec<Column> {
+    let plugins = stats
+        .iter()
+        .flat_map(|stat| stat.plugins.keys())
+        .collect::<BTreeSet<_>>();
+
+    Column::value_variants()
+        .iter()
+        .copied()
+        .chain(plugins.into_iter().filter_map(|name| Column::plugin(name)))
+        .collect()
+}
+
+/// Write the rows as a Parquet file compressed with Snappy, the metadata
+/// being stored as key-value metadata of the file.
+pub(crate) fn write_parquet<W: Write + Send>(
+    writer: W,
+    stats: &[HotspotStats],
+    metadata: HashMap<String, String>,
+) -> Result<(), ParquetError> {
+    let key_values = metadata
+        .iter()
+        .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+        .collect();
+    let batch = record_batch(stats, metadata)?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(key_values))
+        .build();
+
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
+
+/// Write the rows as an Arrow IPC file, readable as a Feather file, the
+/// metadata being stored as metadata of the schema.
+pub(crate) fn write_arrow<W: Write>(
+    writer: W,
+    stats: &[HotspotStats],
+    metadata: HashMap<String, String>,
+) -> Result<(), ArrowError> {
+    let batch = record_batch(stats, metadata)?;
+
+    let mut writer = FileWriter::try_new(writer, &batch.schema())?;
+    writer.write(&batch)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+pub(crate) fn data_type(column: &Column) -> DataType {
+    match column {
+        Column::Path
+        | Column::PathType
+        | Column::Function
+        | Column::LastAuthor
+        | Column::PrimaryOwner
+        | Column::Severity => DataType::Utf8,
+        Column::StartLine | Column::EndLine => DataType::UInt64,
+        Column::RankDelta => DataType::Int64,
+        Column::FirstCommit => DataType::Date32,
+        Column::DuplicationPartners => {
+            DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true)))
+        }
+        Column::MonthlyChanges => {
+            DataType::List(Arc::new(Field::new_list_field(DataType::UInt32, true)))
+        }
+        column if is_count(column) => DataType::UInt32,
+        _ => DataType::Float64,
+    }
+}
+
+/// Whether the column may be unknown for a row, as the optional fields of
+/// the NDJSON rows.
+fn is_nullable(column: &Column) -> bool {
+    matches!(
+        column,
+        Column::Function
+            | Column::StartLine
+            | Column::EndLine
+            | Column::TypeAnnotations
+            | Column::TestRatio
+            | Column::TestLoc
+            | Column::FanIn
+            | Column::FanOut
+            | Column::Centrality
+            | Column::KnowledgeLoss
+            | Column::MonthlyChanges
+            | Column::FirstCommit
+            | Column::LastAuthor
+            | Column::PrimaryOwner
+            | Column::Coverage
+            | Column::Risk
+            | Column::RankDelta
+            | Column::HotspotIndexDelta
+            | Column::Plugin(_)
+    )
+}
+
+fn is_count(column: &Column) -> bool {
+    matches!(
+        column,
+        Column::MaxNesting
+            | Column::Nom
+            | Column::LongestFunction
+            | Column::LinesOfCode
+            | Column::DebtComments
+            | Column::TestLoc
+            | Column::FanIn
+            | Column::FanOut
+            | Column::ChangesCount
+            | Column::Changes30d
+            | Column::Changes90d
+            | Column::Changes365d
+            | Column::LinesChanged
+            | Column::FeatureChanges
+            | Column::FixChanges
+            | Column::RefactorChanges
+            | Column::ChoreChanges
+    )
+}
+
+fn array(column: &Column, stats: &[HotspotStats]) -> ArrayRef {
+    match column {
+        Column::Path | Column::PathType | Column::Severity => Arc::new(
+            stats
+                .iter()
+                .map(|stat| Some(column.value(stat)))
+                .collect::<StringArray>(),
+        ),
+        Column::Function => Arc::new(
+            stats
+                .iter()
+                .map(|stat| stat.function.as_deref())
+                .collect::<StringArray>(),
+        ),
+        Column::LastAuthor => Arc::new(
+            stats
+                .iter()
+                .map(|stat| stat.last_author.as_deref())
+                .collect::<StringArray>(),
+        ),
+        Column::PrimaryOwner => Arc::new(
+            stats
+                .iter()
+                .map(|stat| stat.primary_owner.is_some().then(|| column.value(stat)))
+                .collect::<StringArray>(),
+        ),
+        Column::StartLine => Arc::new(
+            stats
+                .iter()
+                .map(|stat| stat.start_line.map(|line| line as u64))
+                .collect::<UInt64Array>(),
+        ),
+        Column::EndLine => Arc::new(
+            stats
+                .iter()
+                .map(|stat| stat.end_line.map(|line| line as u64))
+                .collect::<UInt64Array>(),
+        ),
+        Column::RankDelta => Arc::new(
+            stats
+                .iter()
+                .map(|stat| stat.rank_delta)
+                .collect::<Int64Array>(),
+        ),
+        Column::FirstCommit => Arc::new(
+            stats
+                .iter()
+                .map(|stat| stat.first_commit.map(Date32Type::from_naive_date))
+                .collect::<Date32Array>(),
+        ),
+        Column::DuplicationPartners => {
+            let mut builder = ListBuilder::new(StringBuilder::new());
+            for stat in stats {
+                builder.append_value(stat.duplication_partners.iter().map(Some));
+            }
+
+            Arc::new(builder.finish())
+        }
+        // Oldest month first, as in the tabular outputs
+        Column::MonthlyChanges => {
+            let mut builder = ListBuilder::new(UInt32Builder::new());
+            for stat in stats {
+                builder.append_option(
+                    stat.monthly_changes
+                        .as_ref()
+                        .map(|monthly_changes| monthly_changes.values().copied().map(Some)),
+                );
+            }
+
+            Arc::new(builder.finish())
+        }
+        // The counts are integers converted without loss to and from `f64`
+        column if is_count(column) => Arc::new(
+            stats
+                .iter()
+                .map(|stat| column.metric(stat).map(|value| value as u32))
+                .collect::<UInt32Array>(),
+        ),
+        column => Arc::new(
+            stats
+                .iter()
+                .map(|stat| column.metric(stat))
+                .collect::<Float64Array>(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::severity::Severity;
+    use arrow_array::Array;
+    use arrow_ipc::reader::FileReader;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::io::Cursor;
+    use tempfile::tempfile;
+
+    #[test]
+    fn test_write_parquet() {
+        // ARRANGE
+        let stats = vec![
+            HotspotStats {
+                path: "a.py".to_string(),
+                loc: 10,
+                maintainability_index: 50.0,
+                duplication_partners: vec!["b.py".to_string()],
+                plugins: [("sql_strings".to_string(), 3.0)].into(),
+                ..Default::default()
+            },
+            HotspotStats {
+                path: "b.py".to_string(),
+                function: Some("foo".to_string()),
+                start_line: Some(1),
+                end_line: Some(2),
+                ..Default::default()
+            },
+        ];
+        let metadata = HashMap::from([("commit".to_string(), "abc".to_string())]);
+        let file = tempfile().unwrap();
+
+        // ACT
+        write_parquet(file.try_clone().unwrap(), &stats, metadata).unwrap();
+
+        // ASSERT
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        let key_values = reader
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .unwrap()
+            .clone();
+        let batch = reader.build().unwrap().next().unwrap().unwrap();
+
+        let loc = batch
+            .column_by_name("loc")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .unwrap();
+        let sql_strings = batch
+            .column_by_name("plugin.sql_strings")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), Column::value_variants().len() + 1);
+        assert_eq!(loc.value(0), 10);
+        assert_eq!(sql_strings.value(0), 3.0);
+        assert!(sql_strings.is_null(1));
+        assert!(key_values.contains(&KeyValue::new("commit".to_string(), "abc".to_string())));
+    }
+
+    #[test]
+    fn test_write_arrow() {
+        // ARRANGE
+        let stats = vec![HotspotStats {
+            path: "a.py".to_string(),
+            severity: Severity::High,
+            ..Default::default()
+        }];
+        let metadata = HashMap::from([("commit".to_string(), "abc".to_string())]);
+        let mut buffer = vec![];
+
+        // ACT
+        write_arrow(&mut buffer, &stats, metadata).unwrap();
+
+        // ASSERT
+        let reader = FileReader::try_new(Cursor::new(buffer), None).unwrap();
+        let schema = reader.schema();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        let severity = batches[0]
+            .column_by_name("severity")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(severity.value(0), "high");
+        assert_eq!(schema.metadata()["commit"], "abc");
+    }
+}
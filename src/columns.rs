@@ -0,0 +1,721 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+
+use crate::hotspot::HotspotStats;
+use crate::plugins;
+
+const PERCENTILE_SUFFIX: &str = "_pctl";
+const Z_SCORE_SUFFIX: &str = "_zscore";
+/// Prefix of the names of the metrics of the plugins, e.g. `plugin.sql_strings`.
+pub(crate) const PLUGIN_PREFIX: &str = "plugin.";
+
+/// A column of the tabular outputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    Path,
+    PathType,
+    Function,
+    StartLine,
+    EndLine,
+    HalsteadVolume,
+    CyclomaticComplexity,
+    CyclomaticAverage,
+    CyclomaticTotal,
+    MaxNesting,
+    Nom,
+    LongestFunction,
+    LinesOfCode,
+    CommentsPercentage,
+    Duplication,
+    DuplicationPartners,
+    DebtComments,
+    TypeAnnotations,
+    TestRatio,
+    TestLoc,
+    FanIn,
+    FanOut,
+    Centrality,
+    MaintainabilityIndex,
+    ChangesCount,
+    Changes30d,
+    Changes90d,
+    Changes365d,
+    LinesChanged,
+    FeatureChanges,
+    FixChanges,
+    RefactorChanges,
+    ChoreChanges,
+    AuthorEntropy,
+    KnowledgeLoss,
+    MonthlyChanges,
+    FirstCommit,
+    LastAuthor,
+    PrimaryOwner,
+    HotspotIndex,
+    Severity,
+    Coverage,
+    Risk,
+    RankDelta,
+    HotspotIndexDelta,
+    /// Percentile rank of a metric among the rows of the report, named
+    /// after the metric with a `_pctl` suffix.
+    Percentile(&'static Column),
+    /// Standard score of a metric among the rows of the report, named after
+    /// the metric with a `_zscore` suffix.
+    ZScore(&'static Column),
+    /// A metric of a plugin, named after it with a `plugin.` prefix.
+    Plugin(&'static str),
+}
+
+impl Column {
+    /// The columns shown when reporting files.
+    pub const FILE_DEFAULTS: &'static [Column] = &[
+        Column::Path,
+        Column::PathType,
+        Column::HalsteadVolume,
+        Column::CyclomaticComplexity,
+        Column::MaxNesting,
+        Column::Nom,
+        Column::LongestFunction,
+        Column::LinesOfCode,
+        Column::CommentsPercentage,
+        Column::Duplication,
+        Column::DebtComments,
+        Column::MaintainabilityIndex,
+        Column::ChangesCount,
+        Column::HotspotIndex,
+    ];
+
+    /// The columns shown when reporting functions.
+    pub const FUNCTION_DEFAULTS: &'static [Column] = &[
+        Column::Path,
+        Column::PathType,
+        Column::Function,
+        Column::StartLine,
+        Column::EndLine,
+        Column::HalsteadVolume,
+        Column::CyclomaticComplexity,
+        Column::MaxNesting,
+        Column::LinesOfCode,
+        Column::CommentsPercentage,
+        Column::Duplication,
+        Column::DebtComments,
+        Column::MaintainabilityIndex,
+        Column::ChangesCount,
+        Column::HotspotIndex,
+    ];
+
+    /// The column of a metric of a plugin from its name without the prefix.
+    ///
+    /// The name is leaked so the column stays `Copy` like the built-in ones,
+    /// the columns being parsed once from the command line.
+    pub fn plugin(name: &str) -> Option<Column> {
+        plugins::is_metric_name(name).then(|| Column::Plugin(Box::leak(name.into())))
+    }
+
+    /// The column of a plugin metric name with its prefix, `None` without
+    /// the prefix.
+    pub(crate) fn from_plugin_name(name: &str) -> Result<Option<Column>, String> {
+        match name.strip_prefix(PLUGIN_PREFIX) {
+            Some(metric) => Column::plugin(metric)
+                .map(Some)
+                .ok_or_else(|| format!("invalid plugin metric `{name}`")),
+            None => Ok(None),
+        }
+    }
+
+    pub fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(match self {
+            Column::Plugin(name) => return Cow::Owned(format!("{PLUGIN_PREFIX}{name}")),
+            Column::Percentile(column) => {
+                return Cow::Owned(format!("{}{PERCENTILE_SUFFIX}", column.name()))
+            }
+            Column::ZScore(column) => {
+                return Cow::Owned(format!("{}{Z_SCORE_SUFFIX}", column.name()))
+            }
+            Column::Path => "path",
+            Column::PathType => "path_type",
+            Column::Function => "function",
+            Column::StartLine => "start_line",
+            Column::EndLine => "end_line",
+            Column::HalsteadVolume => "halstead_volume",
+            Column::CyclomaticComplexity => "cyclomatic_complexity",
+            Column::CyclomaticAverage => "cyclomatic_average",
+            Column::CyclomaticTotal => "cyclomatic_total",
+            Column::MaxNesting => "max_nesting",
+            Column::Nom => "nom",
+            Column::LongestFunction => "longest_function",
+            Column::LinesOfCode => "loc",
+            Column::CommentsPercentage => "comments_percentage",
+            Column::Duplication => "duplication",
+            Column::DuplicationPartners => "duplication_partners",
+            Column::DebtComments => "debt_comments",
+            Column::TypeAnnotations => "type_annotations",
+            Column::TestRatio => "test_ratio",
+            Column::TestLoc => "test_loc",
+            Column::FanIn => "fan_in",
+            Column::FanOut => "fan_out",
+            Column::Centrality => "centrality",
+            Column::MaintainabilityIndex => "maintainability_index",
+            Column::ChangesCount => "changes_count",
+            Column::Changes30d => "changes_30d",
+            Column::Changes90d => "changes_90d",
+            Column::Changes365d => "changes_365d",
+            Column::LinesChanged => "lines_changed",
+            Column::FeatureChanges => "feature_changes",
+            Column::FixChanges => "fix_changes",
+            Column::RefactorChanges => "refactor_changes",
+            Column::ChoreChanges => "chore_changes",
+            Column::AuthorEntropy => "author_entropy",
+            Column::KnowledgeLoss => "knowledge_loss",
+            Column::MonthlyChanges => "monthly_changes",
+            Column::FirstCommit => "first_commit",
+            Column::LastAuthor => "last_author",
+            Column::PrimaryOwner => "primary_owner",
+            Column::HotspotIndex => "hotspot_index",
+            Column::Severity => "severity",
+            Column::Coverage => "coverage",
+            Column::Risk => "risk",
+            Column::RankDelta => "rank_delta",
+            Column::HotspotIndexDelta => "hotspot_index_delta",
+        })
+    }
+
+    /// What the column holds, as shown in the help.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Column::Percentile(_) => "Percentile rank of the metric among the rows of the report",
+            Column::ZScore(_) => "Standard score of the metric among the rows of the report",
+            Column::Plugin(_) => "Metric computed by a plugin",
+            Column::Path => "Path of the file or directory",
+            Column::PathType => "Whether the path is a file or a directory",
+            Column::Function => "Name of the function",
+            Column::StartLine => "First line of the function",
+            Column::EndLine => "Last line of the function",
+            Column::HalsteadVolume => "Halstead volume",
+            Column::CyclomaticComplexity => "Cyclomatic complexity",
+            Column::CyclomaticAverage => "Average cyclomatic complexity of the functions of a file",
+            Column::CyclomaticTotal => "Total cyclomatic complexity of the functions of a file",
+            Column::MaxNesting => "Maximum nesting depth",
+            Column::Nom => "Number of functions",
+            Column::LongestFunction => "Length in lines of the longest function",
+            Column::LinesOfCode => "Lines of code",
+            Column::CommentsPercentage => "Comments percentage",
+            Column::Duplication => "Percentage of the lines of code duplicated",
+            Column::DuplicationPartners => "Other files sharing duplicated code",
+            Column::DebtComments => "Number of TODO, FIXME, HACK or XXX markers in the comments",
+            Column::TypeAnnotations => {
+                "Percentage of the functions with a type annotated signature"
+            }
+            Column::TestRatio => {
+                "Lines of code of the tests over the other lines of code of a directory"
+            }
+            Column::TestLoc => "Lines of code of the tests named after the module of a file",
+            Column::FanIn => "Number of analysed modules importing the module of a file",
+            Column::FanOut => "Number of analysed modules imported by the module of a file",
+            Column::Centrality => {
+                "PageRank of the module of a file in the import graph, 1 on average"
+            }
+            Column::MaintainabilityIndex => "Maintainability index",
+            Column::ChangesCount => "Changes count",
+            Column::Changes30d => "Changes in the last 30 days",
+            Column::Changes90d => "Changes in the last 90 days",
+            Column::Changes365d => "Changes in the last 365 days",
+            Column::LinesChanged => "Lines added and deleted by the changes",
+            Column::FeatureChanges => "Changes by feature commits",
+            Column::FixChanges => "Changes by fix commits",
+            Column::RefactorChanges => "Changes by refactor commits",
+            Column::ChoreChanges => "Changes by chore commits",
+            Column::AuthorEntropy => "Entropy in bits of the changes across the authors",
+            Column::KnowledgeLoss => {
+                "Percentage of the changes made by the authors no longer active"
+            }
+            Column::MonthlyChanges => {
+                "Changes count of each of the last 12 months, oldest first, requires --monthly-changes"
+            }
+            Column::FirstCommit => "Day the file was first committed",
+            Column::LastAuthor => "Author of the most recent change",
+            Column::PrimaryOwner => "Author of the most changes with their share of the changes",
+            Column::HotspotIndex => "Hotspot index",
+            Column::Severity => "Bucket of the hotspot index: low, medium, high or critical",
+            Column::Coverage => "Percentage of the lines covered by the tests",
+            Column::Risk => "Changes count times cyclomatic complexity times the uncovered share",
+            Column::RankDelta => {
+                "Places moved up the ranking by hotspot index since the --baseline"
+            }
+            Column::HotspotIndexDelta => "Change of the hotspot index since the --baseline",
+        }
+    }
+
+    /// Whether the values of the column depend on the other rows of the report.
+    pub fn is_normalized(&self) -> bool {
+        matches!(self, Column::Percentile(_) | Column::ZScore(_))
+    }
+
+    /// Whether the column is a metric of the file or function, rather than
+    /// identifying it.
+    pub fn is_metric(&self) -> bool {
+        !matches!(
+            self,
+            Column::Path
+                | Column::PathType
+                | Column::Function
+                | Column::StartLine
+                | Column::EndLine
+                | Column::DuplicationPartners
+                | Column::MonthlyChanges
+                | Column::FirstCommit
+                | Column::LastAuthor
+                | Column::PrimaryOwner
+                | Column::Severity
+                | Column::Percentile(_)
+                | Column::ZScore(_)
+        )
+    }
+
+    /// The value of a metric column, `None` for the other columns, the
+    /// normalized metrics and the metrics which are not known for the row.
+    pub fn metric(&self, stat: &HotspotStats) -> Option<f64> {
+        Some(match self {
+            Column::Path
+            | Column::PathType
+            | Column::Function
+            | Column::StartLine
+            | Column::EndLine
+            | Column::DuplicationPartners
+            | Column::MonthlyChanges
+            | Column::FirstCommit
+            | Column::LastAuthor
+            | Column::PrimaryOwner
+            | Column::Severity
+            | Column::Percentile(_)
+            | Column::ZScore(_) => return None,
+            Column::HalsteadVolume => stat.halstead_volume,
+            Column::CyclomaticComplexity => stat.cyclomatic_complexity,
+            Column::CyclomaticAverage => stat.cyclomatic_average,
+            Column::CyclomaticTotal => stat.cyclomatic_total,
+            Column::MaxNesting => stat.max_nesting as f64,
+            Column::Nom => stat.nom as f64,
+            Column::LongestFunction => stat.longest_function as f64,
+            Column::LinesOfCode => stat.loc as f64,
+            Column::CommentsPercentage => stat.comments_percentage,
+            Column::Duplication => stat.duplication,
+            Column::DebtComments => stat.debt_comments as f64,
+            Column::TypeAnnotations => return stat.type_annotations,
+            Column::TestRatio => return stat.test_ratio,
+            Column::TestLoc => return stat.test_loc.map(f64::from),
+            Column::FanIn => return stat.fan_in.map(f64::from),
+            Column::FanOut => return stat.fan_out.map(f64::from),
+            Column::Centrality => return stat.centrality,
+            Column::MaintainabilityIndex => stat.maintainability_index,
+            Column::ChangesCount => stat.changes_count as f64,
+            Column::Changes30d => stat.changes_30d as f64,
+            Column::Changes90d => stat.changes_90d as f64,
+            Column::Changes365d => stat.changes_365d as f64,
+            Column::LinesChanged => stat.lines_changed as f64,
+            Column::FeatureChanges => stat.feature_changes as f64,
+            Column::FixChanges => stat.fix_changes as f64,
+            Column::RefactorChanges => stat.refactor_changes as f64,
+            Column::ChoreChanges => stat.chore_changes as f64,
+            Column::AuthorEntropy => stat.author_entropy,
+            Column::KnowledgeLoss => return stat.knowledge_loss,
+            Column::HotspotIndex => stat.hotspot_index,
+            Column::Coverage => return stat.coverage,
+            Column::Risk => return stat.risk,
+            Column::RankDelta => return stat.rank_delta.map(|delta| delta as f64),
+            Column::HotspotIndexDelta => return stat.hotspot_index_delta,
+            Column::Plugin(name) => return stat.plugins.get(*name).copied(),
+        })
+    }
+
+    /// The value of the column rendered as text, empty when not applicable
+    /// or when the column is normalized.
+    pub fn value(&self, stat: &HotspotStats) -> String {
+        match self {
+            Column::Percentile(_) | Column::ZScore(_) => String::new(),
+            Column::Path => stat.path.clone(),
+            Column::PathType => stat.path_type.name().to_string(),
+            Column::Function => stat.function.clone().unwrap_or_default(),
+            Column::StartLine => display_option(stat.start_line),
+            Column::EndLine => display_option(stat.end_line),
+            Column::HalsteadVolume => stat.halstead_volume.to_string(),
+            Column::CyclomaticComplexity => stat.cyclomatic_complexity.to_string(),
+            Column::CyclomaticAverage => stat.cyclomatic_average.to_string(),
+            Column::CyclomaticTotal => stat.cyclomatic_total.to_string(),
+            Column::MaxNesting => stat.max_nesting.to_string(),
+            Column::Nom => stat.nom.to_string(),
+            Column::LongestFunction => stat.longest_function.to_string(),
+            Column::LinesOfCode => stat.loc.to_string(),
+            Column::CommentsPercentage => stat.comments_percentage.to_string(),
+            Column::Duplication => stat.duplication.to_string(),
+            Column::DuplicationPartners => stat.duplication_partners.join(" "),
+            Column::MonthlyChanges => stat
+                .monthly_changes
+                .iter()
+                .flat_map(|monthly_changes| monthly_changes.values())
+                .map(|count| count.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            Column::DebtComments => stat.debt_comments.to_string(),
+            Column::TypeAnnotations => display_option(stat.type_annotations),
+            Column::TestRatio => display_option(stat.test_ratio),
+            Column::TestLoc => display_option(stat.test_loc),
+            Column::FanIn => display_option(stat.fan_in),
+            Column::FanOut => display_option(stat.fan_out),
+            Column::Centrality => display_option(stat.centrality),
+            Column::MaintainabilityIndex => stat.maintainability_index.to_string(),
+            Column::ChangesCount => stat.changes_count.to_string(),
+            Column::Changes30d => stat.changes_30d.to_string(),
+            Column::Changes90d => stat.changes_90d.to_string(),
+            Column::Changes365d => stat.changes_365d.to_string(),
+            Column::LinesChanged => stat.lines_changed.to_string(),
+            Column::FeatureChanges => stat.feature_changes.to_string(),
+            Column::FixChanges => stat.fix_changes.to_string(),
+            Column::RefactorChanges => stat.refactor_changes.to_string(),
+            Column::ChoreChanges => stat.chore_changes.to_string(),
+            Column::AuthorEntropy => stat.author_entropy.to_string(),
+            Column::KnowledgeLoss => display_option(stat.knowledge_loss),
+            Column::FirstCommit => display_option(stat.first_commit),
+            Column::LastAuthor => display_option(stat.last_author.as_deref()),
+            Column::PrimaryOwner => display_option(
+                stat.primary_owner
+                    .as_ref()
+                    .map(|owner| format!("{} {:.0}%", owner.author, owner.share)),
+            ),
+            Column::HotspotIndex => match stat.hotspot_index.is_nan() {
+                true => String::new(),
+                false => stat.hotspot_index.to_string(),
+            },
+            Column::Severity => stat.severity.name().to_string(),
+            Column::Coverage => display_option(stat.coverage),
+            Column::Risk => display_option(stat.risk),
+            Column::RankDelta => display_option(stat.rank_delta),
+            Column::HotspotIndexDelta => display_option(stat.hotspot_index_delta),
+            Column::Plugin(name) => display_option(stat.plugins.get(*name)),
+        }
+    }
+}
+
+impl ValueEnum for Column {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Column::Path,
+            Column::PathType,
+            Column::Function,
+            Column::StartLine,
+            Column::EndLine,
+            Column::HalsteadVolume,
+            Column::CyclomaticComplexity,
+            Column::CyclomaticAverage,
+            Column::CyclomaticTotal,
+            Column::MaxNesting,
+            Column::Nom,
+            Column::LongestFunction,
+            Column::LinesOfCode,
+            Column::CommentsPercentage,
+            Column::Duplication,
+            Column::DuplicationPartners,
+            Column::DebtComments,
+            Column::TypeAnnotations,
+            Column::TestRatio,
+            Column::TestLoc,
+            Column::FanIn,
+            Column::FanOut,
+            Column::Centrality,
+            Column::MaintainabilityIndex,
+            Column::ChangesCount,
+            Column::Changes30d,
+            Column::Changes90d,
+            Column::Changes365d,
+            Column::LinesChanged,
+            Column::FeatureChanges,
+            Column::FixChanges,
+            Column::RefactorChanges,
+            Column::ChoreChanges,
+            Column::AuthorEntropy,
+            Column::KnowledgeLoss,
+            Column::MonthlyChanges,
+            Column::FirstCommit,
+            Column::LastAuthor,
+            Column::PrimaryOwner,
+            Column::HotspotIndex,
+            Column::Severity,
+            Column::Coverage,
+            Column::Risk,
+            Column::RankDelta,
+            Column::HotspotIndexDelta,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let name = match self.name() {
+            Cow::Borrowed(name) => name,
+            Cow::Owned(_) => return None,
+        };
+
+        Some(PossibleValue::new(name).help(self.description()))
+    }
+}
+
+/// Parses the column names of [`ValueEnum`], the metrics of the plugins with
+/// a `plugin.` prefix, and the metric names with a `_pctl` or `_zscore`
+/// suffix for the normalized columns.
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let normalized = [
+            (
+                PERCENTILE_SUFFIX,
+                Column::Percentile as fn(&'static Column) -> Column,
+            ),
+            (Z_SCORE_SUFFIX, Column::ZScore),
+        ];
+
+        for (suffix, normalize) in normalized {
+            if let Some(metric) = name.strip_suffix(suffix) {
+                if let Some(column) = Column::from_plugin_name(metric)? {
+                    return Ok(normalize(Box::leak(Box::new(column))));
+                }
+
+                return match Column::value_variants()
+                    .iter()
+                    .find(|column| column.is_metric() && column.name() == metric)
+                {
+                    Some(column) => Ok(normalize(column)),
+                    None => Err(format!("unknown metric `{metric}`")),
+                };
+            }
+        }
+
+        if let Some(column) = Column::from_plugin_name(name)? {
+            return Ok(column);
+        }
+
+        <Column as ValueEnum>::from_str(name, false).map_err(|_| {
+            format!("unknown column `{name}`, expected one of {} or a metric with a {PERCENTILE_SUFFIX} or {Z_SCORE_SUFFIX} suffix", Column::value_variants()
+                .iter()
+                .map(|column| column.name())
+                .collect::<Vec<_>>()
+                .join(", "))
+        })
+    }
+}
+
+/// Distribution of the metrics of the normalized columns among the rows of a
+/// report.
+pub(crate) struct Distributions(HashMap<Column, Distribution>);
+
+struct Distribution {
+    sorted: Vec<f64>,
+    mean: f64,
+    standard_deviation: f64,
+}
+
+impl Distributions {
+    pub fn new(columns: &[Column], stats: &[HotspotStats]) -> Self {
+        let distributions = columns
+            .iter()
+            .filter_map(|column| match column {
+                Column::Percentile(metric) | Column::ZScore(metric) => Some(**metric),
+                _ => None,
+            })
+            .map(|metric| {
+                let values = stats.iter().filter_map(|stat| metric.metric(stat));
+                (metric, Distribution::new(values.collect()))
+            })
+            .collect();
+
+        Distributions(distributions)
+    }
+
+    /// The value of the column rendered as text, normalized columns included.
+    pub fn value(&self, column: &Column, stat: &HotspotStats) -> String {
+        let normalized = match column {
+            Column::Percentile(metric) => self.normalize(metric, stat, Distribution::percentile),
+            Column::ZScore(metric) => self.normalize(metric, stat, Distribution::z_score),
+            _ => return column.value(stat),
+        };
+
+        display_option(normalized)
+    }
+
+    fn normalize(
+        &self,
+        metric: &Column,
+        stat: &HotspotStats,
+        normalize: fn(&Distribution, f64) -> f64,
+    ) -> Option<f64> {
+        let distribution = self.0.get(metric)?;
+
+        metric
+            .metric(stat)
+            .map(|value| normalize(distribution, value))
+    }
+}
+
+impl Distribution {
+    /// The mean and standard deviation ignore the infinite values, which
+    /// would make every standard score undefined.
+    fn new(mut values: Vec<f64>) -> Self {
+        values.sort_unstable_by(f64::total_cmp);
+
+        let finite = values
+            .iter()
+            .copied()
+            .filter(|value| value.is_finite())
+            .collect::<Vec<_>>();
+        let count = finite.len().max(1) as f64;
+        let mean = finite.iter().sum::<f64>() / count;
+        let variance = finite
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / count;
+
+        Distribution {
+            sorted: values,
+            mean,
+            standard_deviation: variance.sqrt(),
+        }
+    }
+
+    /// Percentage of the values lower than or equal to the given one.
+    fn percentile(&self, value: f64) -> f64 {
+        let rank = self
+            .sorted
+            .partition_point(|other| other.total_cmp(&value).is_le());
+
+        rank as f64 / self.sorted.len().max(1) as f64 * 100.0
+    }
+
+    fn z_score(&self, value: f64) -> f64 {
+        match self.standard_deviation {
+            0.0 => 0.0,
+            standard_deviation => (value - self.mean) / standard_deviation,
+        }
+    }
+}
+
+fn display_option<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotspot::PathType;
+    use rstest::*;
+
+    fn stat(loc: u32) -> HotspotStats {
+        HotspotStats {
+            path: format!("module_{loc}.py"),
+            path_type: PathType::File,
+            function: None,
+            start_line: None,
+            end_line: None,
+            halstead_volume: 0.0,
+            cyclomatic_complexity: 0.0,
+            cyclomatic_average: 0.0,
+            cyclomatic_total: 0.0,
+            max_nesting: 0,
+            nom: 0,
+            longest_function: 0,
+            loc,
+            comments_percentage: 0.0,
+            maintainability_index: 0.0,
+            changes_count: 0,
+            changes_30d: 0,
+            changes_90d: 0,
+            changes_365d: 0,
+            lines_changed: 0,
+            feature_changes: 0,
+            fix_changes: 0,
+            refactor_changes: 0,
+            chore_changes: 0,
+            author_entropy: 0.0,
+            knowledge_loss: None,
+            monthly_changes: None,
+            first_commit: None,
+            last_author: None,
+            primary_owner: None,
+            hotspot_index: 0.0,
+            coverage: None,
+            risk: None,
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: 0.0,
+            duplication_partners: vec![],
+            debt_comments: 0,
+            type_annotations: None,
+            test_ratio: None,
+            test_loc: None,
+            fan_in: None,
+            fan_out: None,
+            centrality: None,
+            plugins: Default::default(),
+            severity: Default::default(),
+        }
+    }
+
+    #[rstest]
+    #[case("loc", Ok(Column::LinesOfCode))]
+    #[case("nom", Ok(Column::Nom))]
+    #[case("longest_function", Ok(Column::LongestFunction))]
+    #[case("last_author", Ok(Column::LastAuthor))]
+    #[case("loc_pctl", Ok(Column::Percentile(&Column::LinesOfCode)))]
+    #[case("hotspot_index_zscore", Ok(Column::ZScore(&Column::HotspotIndex)))]
+    #[case("path_pctl", Err("unknown metric `path`".to_string()))]
+    fn test_column_from_str(#[case] name: &str, #[case] expected: Result<Column, String>) {
+        // ACT
+        let actual = name.parse::<Column>();
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_distributions_value() {
+        // ARRANGE
+        let stats = [stat(10), stat(20), stat(30), stat(40)];
+        let columns = [
+            Column::LinesOfCode,
+            Column::Percentile(&Column::LinesOfCode),
+            Column::ZScore(&Column::LinesOfCode),
+        ];
+        let distributions = Distributions::new(&columns, &stats);
+
+        // ACT
+        let actual = stats
+            .iter()
+            .map(|stat| {
+                columns
+                    .iter()
+                    .map(|column| distributions.value(column, stat))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // ASSERT
+        let standard_deviation = 125.0_f64.sqrt();
+        let expected = [
+            (10, 25, -15.0),
+            (20, 50, -5.0),
+            (30, 75, 5.0),
+            (40, 100, 15.0),
+        ]
+        .map(|(loc, percentile, deviation)| {
+            vec![
+                loc.to_string(),
+                percentile.to_string(),
+                (deviation / standard_deviation).to_string(),
+            ]
+        });
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Months, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::ownership::Contribution;
+
+/// Kind of change of a commit, told by the prefix of its subject.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitType {
+    Feature,
+    Fix,
+    Refactor,
+    Chore,
+}
+
+/// Regexes matched against the subject of the commits, a commit being of the
+/// first type with a matching regex and of none when no regex matches. The
+/// defaults recognise the prefixes of the conventional commits.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommitTypes {
+    pub feature: Vec<String>,
+    pub fix: Vec<String>,
+    pub refactor: Vec<String>,
+    pub chore: Vec<String>,
+}
+
+impl Default for CommitTypes {
+    fn default() -> Self {
+        let conventional = |prefixes: &str| vec![format!(r"(?i)^({prefixes})(\(.*\))?!?:")];
+
+        CommitTypes {
+            feature: conventional("feat"),
+            fix: conventional("fix"),
+            refactor: conventional("refactor|perf|style"),
+            chore: conventional("chore|build|ci|docs|test"),
+        }
+    }
+}
+
+impl CommitTypes {
+    /// Compile the regexes, failing on the first invalid one.
+    pub(crate) fn classifier(&self) -> Result<CommitClassifier> {
+        let types = [
+            (CommitType::Feature, &self.feature),
+            (CommitType::Fix, &self.fix),
+            (CommitType::Refactor, &self.refactor),
+            (CommitType::Chore, &self.chore),
+        ];
+        let mut patterns = vec![];
+
+        for (commit_type, regexes) in types {
+            for regex in regexes {
+                let compiled = Regex::new(regex).map_err(|error| Error::InvalidCommitPattern {
+                    pattern: regex.clone(),
+                    message: error.to_string(),
+                })?;
+                patterns.push((commit_type, compiled));
+            }
+        }
+
+        Ok(CommitClassifier { patterns })
+    }
+}
+
+pub(crate) struct CommitClassifier {
+    patterns: Vec<(CommitType, Regex)>,
+}
+
+impl CommitClassifier {
+    pub fn classify(&self, subject: &str) -> Option<CommitType> {
+        self.patterns
+            .iter()
+            .find(|(_, regex)| regex.is_match(subject))
+            .map(|(commit_type, _)| *commit_type)
+    }
+}
+
+/// Number of months of the monthly changes counts, the current one included.
+const MONTHS: u32 = 12;
+
+/// Days of the windows of the recent changes counts, as with `--since 30d`.
+const WINDOWS: [i64; 3] = [30, 90, 365];
+
+/// Changes count of a file broken down by the type of the commits, the
+/// commits of no known type only being in the total.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Churn {
+    pub changes_count: u32,
+    /// Changes in the last 30, 90 and 365 days.
+    pub changes_30d: u32,
+    pub changes_90d: u32,
+    pub changes_365d: u32,
+    pub lines_changed: u32,
+    pub feature: u32,
+    pub fix: u32,
+    pub refactor: u32,
+    pub chore: u32,
+    /// How evenly the changes are spread across the authors.
+    pub author_entropy: f64,
+    /// Percentage of the changes made by the authors no longer active.
+    pub knowledge_loss: Option<f64>,
+    /// Changes count of each of the last months by `YYYY-MM`, when
+    /// requested.
+    #[serde(default)]
+    pub monthly_changes: Option<BTreeMap<String, u32>>,
+    /// Day the file was first committed in the whole history.
+    #[serde(default)]
+    pub first_commit: Option<NaiveDate>,
+    /// Author of the most recent change.
+    #[serde(default)]
+    pub last_author: Option<String>,
+    /// Author of the most changes and their share of them.
+    #[serde(default)]
+    pub primary_owner: Option<Contribution>,
+}
+
+impl Churn {
+    pub fn add(&mut self, commit_type: Option<CommitType>, lines: u32) {
+        self.changes_count += 1;
+        self.lines_changed += lines;
+
+        match commit_type {
+            Some(CommitType::Feature) => self.feature += 1,
+            Some(CommitType::Fix) => self.fix += 1,
+            Some(CommitType::Refactor) => self.refactor += 1,
+            Some(CommitType::Chore) => self.chore += 1,
+            None => {}
+        }
+    }
+
+    /// Count a change made on the date in the windows of the recent changes
+    /// including it.
+    pub fn add_recent(&mut self, date: Option<NaiveDate>, today: NaiveDate) {
+        let Some(days) = date.map(|date| (today - date).num_days()) else {
+            return;
+        };
+        let counts = [
+            &mut self.changes_30d,
+            &mut self.changes_90d,
+            &mut self.changes_365d,
+        ];
+
+        for (window, count) in WINDOWS.into_iter().zip(counts) {
+            if days <= window {
+                *count += 1;
+            }
+        }
+    }
+
+    /// Count a change in the month of the date, the changes before the
+    /// months of the counts being left out.
+    pub fn add_monthly(&mut self, months: &BTreeMap<String, u32>, date: Option<NaiveDate>) {
+        let monthly_changes = self.monthly_changes.get_or_insert_with(|| months.clone());
+
+        if let Some(count) = date.and_then(|date| monthly_changes.get_mut(&month(date))) {
+            *count += 1;
+        }
+    }
+}
+
+fn month(date: NaiveDate) -> String {
+    date.format("%Y-%m").to_string()
+}
+
+/// No changes in each of the last months up to the one of today.
+pub(crate) fn last_months(today: NaiveDate) -> BTreeMap<String, u32> {
+    let first_day = today.with_day(1).expect("every month has a first day");
+
+    (0..MONTHS)
+        .filter_map(|months| first_day.checked_sub_months(Months::new(months)))
+        .map(|date| (month(date), 0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("feat: add the html output", Some(CommitType::Feature))]
+    #[case("feat(cli)!: drop --legacy", Some(CommitType::Feature))]
+    #[case("Fix: off by one in the nesting", Some(CommitType::Fix))]
+    #[case("perf: cache the churn", Some(CommitType::Refactor))]
+    #[case("docs: fix the typos", Some(CommitType::Chore))]
+    #[case("fixed the build", None)]
+    #[case("Merge branch 'main'", None)]
+    fn test_classify_conventional_commits(
+        #[case] subject: &str,
+        #[case] expected: Option<CommitType>,
+    ) {
+        // ARRANGE
+        let classifier = CommitTypes::default().classifier().unwrap();
+
+        // ACT
+        let actual = classifier.classify(subject);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_classify_custom_patterns() {
+        // ARRANGE
+        let commit_types = CommitTypes {
+            fix: vec![r"^\[BUG-\d+\]".to_string()],
+            ..Default::default()
+        };
+        let classifier = commit_types.classifier().unwrap();
+
+        // ACT
+        let actual = classifier.classify("[BUG-42] handle empty files");
+
+        // ASSERT
+        assert_eq!(actual, Some(CommitType::Fix));
+    }
+
+    #[test]
+    fn test_classifier_invalid_pattern() {
+        // ARRANGE
+        let commit_types = CommitTypes {
+            chore: vec!["(".to_string()],
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = commit_types.classifier();
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::InvalidCommitPattern { .. })));
+    }
+
+    #[rstest]
+    #[case("2024-03-15", [1, 1, 1])]
+    #[case("2024-02-14", [1, 1, 1])]
+    #[case("2024-02-13", [0, 1, 1])]
+    #[case("2023-03-16", [0, 0, 1])]
+    #[case("2023-03-15", [0, 0, 0])]
+    fn test_add_recent(#[case] date: &str, #[case] expected: [u32; 3]) {
+        // ARRANGE
+        let today = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let mut churn = Churn::default();
+
+        // ACT
+        churn.add_recent(date.parse().ok(), today);
+
+        // ASSERT
+        assert_eq!(
+            [churn.changes_30d, churn.changes_90d, churn.changes_365d],
+            expected
+        );
+    }
+
+    #[test]
+    fn test_add_monthly() {
+        // ARRANGE
+        let months = last_months(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        let mut churn = Churn::default();
+
+        // ACT
+        for date in ["2024-03-01", "2024-03-31", "2023-04-30", "2023-03-31"] {
+            churn.add_monthly(&months, date.parse().ok());
+        }
+        churn.add_monthly(&months, None);
+
+        // ASSERT
+        let monthly_changes = churn.monthly_changes.unwrap();
+        assert_eq!(monthly_changes.len(), 12);
+        assert_eq!(monthly_changes.keys().next().unwrap(), "2023-04");
+        assert_eq!(monthly_changes["2023-04"], 1);
+        assert_eq!(monthly_changes["2024-03"], 2);
+        assert_eq!(monthly_changes.values().sum::<u32>(), 3);
+    }
+}
@@ -0,0 +1,709 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::columns::Column;
+use crate::commit_type::CommitTypes;
+use crate::debt_comments::DEFAULT_DEBT_MARKERS;
+use crate::discovery::DEFAULT_EXCLUDES;
+use crate::error::{Error, Result};
+use crate::formatting::{CRITICAL_MAINTAINABILITY_INDEX, LOW_MAINTAINABILITY_INDEX};
+use crate::formula::Formula;
+use crate::history::IgnoreCommits;
+use crate::hotspot::HotspotStats;
+use crate::overrides::Overrides;
+use crate::preset::Preset;
+use crate::severity::{Severity, SeverityCutoffs};
+
+/// Name of the configuration file looked up in the analysed directory.
+pub const CONFIG_FILENAME: &str = ".tech_debt_hotspot.toml";
+
+/// Settings read from the configuration file, every section being optional.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub weights: Option<Weights>,
+    pub debt_comments: Option<DebtComments>,
+    /// Files larger than this number of bytes are skipped.
+    pub max_file_size: Option<u64>,
+    pub markers: Option<Markers>,
+    pub severity: Option<SeverityCutoffs>,
+    pub commit_types: Option<CommitTypes>,
+    pub ignore_commits: Option<IgnoreCommits>,
+    /// WebAssembly plugins adding metrics, relative to the configuration
+    /// file.
+    pub plugins: Option<Vec<PathBuf>>,
+    /// Thresholds of the markers and severity cutoffs used when their
+    /// sections are missing.
+    pub preset: Option<Preset>,
+    /// Files and directories skipped, relative to the configuration file.
+    pub exclude: Option<Vec<PathBuf>>,
+}
+
+/// Exponents of the metrics in the weighted hotspot index
+/// `changes_count^churn * cyclomatic_complexity^complexity * loc^size / (1 + comments_percentage / 100)^comments`,
+/// a weight of 0 ignoring the metric.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub churn: f64,
+    pub complexity: f64,
+    pub size: f64,
+    pub comments: f64,
+    /// The cyclomatic complexity of the files weighted by `complexity`.
+    pub complexity_metric: ComplexityMetric,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            churn: 1.0,
+            complexity: 1.0,
+            size: 0.0,
+            comments: 0.0,
+            complexity_metric: ComplexityMetric::default(),
+        }
+    }
+}
+
+/// Which cyclomatic complexity of a file, of its most complex function, or
+/// the average or sum of all of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComplexityMetric {
+    #[default]
+    Max,
+    Average,
+    Total,
+}
+
+impl ComplexityMetric {
+    pub fn column(&self) -> Column {
+        match self {
+            ComplexityMetric::Max => Column::CyclomaticComplexity,
+            ComplexityMetric::Average => Column::CyclomaticAverage,
+            ComplexityMetric::Total => Column::CyclomaticTotal,
+        }
+    }
+}
+
+/// Markers counted in the comments by the `debt_comments` column.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DebtComments {
+    pub markers: Vec<String>,
+}
+
+impl Default for DebtComments {
+    fn default() -> Self {
+        DebtComments {
+            markers: DEFAULT_DEBT_MARKERS
+                .iter()
+                .map(|marker| marker.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Thresholds of the severity markers of the markdown table, the lower one
+/// first: a maintainability index below the lower threshold is 🔴 and below
+/// the upper one 🟡, a hotspot index or cyclomatic complexity from the upper
+/// threshold is 🔴 and from the lower one 🟡, the others being 🟢.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Markers {
+    pub maintainability_index: [f64; 2],
+    pub hotspot_index: [f64; 2],
+    pub cyclomatic_complexity: [f64; 2],
+}
+
+impl Default for Markers {
+    fn default() -> Self {
+        Markers {
+            maintainability_index: [LOW_MAINTAINABILITY_INDEX, CRITICAL_MAINTAINABILITY_INDEX],
+            hotspot_index: [10.0, 20.0],
+            cyclomatic_complexity: [10.0, 20.0],
+        }
+    }
+}
+
+impl Markers {
+    /// The marker of the maintainability index, hotspot index, cyclomatic
+    /// complexity and test lines of code columns, none for the other columns.
+    pub fn marker(&self, column: &Column, stat: &HotspotStats) -> Option<&'static str> {
+        let marker = match column {
+            Column::MaintainabilityIndex => {
+                let [low, high] = self.maintainability_index;
+
+                match stat.maintainability_index {
+                    value if value < low => "🔴",
+                    value if value < high => "🟡",
+                    _ => "🟢",
+                }
+            }
+            Column::HotspotIndex => {
+                let [low, high] = self.hotspot_index;
+
+                match stat.hotspot_index {
+                    value if value >= high => "🔴",
+                    value if value >= low => "🟡",
+                    _ => "🟢",
+                }
+            }
+            Column::CyclomaticComplexity => {
+                let [low, high] = self.cyclomatic_complexity;
+
+                match stat.cyclomatic_complexity {
+                    value if value >= high => "🔴",
+                    value if value >= low => "🟡",
+                    _ => "🟢",
+                }
+            }
+            // The files without tests, the hotspots first
+            Column::TestLoc => match stat.test_loc? {
+                0 if stat.hotspot_index >= self.hotspot_index[1] => "🔴",
+                0 => "🟡",
+                _ => "🟢",
+            },
+            _ => return None,
+        };
+
+        Some(marker)
+    }
+
+    /// Why the row is marked 🔴 or of critical severity, empty otherwise.
+    pub fn violations(&self, stat: &HotspotStats) -> Vec<String> {
+        let mut violations = vec![];
+
+        if stat.maintainability_index < self.maintainability_index[0] {
+            violations.push(format!(
+                "maintainability index {:.1} is below {}",
+                stat.maintainability_index, self.maintainability_index[0]
+            ));
+        }
+        if stat.hotspot_index >= self.hotspot_index[1] {
+            violations.push(format!(
+                "hotspot index {:.2} is from {}",
+                stat.hotspot_index, self.hotspot_index[1]
+            ));
+        }
+        if stat.cyclomatic_complexity >= self.cyclomatic_complexity[1] {
+            violations.push(format!(
+                "cyclomatic complexity {} is from {}",
+                stat.cyclomatic_complexity, self.cyclomatic_complexity[1]
+            ));
+        }
+        if stat.severity == Severity::Critical {
+            violations.push("severity is critical".to_string());
+        }
+
+        violations
+    }
+}
+
+impl Weights {
+    pub fn formula(&self) -> Formula {
+        format!(
+            "changes_count ^ {} * {} ^ {} * loc ^ {} / (1 + comments_percentage / 100) ^ {}",
+            self.churn,
+            self.complexity_metric.column().name(),
+            self.complexity,
+            self.size,
+            self.comments
+        )
+        .parse()
+        .expect("the weighted formula is valid")
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config> {
+        let content = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut config: Config = toml::from_str(&content).map_err(|error| Error::Config {
+            path: path.to_path_buf(),
+            message: error.message().to_string(),
+        })?;
+
+        if let Some(weights) = &config.weights {
+            let values = [
+                weights.churn,
+                weights.complexity,
+                weights.size,
+                weights.comments,
+            ];
+
+            if !values.iter().all(|value| value.is_finite()) {
+                return Err(Error::Config {
+                    path: path.to_path_buf(),
+                    message: "weights must be finite numbers".to_string(),
+                });
+            }
+        }
+
+        if let Some(markers) = &config.markers {
+            let thresholds = [
+                markers.maintainability_index,
+                markers.hotspot_index,
+                markers.cyclomatic_complexity,
+            ];
+
+            if !thresholds
+                .iter()
+                .all(|[low, high]| low.is_finite() && high.is_finite() && low <= high)
+            {
+                return Err(Error::Config {
+                    path: path.to_path_buf(),
+                    message: "marker thresholds must be finite numbers, the lower one first"
+                        .to_string(),
+                });
+            }
+        }
+
+        if let Some(severity) = &config.severity {
+            if !severity.is_valid() {
+                return Err(Error::Config {
+                    path: path.to_path_buf(),
+                    message: "severity cutoffs must be finite numbers in increasing order"
+                        .to_string(),
+                });
+            }
+        }
+
+        if let Some(commit_types) = &config.commit_types {
+            commit_types.classifier().map_err(|error| Error::Config {
+                path: path.to_path_buf(),
+                message: error.to_string(),
+            })?;
+        }
+
+        if let Some(ignore_commits) = &config.ignore_commits {
+            if let Some(message) = ignore_commits
+                .messages
+                .iter()
+                .find(|message| Regex::new(message).is_err())
+            {
+                return Err(Error::Config {
+                    path: path.to_path_buf(),
+                    message: format!("invalid commit message pattern {message:?}"),
+                });
+            }
+        }
+
+        if let Some(debt_comments) = &config.debt_comments {
+            if debt_comments.markers.iter().any(|marker| marker.is_empty()) {
+                return Err(Error::Config {
+                    path: path.to_path_buf(),
+                    message: "debt comment markers must not be empty".to_string(),
+                });
+            }
+        }
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+
+        for plugin in config.plugins.iter_mut().flatten() {
+            *plugin = directory.join(&plugin);
+        }
+        for exclude in config.exclude.iter_mut().flatten() {
+            *exclude = directory.join(&exclude);
+        }
+
+        Ok(config)
+    }
+
+    /// Load the configuration file of the directory, the defaults being used
+    /// when there is none.
+    pub fn discover(directory: &Path) -> Result<Config> {
+        match Config::load(&directory.join(CONFIG_FILENAME)) {
+            Err(Error::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                Ok(Config::default())
+            }
+            result => result,
+        }
+    }
+
+    /// Load the configuration files of the subdirectories of the directory,
+    /// with their directory relative to it, parents first.
+    ///
+    /// They only set the settings of the files below them, `preset`,
+    /// `markers`, `severity` and `exclude`. The hidden directories and the
+    /// ones of `DEFAULT_EXCLUDES` are not searched.
+    pub fn discover_nested(directory: &Path) -> Result<Vec<(PathBuf, Config)>> {
+        let mut configs = vec![];
+        let mut directories = vec![directory.to_path_buf()];
+
+        while let Some(current) = directories.pop() {
+            // Unreadable directories are reported by the discovery of the files
+            let Ok(entries) = fs::read_dir(&current) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+
+                if !entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+                    || name.starts_with('.')
+                    || DEFAULT_EXCLUDES.contains(&name.as_ref())
+                {
+                    continue;
+                }
+
+                let subdirectory = entry.path();
+                let path = subdirectory.join(CONFIG_FILENAME);
+
+                if path.is_file() {
+                    let config = Config::load(&path)?;
+                    config.check_nested(&path)?;
+
+                    let relative = subdirectory
+                        .strip_prefix(directory)
+                        .unwrap_or(&subdirectory);
+                    configs.push((relative.to_path_buf(), config));
+                }
+
+                directories.push(subdirectory);
+            }
+        }
+
+        // A directory sorts before the ones below it
+        configs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(configs)
+    }
+
+    fn check_nested(&self, path: &Path) -> Result<()> {
+        let Config {
+            weights,
+            debt_comments,
+            max_file_size,
+            markers: _,
+            severity: _,
+            commit_types,
+            ignore_commits,
+            plugins,
+            preset: _,
+            exclude: _,
+        } = self;
+
+        if weights.is_some()
+            || debt_comments.is_some()
+            || max_file_size.is_some()
+            || commit_types.is_some()
+            || ignore_commits.is_some()
+            || plugins.is_some()
+        {
+            return Err(Error::Config {
+                path: path.to_path_buf(),
+                message: "the configuration file of a subdirectory can only set preset, markers, severity and exclude".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// The value of a setting for the files below each directory of the nested
+/// configuration files, a configuration without it inheriting the value of
+/// its parent directory.
+pub fn nested_overrides<T: Clone>(
+    value: T,
+    nested: &[(PathBuf, Config)],
+    setting: impl Fn(&Config) -> Option<T>,
+) -> Overrides<T> {
+    let mut overrides = Overrides::new(value);
+
+    // The parents come first so their value is known
+    for (directory, config) in nested {
+        let value = setting(config).unwrap_or_else(|| overrides.get(directory).clone());
+        overrides = overrides.with_directory(directory, value);
+    }
+
+    overrides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotspot::{HotspotStats, PathType};
+    use rstest::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_config_discover() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILENAME),
+            "max_file_size = 1000000\nplugins = [\"plugins/sql.wasm\"]\npreset = \"strict\"\n\n[weights]\nchurn = 2\nsize = 0.5\n\n[debt_comments]\nmarkers = [\"TODO\", \"DEBT\"]\n\n[markers]\nhotspot_index = [5, 15]\n\n[commit_types]\nfix = [\"^hotfix\"]\n",
+        )
+        .unwrap();
+
+        // ACT
+        let actual = Config::discover(temp_dir.path()).unwrap();
+
+        // ASSERT
+        let expected = Config {
+            weights: Some(Weights {
+                churn: 2.0,
+                complexity: 1.0,
+                size: 0.5,
+                comments: 0.0,
+                complexity_metric: ComplexityMetric::Max,
+            }),
+            debt_comments: Some(DebtComments {
+                markers: vec!["TODO".to_string(), "DEBT".to_string()],
+            }),
+            max_file_size: Some(1_000_000),
+            markers: Some(Markers {
+                hotspot_index: [5.0, 15.0],
+                ..Default::default()
+            }),
+            severity: None,
+            commit_types: Some(CommitTypes {
+                fix: vec!["^hotfix".to_string()],
+                ..Default::default()
+            }),
+            ignore_commits: None,
+            plugins: Some(vec![temp_dir.path().join("plugins/sql.wasm")]),
+            preset: Some(Preset::Strict),
+            exclude: None,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_config_discover_without_file() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+
+        // ACT
+        let actual = Config::discover(temp_dir.path()).unwrap();
+
+        // ASSERT
+        assert_eq!(actual, Config::default());
+    }
+
+    #[test]
+    fn test_config_discover_nested() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        for directory in ["legacy/billing", "legacy/reports", ".venv/lib"] {
+            fs::create_dir_all(temp_dir.path().join(directory)).unwrap();
+        }
+        fs::write(
+            temp_dir.path().join("legacy").join(CONFIG_FILENAME),
+            "preset = \"lenient\"\nexclude = [\"generated\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("legacy/billing").join(CONFIG_FILENAME),
+            "[severity]\nmedium = 1\nhigh = 2\ncritical = 3\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join(".venv/lib").join(CONFIG_FILENAME),
+            "[severity]\nmedium = 0\n",
+        )
+        .unwrap();
+
+        // ACT
+        let nested = Config::discover_nested(temp_dir.path()).unwrap();
+        let actual = nested_overrides(SeverityCutoffs::default(), &nested, |config| {
+            config
+                .severity
+                .clone()
+                .or_else(|| config.preset.map(|preset| preset.severity_cutoffs()))
+        });
+
+        // ASSERT
+        let directories = nested.iter().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(
+            directories,
+            [Path::new("legacy"), Path::new("legacy/billing")]
+        );
+        assert_eq!(
+            nested[0].1.exclude,
+            Some(vec![temp_dir.path().join("legacy/generated")])
+        );
+        assert_eq!(actual.get(Path::new("app.py")), &SeverityCutoffs::default());
+        assert_eq!(
+            actual.get(Path::new("legacy/reports/a.py")),
+            &Preset::Lenient.severity_cutoffs()
+        );
+        assert_eq!(actual.get(Path::new("legacy/billing/a.py")).critical, 3.0);
+    }
+
+    #[test]
+    fn test_config_discover_nested_global_setting() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("legacy")).unwrap();
+        fs::write(
+            temp_dir.path().join("legacy").join(CONFIG_FILENAME),
+            "max_file_size = 1000\n",
+        )
+        .unwrap();
+
+        // ACT
+        let actual = Config::discover_nested(temp_dir.path());
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::Config { .. })));
+    }
+
+    #[test]
+    fn test_config_unknown_field() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILENAME),
+            "[weights]\nchrun = 2\n",
+        )
+        .unwrap();
+
+        // ACT
+        let actual = Config::discover(temp_dir.path());
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::Config { .. })));
+    }
+
+    #[rstest]
+    #[case(Column::MaintainabilityIndex, 5.0, 0.0, Some("🔴"))]
+    #[case(Column::MaintainabilityIndex, 15.0, 0.0, Some("🟡"))]
+    #[case(Column::MaintainabilityIndex, 20.0, 0.0, Some("🟢"))]
+    #[case(Column::HotspotIndex, 100.0, 20.0, Some("🔴"))]
+    #[case(Column::HotspotIndex, 100.0, 12.0, Some("🟡"))]
+    #[case(Column::HotspotIndex, 100.0, 2.0, Some("🟢"))]
+    #[case(Column::LinesOfCode, 5.0, 20.0, None)]
+    #[case(Column::TestLoc, 100.0, 20.0, Some("🔴"))]
+    #[case(Column::TestLoc, 100.0, 2.0, Some("🟡"))]
+    fn test_markers_marker(
+        #[case] column: Column,
+        #[case] maintainability_index: f64,
+        #[case] hotspot_index: f64,
+        #[case] expected: Option<&str>,
+    ) {
+        // ARRANGE
+        let stat = HotspotStats {
+            maintainability_index,
+            hotspot_index,
+            test_loc: Some(0),
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = Markers::default().marker(&column, &stat);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(50.0, 2.0, Severity::Low, vec![])]
+    #[case(15.0, 12.0, Severity::High, vec![])]
+    #[case(5.0, 2.0, Severity::Low, vec!["maintainability index 5.0 is below 10"])]
+    #[case(
+        5.0,
+        20.0,
+        Severity::Critical,
+        vec!["maintainability index 5.0 is below 10", "hotspot index 20.00 is from 20", "severity is critical"]
+    )]
+    fn test_markers_violations(
+        #[case] maintainability_index: f64,
+        #[case] hotspot_index: f64,
+        #[case] severity: Severity,
+        #[case] expected: Vec<&str>,
+    ) {
+        // ARRANGE
+        let stat = HotspotStats {
+            maintainability_index,
+            hotspot_index,
+            severity,
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = Markers::default().violations(&stat);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(ComplexityMetric::Max, 4.0 * 3.0 * 4.0 / 2.0)]
+    #[case(ComplexityMetric::Average, 4.0 * 2.0 * 4.0 / 2.0)]
+    #[case(ComplexityMetric::Total, 4.0 * 6.0 * 4.0 / 2.0)]
+    fn test_weights_formula(#[case] complexity_metric: ComplexityMetric, #[case] expected: f64) {
+        // ARRANGE
+        let weights = Weights {
+            churn: 2.0,
+            complexity: 1.0,
+            size: 0.5,
+            comments: 1.0,
+            complexity_metric,
+        };
+        let stat = HotspotStats {
+            path: "module.py".to_string(),
+            path_type: PathType::File,
+            function: None,
+            start_line: None,
+            end_line: None,
+            halstead_volume: 0.0,
+            cyclomatic_complexity: 3.0,
+            cyclomatic_average: 2.0,
+            cyclomatic_total: 6.0,
+            max_nesting: 0,
+            nom: 0,
+            longest_function: 0,
+            loc: 16,
+            comments_percentage: 100.0,
+            maintainability_index: 50.0,
+            changes_count: 2,
+            changes_30d: 0,
+            changes_90d: 0,
+            changes_365d: 0,
+            lines_changed: 0,
+            feature_changes: 0,
+            fix_changes: 0,
+            refactor_changes: 0,
+            chore_changes: 0,
+            author_entropy: 0.0,
+            knowledge_loss: None,
+            monthly_changes: None,
+            first_commit: None,
+            last_author: None,
+            primary_owner: None,
+            hotspot_index: 0.0,
+            coverage: None,
+            risk: None,
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: 0.0,
+            duplication_partners: vec![],
+            debt_comments: 0,
+            type_annotations: None,
+            test_ratio: None,
+            test_loc: None,
+            fan_in: None,
+            fan_out: None,
+            centrality: None,
+            plugins: Default::default(),
+            severity: Default::default(),
+        };
+
+        // ACT
+        let actual = weights.formula().evaluate(&stat);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use quick_xml::events::Event;
+use quick_xml::{Reader, XmlVersion};
+
+use crate::error::{Error, Result};
+
+/// Line coverage of the files of a coverage.py XML (Cobertura) or lcov
+/// report.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Coverage {
+    files: Vec<(PathBuf, FileCoverage)>,
+}
+
+/// Whether each instrumented line of a file was executed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct FileCoverage {
+    lines: BTreeMap<usize, bool>,
+}
+
+impl FileCoverage {
+    /// Percentage of the instrumented lines between `start` and `end`
+    /// included which were executed, `None` without instrumented lines.
+    pub fn percentage(&self, start: usize, end: usize) -> Option<f64> {
+        let lines = self.lines.range(start..=end);
+        let (covered, total) = lines.fold((0, 0), |(covered, total), (_, hit)| {
+            (covered + usize::from(*hit), total + 1)
+        });
+
+        match total {
+            0 => None,
+            _ => Some(covered as f64 / total as f64 * 100.0),
+        }
+    }
+
+    pub fn total(&self) -> Option<f64> {
+        self.percentage(0, usize::MAX)
+    }
+
+    fn add(&mut self, line: usize, hits: u64) {
+        *self.lines.entry(line).or_default() |= hits > 0;
+    }
+}
+
+impl Coverage {
+    /// Load the report, lcov files being recognised by their `.info` or
+    /// `.lcov` extension.
+    ///
+    /// Relative paths of the report are resolved from the source directories
+    /// of Cobertura reports, or else matched against the end of the analysed
+    /// paths.
+    pub fn load(path: &Path) -> Result<Coverage> {
+        let content = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        let coverage = match extension {
+            Some("info" | "lcov") => Coverage::parse_lcov(&content),
+            _ => Coverage::parse_cobertura(&content),
+        };
+
+        coverage.map_err(|message| Error::Coverage {
+            path: path.to_path_buf(),
+            message,
+        })
+    }
+
+    /// The coverage of the file at the given absolute path.
+    pub(crate) fn file(&self, path: &Path) -> Option<&FileCoverage> {
+        self.files
+            .iter()
+            .find(|(covered_path, _)| path.ends_with(covered_path))
+            .map(|(_, coverage)| coverage)
+    }
+
+    fn parse_lcov(content: &str) -> std::result::Result<Coverage, String> {
+        let mut coverage = Coverage::default();
+        let mut current: Option<(PathBuf, FileCoverage)> = None;
+
+        for (index, line) in content.lines().enumerate() {
+            let invalid = || format!("invalid line {}: {line}", index + 1);
+
+            if let Some(path) = line.strip_prefix("SF:") {
+                current = Some((PathBuf::from(path), FileCoverage::default()));
+            } else if let Some(data) = line.strip_prefix("DA:") {
+                let mut fields = data.split(',');
+                let line_number = fields.next().and_then(|field| field.parse().ok());
+                let hits = fields.next().and_then(|field| field.parse().ok());
+
+                match (current.as_mut(), line_number, hits) {
+                    (Some((_, file)), Some(line_number), Some(hits)) => file.add(line_number, hits),
+                    _ => return Err(invalid()),
+                }
+            } else if line == "end_of_record" {
+                coverage.files.extend(current.take());
+            }
+        }
+
+        coverage.files.extend(current);
+
+        Ok(coverage)
+    }
+
+    fn parse_cobertura(content: &str) -> std::result::Result<Coverage, String> {
+        let mut reader = Reader::from_str(content);
+        let mut sources = vec![];
+        let mut in_source = false;
+        let mut files: Vec<(PathBuf, FileCoverage)> = vec![];
+
+        loop {
+            let event = reader.read_event().map_err(|error| error.to_string())?;
+
+            match event {
+                Event::Start(element) if element.name().as_ref() == "source" => in_source = true,
+                Event::End(element) if element.name().as_ref() == "source" => in_source = false,
+                Event::Text(text) if in_source => {
+                    sources.push(PathBuf::from(text.xml10_content().trim()));
+                }
+                Event::Start(element) | Event::Empty(element)
+                    if element.name().as_ref() == "class" =>
+                {
+                    let filename = attribute(&element, "filename")?
+                        .ok_or("missing filename attribute of class")?;
+                    files.push((PathBuf::from(filename), FileCoverage::default()));
+                }
+                Event::Start(element) | Event::Empty(element)
+                    if element.name().as_ref() == "line" =>
+                {
+                    let number = attribute(&element, "number")?.and_then(|v| v.parse().ok());
+                    let hits = attribute(&element, "hits")?.and_then(|v| v.parse().ok());
+
+                    // Lines of the methods of a class are repeated in the class
+                    if let (Some((_, file)), Some(number), Some(hits)) =
+                        (files.last_mut(), number, hits)
+                    {
+                        file.add(number, hits);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        let files = files
+            .into_iter()
+            .map(|(path, coverage)| {
+                let resolved = sources
+                    .iter()
+                    .map(|source| source.join(&path))
+                    .find(|candidate| candidate.exists())
+                    .unwrap_or(path);
+
+                (resolved, coverage)
+            })
+            .collect();
+
+        Ok(Coverage { files })
+    }
+}
+
+fn attribute(
+    element: &quick_xml::events::BytesStart,
+    name: &str,
+) -> std::result::Result<Option<String>, String> {
+    element
+        .try_get_attribute(name)
+        .map_err(|error| error.to_string())?
+        .map(|attribute| {
+            attribute
+                .normalized_value(XmlVersion::Implicit1_0)
+                .map(|value| value.into_owned())
+                .map_err(|error| error.to_string())
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cobertura() {
+        // ARRANGE
+        let content = r#"<?xml version="1.0" ?>
+<coverage version="7.4.0" line-rate="0.75">
+    <sources>
+        <source>/nonexistent/project</source>
+    </sources>
+    <packages>
+        <package name="pkg" line-rate="0.75">
+            <classes>
+                <class name="a.py" filename="pkg/a.py" line-rate="0.75">
+                    <methods/>
+                    <lines>
+                        <line number="1" hits="1"/>
+                        <line number="2" hits="1"/>
+                        <line number="4" hits="0"/>
+                        <line number="5" hits="3"/>
+                    </lines>
+                </class>
+            </classes>
+        </package>
+    </packages>
+</coverage>"#;
+
+        // ACT
+        let actual = Coverage::parse_cobertura(content).unwrap();
+
+        // ASSERT
+        let file = actual.file(Path::new("/repo/pkg/a.py")).unwrap();
+
+        assert_eq!(file.total(), Some(75.0));
+        assert_eq!(file.percentage(3, 4), Some(0.0));
+        assert_eq!(file.percentage(6, 10), None);
+        assert_eq!(actual.file(Path::new("/repo/pkg/b.py")), None);
+    }
+
+    #[test]
+    fn test_parse_lcov() {
+        // ARRANGE
+        let content = "TN:\nSF:/repo/pkg/a.py\nDA:1,1\nDA:2,0\nLF:2\nLH:1\nend_of_record\nSF:pkg/b.py\nDA:1,0\nend_of_record\n";
+
+        // ACT
+        let actual = Coverage::parse_lcov(content).unwrap();
+
+        // ASSERT
+        let a = actual
+            .file(Path::new("/repo/pkg/a.py"))
+            .map(FileCoverage::total);
+        let b = actual
+            .file(Path::new("/repo/pkg/b.py"))
+            .map(FileCoverage::total);
+
+        assert_eq!((a, b), (Some(Some(50.0)), Some(Some(0.0))));
+    }
+
+    #[test]
+    fn test_parse_lcov_invalid() {
+        // ACT
+        let actual = Coverage::parse_lcov("SF:a.py\nDA:one,1\n");
+
+        // ASSERT
+        assert_eq!(actual, Err("invalid line 2: DA:one,1".to_string()));
+    }
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+
+use crate::columns::{Column, Distributions};
+use crate::hotspot::{HotspotStats, PathType};
+use crate::metadata::Metadata;
+
+/// The page of the dashboard, the rows being inlined in place of the marker.
+const TEMPLATE: &str = include_str!("dashboard.html");
+const DATA_MARKER: &str = "/*DATA*/";
+
+#[derive(Serialize)]
+struct Dashboard<'a> {
+    metadata: Option<&'a Metadata>,
+    columns: Vec<String>,
+    rows: Vec<DashboardRow<'a>>,
+}
+
+/// A row of the table with the metrics plotted by the charts.
+#[derive(Serialize)]
+struct DashboardRow<'a> {
+    label: String,
+    path: &'a str,
+    path_type: PathType,
+    function: Option<&'a str>,
+    changes_count: u32,
+    cyclomatic_complexity: f64,
+    loc: u32,
+    maintainability_index: f64,
+    values: Vec<String>,
+}
+
+/// A self-contained HTML page with the rows in a sortable and filterable
+/// table, a scatter plot of the changes count against the complexity and a
+/// treemap of the lines of code.
+pub(crate) fn render(
+    columns: &[Column],
+    stats: &[HotspotStats],
+    distributions: &Distributions,
+    metadata: Option<&Metadata>,
+) -> serde_json::Result<String> {
+    let dashboard = Dashboard {
+        metadata,
+        columns: columns
+            .iter()
+            .map(|column| column.name().into_owned())
+            .collect(),
+        rows: stats
+            .iter()
+            .map(|stat| DashboardRow {
+                label: match &stat.function {
+                    Some(function) => format!("{}::{function}", stat.path),
+                    None => stat.path.clone(),
+                },
+                path: &stat.path,
+                path_type: stat.path_type,
+                function: stat.function.as_deref(),
+                changes_count: stat.changes_count,
+                cyclomatic_complexity: stat.cyclomatic_complexity,
+                loc: stat.loc,
+                maintainability_index: stat.maintainability_index,
+                values: columns
+                    .iter()
+                    .map(|column| distributions.value(column, stat))
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    // A `</script>` in a path would close the script, `<` is escaped in the
+    // strings of the JSON to be safe
+    let data = serde_json::to_string(&dashboard)?.replace('<', "\\u003c");
+
+    Ok(TEMPLATE.replace(DATA_MARKER, &data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_escapes_script() {
+        // ARRANGE
+        let stats = vec![HotspotStats {
+            path: "</script><script>alert(1)</script>.py".to_string(),
+            ..Default::default()
+        }];
+        let columns = [Column::Path];
+
+        // ACT
+        let actual = render(
+            &columns,
+            &stats,
+            &Distributions::new(&columns, &stats),
+            None,
+        )
+        .unwrap();
+
+        // ASSERT
+        assert!(!actual.contains("</script><script>"));
+        assert!(actual.contains("\\u003c/script>\\u003cscript>alert(1)"));
+    }
+}
@@ -0,0 +1,76 @@
+use rustpython_parser::lexer::lex;
+use rustpython_parser::source_code::LineIndex;
+use rustpython_parser::{Mode, Tok};
+
+/// Markers counted in the comments when the configuration does not set any.
+pub const DEFAULT_DEBT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+/// The line of each marker found in the comments of the source code, a
+/// comment with several markers being counted once per marker.
+///
+/// Markers are case sensitive and only match whole words, so `TODOS` is not
+/// a `TODO`. Source code which cannot be tokenized has no marker.
+pub(crate) fn lines(source_code: &str, markers: &[String]) -> Vec<usize> {
+    if markers.is_empty() {
+        return vec![];
+    }
+
+    let line_index = LineIndex::from_source_text(source_code);
+    let mut lines = vec![];
+
+    for token in lex(source_code, Mode::Module) {
+        let Ok((token, range)) = token else {
+            return vec![];
+        };
+        let Tok::Comment(comment) = token else {
+            continue;
+        };
+
+        let count = markers
+            .iter()
+            .map(|marker| count_words(&comment, marker))
+            .sum();
+        let line = line_index
+            .source_location(range.start(), source_code)
+            .row
+            .to_usize();
+
+        lines.extend(std::iter::repeat_n(line, count));
+    }
+
+    lines
+}
+
+fn count_words(text: &str, word: &str) -> usize {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    text.match_indices(word)
+        .filter(|(index, _)| {
+            let before = text[..*index].chars().next_back();
+            let after = text[index + word.len()..].chars().next();
+
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        // ARRANGE
+        let source_code = "# TODO: split\nx = \"TODO in a string\"\n\ndef f():  # FIXME HACK\n    return 1  # TODOS, todo and XXX_1 are not markers\n";
+        let markers = DEFAULT_DEBT_MARKERS
+            .iter()
+            .map(|marker| marker.to_string())
+            .collect::<Vec<_>>();
+
+        // ACT
+        let actual = lines(source_code, &markers);
+
+        // ASSERT
+        assert_eq!(actual, vec![1, 4, 4]);
+    }
+}
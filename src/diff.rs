@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::columns::Column;
+use crate::error::{Error, Result};
+use crate::hotspot::{HotspotStats, PathType};
+use crate::sorting::{sort_stats_by, SortBy, SortKey};
+
+/// Differences of metrics smaller than this are ignored.
+const EPSILON: f64 = 1e-9;
+
+/// How a file or function changed since the baseline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffStatus {
+    /// The hotspot index increased, or the maintainability index decreased.
+    Regressed,
+    /// Not in the baseline.
+    New,
+    Improved,
+    /// Not in the current analysis any more.
+    Removed,
+    Unchanged,
+}
+
+impl DiffStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiffStatus::Regressed => "regressed",
+            DiffStatus::New => "new",
+            DiffStatus::Improved => "improved",
+            DiffStatus::Removed => "removed",
+            DiffStatus::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// A row of the baseline matched with the same file or function of the
+/// current analysis.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiffRow {
+    pub path: String,
+    pub function: Option<String>,
+    pub status: DiffStatus,
+    pub before: Option<HotspotStats>,
+    pub after: Option<HotspotStats>,
+}
+
+impl DiffRow {
+    fn new(before: Option<HotspotStats>, after: Option<HotspotStats>) -> DiffRow {
+        let stat = after.as_ref().or(before.as_ref()).expect("one side is set");
+        let (path, function) = (stat.path.clone(), stat.function.clone());
+
+        let status = match (&before, &after) {
+            (None, _) => DiffStatus::New,
+            (_, None) => DiffStatus::Removed,
+            (Some(before), Some(after)) => {
+                let hotspot_index = after.hotspot_index - before.hotspot_index;
+                let maintainability_index =
+                    after.maintainability_index - before.maintainability_index;
+
+                if hotspot_index > EPSILON
+                    || (hotspot_index.abs() <= EPSILON && maintainability_index < -EPSILON)
+                {
+                    DiffStatus::Regressed
+                } else if hotspot_index < -EPSILON || maintainability_index > EPSILON {
+                    DiffStatus::Improved
+                } else {
+                    DiffStatus::Unchanged
+                }
+            }
+        };
+
+        DiffRow {
+            path,
+            function,
+            status,
+            before,
+            after,
+        }
+    }
+
+    /// The value of the metric in the baseline and in the current analysis.
+    pub fn values(&self, column: &Column) -> (Option<f64>, Option<f64>) {
+        (
+            self.before.as_ref().and_then(|stat| column.metric(stat)),
+            self.after.as_ref().and_then(|stat| column.metric(stat)),
+        )
+    }
+
+    /// How much the metric changed, unknown when the row is on one side only.
+    pub fn delta(&self, column: &Column) -> Option<f64> {
+        match self.values(column) {
+            (Some(before), Some(after)) => Some(after - before),
+            _ => None,
+        }
+    }
+}
+
+/// The files or functions of a baseline compared with a current analysis,
+/// regressions first.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Diff {
+    pub rows: Vec<DiffRow>,
+}
+
+impl Diff {
+    /// The metrics compared in the reports.
+    pub const COLUMNS: &'static [Column] = &[
+        Column::HotspotIndex,
+        Column::MaintainabilityIndex,
+        Column::CyclomaticComplexity,
+        Column::LinesOfCode,
+    ];
+
+    pub fn new(baseline: Vec<HotspotStats>, current: Vec<HotspotStats>) -> Diff {
+        let mut baseline = baseline
+            .into_iter()
+            .map(|stat| ((stat.path.clone(), stat.function.clone()), stat))
+            .collect::<HashMap<_, _>>();
+
+        let mut rows = current
+            .into_iter()
+            .map(|stat| {
+                let before = baseline.remove(&(stat.path.clone(), stat.function.clone()));
+                DiffRow::new(before, Some(stat))
+            })
+            .collect::<Vec<_>>();
+        rows.extend(
+            baseline
+                .into_values()
+                .map(|stat| DiffRow::new(Some(stat), None)),
+        );
+
+        rows.sort_by(|a, b| {
+            let hotspot_index = |row: &DiffRow| {
+                row.delta(&Column::HotspotIndex)
+                    .or_else(|| row.values(&Column::HotspotIndex).1)
+                    .unwrap_or_default()
+                    .abs()
+            };
+
+            a.status
+                .cmp(&b.status)
+                .then_with(|| hotspot_index(b).total_cmp(&hotspot_index(a)))
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.function.cmp(&b.function))
+        });
+
+        Diff { rows }
+    }
+
+    /// Only the rows which changed since the baseline.
+    pub fn changed(mut self) -> Diff {
+        self.rows.retain(|row| row.status != DiffStatus::Unchanged);
+        self
+    }
+}
+
+/// Read a snapshot written with `--output ndjson`, one row per line, the
+/// other records such as the skipped files being ignored.
+pub fn load_snapshot(path: &Path) -> Result<Vec<HotspotStats>> {
+    let content = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut stats = vec![];
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let invalid = |error: serde_json::Error| Error::Snapshot {
+            path: path.to_path_buf(),
+            message: format!("line {}: {error}", index + 1),
+        };
+        let value = serde_json::from_str::<serde_json::Value>(line).map_err(invalid)?;
+
+        if value.get("record").is_none() {
+            stats.push(serde_json::from_value(value).map_err(invalid)?);
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Key matching the same file or function across analyses.
+fn row_key(stat: &HotspotStats) -> (String, Option<String>) {
+    (stat.path.clone(), stat.function.clone())
+}
+
+/// Rank of each row by hotspot index among the rows of the same kind, the
+/// files, the functions or the directories, starting from 1.
+fn ranks(stats: &[HotspotStats]) -> HashMap<(String, Option<String>), i64> {
+    let sorted = sort_stats_by(stats.to_vec(), &[SortKey::new(SortBy::HotspotIndex)]);
+    let mut counts = HashMap::new();
+
+    sorted
+        .iter()
+        .map(|stat| {
+            let kind = (
+                stat.path_type == PathType::Directory,
+                stat.function.is_some(),
+            );
+            let count = counts.entry(kind).or_insert(0);
+            *count += 1;
+
+            (row_key(stat), *count)
+        })
+        .collect()
+}
+
+/// Set the places each row moved up the ranking by hotspot index and the
+/// change of its hotspot index since the baseline, the rows not in the
+/// baseline having none.
+pub fn set_baseline_deltas(stats: &mut [HotspotStats], baseline: &[HotspotStats]) {
+    let before_ranks = ranks(baseline);
+    let after_ranks = ranks(stats);
+    let before = baseline
+        .iter()
+        .map(|stat| (row_key(stat), stat.hotspot_index))
+        .collect::<HashMap<_, _>>();
+
+    for stat in stats.iter_mut() {
+        let key = row_key(stat);
+
+        stat.rank_delta = before_ranks
+            .get(&key)
+            .map(|before| before - after_ranks[&key]);
+        stat.hotspot_index_delta = before.get(&key).map(|before| stat.hotspot_index - before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(path: &str, hotspot_index: f64, maintainability_index: f64) -> HotspotStats {
+        HotspotStats {
+            path: path.to_string(),
+            hotspot_index,
+            maintainability_index,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_new() {
+        // ARRANGE
+        let baseline = vec![
+            stat("improved.py", 4.0, 50.0),
+            stat("regressed.py", 1.0, 80.0),
+            stat("unchanged.py", 1.0, 80.0),
+            stat("removed.py", 3.0, 60.0),
+            stat("split.py", 2.0, 60.0),
+        ];
+        let current = vec![
+            stat("improved.py", 2.0, 60.0),
+            stat("regressed.py", 3.0, 70.0),
+            stat("unchanged.py", 1.0, 80.0),
+            stat("new.py", 1.0, 90.0),
+            stat("split.py", 2.0, 50.0),
+        ];
+
+        // ACT
+        let actual = Diff::new(baseline, current)
+            .rows
+            .into_iter()
+            .map(|row| (row.path, row.status))
+            .collect::<Vec<_>>();
+
+        // ASSERT
+        let expected = vec![
+            ("regressed.py".to_string(), DiffStatus::Regressed),
+            ("split.py".to_string(), DiffStatus::Regressed),
+            ("new.py".to_string(), DiffStatus::New),
+            ("improved.py".to_string(), DiffStatus::Improved),
+            ("removed.py".to_string(), DiffStatus::Removed),
+            ("unchanged.py".to_string(), DiffStatus::Unchanged),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_load_snapshot() {
+        // ARRANGE
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("snapshot.ndjson");
+        let stats = vec![stat("a.py", 1.5, 80.0), stat("b.py", 2.0, 70.0)];
+        let lines = stats
+            .iter()
+            .map(|stat| serde_json::to_string(stat).unwrap())
+            .collect::<Vec<_>>();
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        // ACT
+        let actual = load_snapshot(&path).unwrap();
+
+        // ASSERT
+        assert_eq!(actual, stats);
+    }
+
+    #[test]
+    fn test_set_baseline_deltas() {
+        // ARRANGE
+        let baseline = vec![
+            stat("a.py", 3.0, 50.0),
+            stat("b.py", 2.0, 60.0),
+            stat("c.py", 1.0, 70.0),
+        ];
+        let function = HotspotStats {
+            function: Some("f".to_string()),
+            ..stat("a.py", 9.0, 40.0)
+        };
+        let mut current = vec![
+            stat("a.py", 1.5, 60.0),
+            stat("b.py", 2.0, 60.0),
+            stat("c.py", 4.0, 50.0),
+            stat("d.py", 5.0, 40.0),
+            function,
+        ];
+
+        // ACT
+        set_baseline_deltas(&mut current, &baseline);
+
+        // ASSERT
+        let actual = current
+            .iter()
+            .map(|stat| (stat.rank_delta, stat.hotspot_index_delta))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (Some(-3), Some(-1.5)),
+            (Some(-1), Some(0.0)),
+            (Some(1), Some(3.0)),
+            (None, None),
+            (None, None),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{builder::PossibleValue, ValueEnum};
+use tracing::{debug, info, trace, warn};
+
+use crate::error::{Error, Result};
+use crate::git;
+
+/// Directories of virtual environments, dependencies, build outputs and
+/// bytecode, skipped anywhere below the root unless disabled.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    ".venv",
+    "venv",
+    "node_modules",
+    "build",
+    "dist",
+    ".tox",
+    "__pycache__",
+];
+
+/// Which files of a directory are analysed.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct Discovery {
+    root: PathBuf,
+    excludes: Vec<PathBuf>,
+    files: Option<Vec<PathBuf>>,
+    follow_symlinks: bool,
+    stubs: StubFiles,
+    default_excludes: bool,
+    tracked_only: bool,
+    include_untracked: bool,
+}
+
+impl Discovery {
+    pub fn new(root: &Path, excludes: &[PathBuf], files: Option<Vec<PathBuf>>) -> Self {
+        Discovery {
+            root: root.to_path_buf(),
+            excludes: excludes.to_vec(),
+            files,
+            follow_symlinks: false,
+            stubs: StubFiles::default(),
+            default_excludes: true,
+            tracked_only: false,
+            include_untracked: false,
+        }
+    }
+
+    /// Walk the symbolic links to the files and directories inside the root
+    /// instead of skipping them.
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    pub fn with_stubs(mut self, stubs: StubFiles) -> Self {
+        self.stubs = stubs;
+        self
+    }
+
+    /// Skip the directories of `DEFAULT_EXCLUDES`, enabled by default.
+    pub fn with_default_excludes(mut self, enabled: bool) -> Self {
+        self.default_excludes = enabled;
+        self
+    }
+
+    /// List the files tracked by git instead of walking the directory.
+    pub fn with_tracked_only(mut self, enabled: bool) -> Self {
+        self.tracked_only = enabled;
+        self
+    }
+
+    /// Add the untracked files which are not ignored to the tracked ones,
+    /// so new files without history yet are analysed.
+    pub fn with_include_untracked(mut self, enabled: bool) -> Self {
+        self.include_untracked = enabled;
+        self
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.excludes
+            .iter()
+            .any(|exclude| path.starts_with(exclude))
+            || (self.default_excludes && is_default_excluded(path, &self.root))
+    }
+
+    /// Whether a path, discovered or not, would be part of the analysis.
+    pub fn is_analysed(&self, path: &Path) -> bool {
+        path.starts_with(&self.root) && !self.is_excluded(path) && self.stubs.is_analysed(path)
+    }
+
+    /// Walk the directory lazily, or the explicit list of files when given,
+    /// or the files tracked by git when restricted to them.
+    pub fn files(self) -> Files {
+        let mut errors = vec![];
+        let paths_to_visit = match self.files {
+            // Explicit file lists are never expanded into directories
+            Some(files) => files
+                .into_iter()
+                .filter(|path| {
+                    let inside = path.starts_with(&self.root);
+                    if !inside {
+                        warn!(
+                            path = %path.display(),
+                            reason = %format_args!("outside of {}", self.root.display()),
+                            "Skipping the file"
+                        );
+                    }
+
+                    inside && !path.is_dir()
+                })
+                .collect(),
+            // Files deleted from the working tree but not from the index,
+            // and submodules, are left out
+            None if self.tracked_only => {
+                match git::tracked_files(&self.root, self.include_untracked) {
+                    Ok(files) => files.into_iter().filter(|path| path.is_file()).collect(),
+                    Err(error) => {
+                        errors.push(error);
+                        vec![]
+                    }
+                }
+            }
+            None => vec![self.root.clone()],
+        };
+
+        Files {
+            paths_to_visit,
+            root: self.root,
+            excludes: self.excludes,
+            follow_symlinks: self.follow_symlinks,
+            stubs: self.stubs,
+            default_excludes: self.default_excludes,
+            visited: HashSet::new(),
+            errors,
+        }
+    }
+}
+
+/// Whether a directory of `DEFAULT_EXCLUDES` is part of the path below the
+/// root.
+fn is_default_excluded(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .any(|component| {
+            component
+                .as_os_str()
+                .to_str()
+                .is_some_and(|name| DEFAULT_EXCLUDES.contains(&name))
+        })
+}
+
+/// Whether the `.pyi` stub files are analysed along the `.py` modules,
+/// skipped, or analysed alone to report them separately.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StubFiles {
+    #[default]
+    Exclude,
+    Include,
+    Only,
+}
+
+impl StubFiles {
+    pub(crate) fn is_analysed(&self, path: &Path) -> bool {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("py") => *self != StubFiles::Only,
+            Some("pyi") => *self != StubFiles::Exclude,
+            _ => false,
+        }
+    }
+}
+
+impl ValueEnum for StubFiles {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[StubFiles::Exclude, StubFiles::Include, StubFiles::Only]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            StubFiles::Exclude => PossibleValue::new("exclude").help("Skip the stub files"),
+            StubFiles::Include => {
+                PossibleValue::new("include").help("Analyse the stub files with the modules")
+            }
+            StubFiles::Only => PossibleValue::new("only").help("Analyse the stub files alone"),
+        })
+    }
+}
+
+/// Whether the file holds tests as collected by pytest: `test_*.py`,
+/// `*_test.py` and `conftest.py` files, and any file in a `tests` or `test`
+/// directory.
+pub(crate) fn is_test_file(path: &Path) -> bool {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let in_test_directory = path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .any(|component| matches!(component.as_os_str().to_str(), Some("tests" | "test")));
+
+    file_name.starts_with("test_")
+        || file_name.ends_with("_test.py")
+        || file_name == "conftest.py"
+        || in_test_directory
+}
+
+/// The module a test file is named after, `m` for `test_m.py` and
+/// `m_test.py`, none for the other files.
+pub(crate) fn tested_module(path: &Path) -> Option<&str> {
+    let stem = path
+        .extension()
+        .filter(|extension| *extension == "py")
+        .and(path.file_stem())?
+        .to_str()?;
+
+    stem.strip_prefix("test_")
+        .or_else(|| stem.strip_suffix("_test"))
+        .filter(|module| !module.is_empty())
+}
+
+/// The name a module is imported as and the directory it is in, the package
+/// and its parent for an `__init__.py`.
+fn module_location(path: &Path) -> Option<(&str, &Path)> {
+    let parent = path.parent()?;
+
+    match path.file_stem()?.to_str()? {
+        "__init__" => Some((parent.file_name()?.to_str()?, parent.parent()?)),
+        stem => Some((stem, parent)),
+    }
+}
+
+/// The test files of each module among the given files.
+///
+/// A test file is matched to the modules it is named after, see
+/// [`tested_module`], which are near it: in the same directory, below the
+/// directory containing its `tests` directory, or in the same
+/// subdirectories as the test is below `tests`, e.g. `pkg/models.py` for
+/// `pkg/tests/test_models.py`, `tests/test_models.py` and
+/// `tests/pkg/test_models.py`. A test matching several modules, such as
+/// `tests/test_models.py` with a `models.py` in two packages, is left out.
+pub(crate) fn module_tests<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+) -> HashMap<&'a Path, Vec<&'a Path>> {
+    let (tests, modules): (Vec<_>, Vec<_>) = paths.into_iter().partition(|path| is_test_file(path));
+    let mut modules_by_name: HashMap<&str, Vec<(&Path, &Path)>> = HashMap::new();
+
+    for module in modules {
+        if let Some((name, directory)) = module_location(module) {
+            modules_by_name
+                .entry(name)
+                .or_default()
+                .push((module, directory));
+        }
+    }
+
+    let mut module_tests: HashMap<&Path, Vec<&Path>> = HashMap::new();
+
+    for test in tests {
+        let Some((name, test_directory)) = tested_module(test).zip(test.parent()) else {
+            continue;
+        };
+        let matches = modules_by_name
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|(_, directory)| is_near(test_directory, directory))
+            .collect::<Vec<_>>();
+
+        match matches.as_slice() {
+            [(module, _)] => module_tests.entry(module).or_default().push(test),
+            [] => {}
+            _ => debug!(
+                "Skipping the test {} matching several modules",
+                test.display()
+            ),
+        }
+    }
+
+    module_tests
+}
+
+/// Whether the tests of a directory may be the ones of the modules of
+/// another directory, see [`module_tests`].
+fn is_near(test_directory: &Path, module_directory: &Path) -> bool {
+    if test_directory == module_directory {
+        return true;
+    }
+
+    let test_components = test_directory.components().collect::<Vec<_>>();
+    let module_components = module_directory.components().collect::<Vec<_>>();
+    let Some(tests) = test_components
+        .iter()
+        .position(|component| matches!(component.as_os_str().to_str(), Some("tests" | "test")))
+    else {
+        return false;
+    };
+    // The directory containing the tests and the subdirectories below them
+    let (root, mirrored) = (&test_components[..tests], &test_components[tests + 1..]);
+
+    module_components.len() >= root.len() + mirrored.len()
+        && module_components.starts_with(root)
+        && module_components.ends_with(mirrored)
+}
+
+/// Iterator over the discovered Python files, depth first.
+///
+/// Symbolic links are skipped unless followed, in which case they are
+/// resolved to their canonical path so a file or directory reached through
+/// several links is only visited once and cycles end.
+pub(crate) struct Files {
+    paths_to_visit: Vec<PathBuf>,
+    root: PathBuf,
+    excludes: Vec<PathBuf>,
+    follow_symlinks: bool,
+    stubs: StubFiles,
+    default_excludes: bool,
+    /// Canonical paths of the directories and files already visited.
+    visited: HashSet<PathBuf>,
+    errors: Vec<Error>,
+}
+
+impl Files {
+    /// The canonical path of a symbolic link to follow, none to skip it.
+    fn resolve_symlink(&self, path: &Path) -> Option<PathBuf> {
+        if !self.follow_symlinks {
+            debug!("Skipping symbolic link {}", path.display());
+            return None;
+        }
+
+        match path.canonicalize() {
+            Ok(target) if target.starts_with(&self.root) => Some(target),
+            Ok(target) => {
+                info!(
+                    "Skipping symbolic link {} to {} outside of {}",
+                    path.display(),
+                    target.display(),
+                    self.root.display()
+                );
+                None
+            }
+            Err(e) => {
+                warn!(
+                    path = %path.display(),
+                    reason = %e,
+                    "Skipping the broken symbolic link"
+                );
+                None
+            }
+        }
+    }
+}
+
+impl Iterator for Files {
+    type Item = Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.errors.pop() {
+            return Some(Err(error));
+        }
+
+        while let Some(mut current_path) = self.paths_to_visit.pop() {
+            let is_symlink = fs::symlink_metadata(&current_path)
+                .is_ok_and(|metadata| metadata.file_type().is_symlink());
+
+            if is_symlink {
+                match self.resolve_symlink(&current_path) {
+                    Some(target) => current_path = target,
+                    None => continue,
+                }
+            }
+
+            if self
+                .excludes
+                .iter()
+                .any(|exclude| current_path.starts_with(exclude))
+            {
+                info!("Skipping excluded path {}", current_path.display());
+                continue;
+            }
+
+            if self.default_excludes && is_default_excluded(&current_path, &self.root) {
+                debug!("Skipping default excluded path {}", current_path.display());
+                continue;
+            }
+
+            // Paths below the canonical root are canonical unless reached
+            // through a link, which is resolved above
+            if !self.visited.insert(current_path.clone()) {
+                debug!("Skipping already visited {}", current_path.display());
+                continue;
+            }
+
+            match current_path.is_dir() {
+                true => {
+                    let entries = match current_path.read_dir() {
+                        Ok(entries) => entries,
+                        Err(source) => {
+                            return Some(Err(Error::discovered_io(current_path, source)))
+                        }
+                    };
+
+                    for entry in entries {
+                        match entry {
+                            Ok(entry) => self.paths_to_visit.push(entry.path()),
+                            Err(source) => self.errors.push(Error::Io {
+                                path: current_path.clone(),
+                                source,
+                            }),
+                        }
+                    }
+
+                    if let Some(error) = self.errors.pop() {
+                        return Some(Err(error));
+                    }
+                }
+                false if self.stubs.is_analysed(&current_path) => {
+                    debug!("Found {}", current_path.display());
+                    return Some(Ok(current_path));
+                }
+                _ => trace!("Ignoring non-Python file {}", current_path.display()),
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("tests/test_invoice.py", Some("invoice"))]
+    #[case("billing/invoice_test.py", Some("invoice"))]
+    #[case("tests/test_.py", None)]
+    #[case("tests/conftest.py", None)]
+    #[case("billing/invoice.py", None)]
+    fn test_tested_module(#[case] path: &str, #[case] expected: Option<&str>) {
+        // ACT
+        let actual = tested_module(Path::new(path));
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("pkg", "pkg", true)]
+    #[case("pkg/tests", "pkg", true)]
+    #[case("tests", "src/pkg", true)]
+    #[case("tests/pkg", "src/pkg", true)]
+    #[case("pkg_a/tests", "pkg_b", false)]
+    #[case("tests/pkg_a", "pkg_b", false)]
+    #[case("pkg_a", "pkg_b", false)]
+    fn test_is_near(
+        #[case] test_directory: &str,
+        #[case] module_directory: &str,
+        #[case] expected: bool,
+    ) {
+        // ACT
+        let actual = is_near(Path::new(test_directory), Path::new(module_directory));
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_module_tests() {
+        // ARRANGE
+        let paths = [
+            "pkg_a/models.py",
+            "pkg_a/tests/test_models.py",
+            "pkg_b/models.py",
+            "pkg_a/views.py",
+            "pkg_b/views.py",
+            "tests/test_views.py",
+            "billing/__init__.py",
+            "tests/test_billing.py",
+        ]
+        .map(Path::new);
+
+        // ACT
+        let actual = module_tests(paths);
+
+        // ASSERT
+        let expected = HashMap::from([
+            (
+                Path::new("pkg_a/models.py"),
+                vec![Path::new("pkg_a/tests/test_models.py")],
+            ),
+            (
+                Path::new("billing/__init__.py"),
+                vec![Path::new("tests/test_billing.py")],
+            ),
+        ]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("/build/repo/.venv/lib/site.py", true)]
+    #[case("/build/repo/pkg/__pycache__/a.py", true)]
+    #[case("/build/repo/pkg/builder.py", false)]
+    // The root itself may be inside an excluded directory
+    #[case("/build/repo/pkg/a.py", false)]
+    fn test_is_default_excluded(#[case] path: &str, #[case] expected: bool) {
+        // ACT
+        let actual = is_default_excluded(Path::new(path), Path::new("/build/repo"));
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(StubFiles::Exclude, "pkg/a.py", true)]
+    #[case(StubFiles::Exclude, "pkg/a.pyi", false)]
+    #[case(StubFiles::Include, "pkg/a.py", true)]
+    #[case(StubFiles::Include, "pkg/a.pyi", true)]
+    #[case(StubFiles::Only, "pkg/a.py", false)]
+    #[case(StubFiles::Only, "pkg/a.pyi", true)]
+    #[case(StubFiles::Include, "pkg/a.pyc", false)]
+    fn test_stub_files_is_analysed(
+        #[case] stubs: StubFiles,
+        #[case] path: &str,
+        #[case] expected: bool,
+    ) {
+        // ACT
+        let actual = stubs.is_analysed(Path::new(path));
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+}
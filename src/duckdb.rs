@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+
+use tempfile::Builder;
+
+use crate::columnar;
+use crate::error::{Error, Result};
+use crate::external::run_with_stdin;
+use crate::hotspot::HotspotStats;
+use crate::metadata::Metadata;
+use crate::sql::{quote_identifier, quote_literal};
+
+/// Append the rows to a table of a DuckDB database, created if missing, with
+/// the time and the commit of the run.
+pub fn append_to_duckdb(
+    database: &Path,
+    table: &str,
+    stats: &[HotspotStats],
+    metadata: &Metadata,
+) -> Result<()> {
+    // Removed when dropped, whether the `duckdb` CLI succeeded or not
+    let parquet = Builder::new()
+        .prefix("tech_debt_hotspot-")
+        .suffix(".parquet")
+        .tempfile()
+        .map_err(|source| Error::Io {
+            path: env::temp_dir(),
+            source,
+        })?;
+
+    columnar::write_parquet(parquet.as_file(), stats, HashMap::new())
+        .map_err(|error| Error::Output(error.into()))?;
+
+    let sql = append_sql(table, parquet.path(), metadata);
+
+    run_with_stdin("duckdb", [database], sql.as_bytes())
+}
+
+fn append_sql(table: &str, parquet: &Path, metadata: &Metadata) -> String {
+    let table = quote_identifier(table);
+    let select = format!(
+        "SELECT TIMESTAMPTZ {} AS run_at, {} AS \"commit\", * FROM read_parquet({})",
+        quote_literal(&metadata.timestamp),
+        metadata
+            .commit
+            .as_deref()
+            .map_or("NULL::VARCHAR".to_string(), quote_literal),
+        quote_literal(&parquet.display().to_string()),
+    );
+
+    format!(
+        "CREATE TABLE IF NOT EXISTS {table} AS {select} LIMIT 0;\n\
+         INSERT INTO {table} BY NAME {select};\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_append_sql() {
+        // ARRANGE
+        let metadata = Metadata {
+            commit: Some("abc".to_string()),
+            timestamp: "2024-05-31T12:00:00Z".to_string(),
+            ..Metadata::new(".".to_string(), None, None, 0, 0)
+        };
+
+        // ACT
+        let actual = append_sql("tech\"debt", Path::new("/tmp/it's.parquet"), &metadata);
+
+        // ASSERT
+        let select = "SELECT TIMESTAMPTZ '2024-05-31T12:00:00Z' AS run_at, 'abc' AS \"commit\", * FROM read_parquet('/tmp/it''s.parquet')";
+        let expected = format!(
+            "CREATE TABLE IF NOT EXISTS \"tech\"\"debt\" AS {select} LIMIT 0;\n\
+             INSERT INTO \"tech\"\"debt\" BY NAME {select};\n"
+        );
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_append_to_duckdb_removes_parquet() {
+        // ARRANGE
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database = temp_dir.path().join("missing").join("hotspots.duckdb");
+        let metadata = Metadata::new(".".to_string(), None, None, 0, 0);
+        let parquets = || {
+            fs::read_dir(env::temp_dir())
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with("tech_debt_hotspot-") && name.ends_with(".parquet"))
+                .collect::<Vec<_>>()
+        };
+
+        // ACT
+        let actual = append_to_duckdb(&database, "hotspots", &[], &metadata);
+
+        // ASSERT
+        assert!(actual.is_err());
+        assert!(parquets().is_empty());
+    }
+}
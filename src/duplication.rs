@@ -0,0 +1,193 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use rustpython_parser::lexer::lex;
+use rustpython_parser::source_code::LineIndex;
+use rustpython_parser::{Mode, Tok};
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Minimum number of consecutive tokens repeated for a block of code to be
+/// reported as duplicated.
+const MIN_TOKENS: usize = 50;
+
+/// Hash of a window of [`MIN_TOKENS`] tokens with the lines it spans.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Fingerprint {
+    pub hash: u64,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// The duplicated lines of a file and the files sharing them.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Duplicates<'a> {
+    lines: BTreeSet<u32>,
+    pub partners: BTreeSet<&'a Path>,
+}
+
+impl Duplicates<'_> {
+    /// Percentage of the `loc` lines of code between `start` and `end`
+    /// included which are duplicated.
+    pub fn percentage(&self, start: usize, end: usize, loc: u32) -> f64 {
+        let duplicated = self.lines.range(start as u32..=end as u32).count();
+
+        match loc {
+            0 => 0.0,
+            _ => (duplicated as f64 / loc as f64 * 100.0).min(100.0),
+        }
+    }
+}
+
+/// Fingerprints of every window of tokens of the source code, comments and
+/// layout being ignored, none when the source code cannot be tokenized.
+pub(crate) fn fingerprints(source_code: &str) -> Vec<Fingerprint> {
+    let line_index = LineIndex::from_source_text(source_code);
+    let line = |offset| {
+        line_index
+            .source_location(offset, source_code)
+            .row
+            .to_usize() as u32
+    };
+
+    let mut tokens = vec![];
+
+    for token in lex(source_code, Mode::Module) {
+        let Ok((token, range)) = token else {
+            return vec![];
+        };
+
+        if matches!(
+            token,
+            Tok::Comment(_)
+                | Tok::Newline
+                | Tok::NonLogicalNewline
+                | Tok::Indent
+                | Tok::Dedent
+                | Tok::EndOfFile
+        ) {
+            continue;
+        }
+
+        let hash = xxh3_64(format!("{token:?}").as_bytes());
+        tokens.push((hash, line(range.start()), line(range.end())));
+    }
+
+    tokens
+        .windows(MIN_TOKENS)
+        .map(|window| {
+            let hashes = window
+                .iter()
+                .flat_map(|(hash, _, _)| hash.to_le_bytes())
+                .collect::<Vec<_>>();
+
+            Fingerprint {
+                hash: xxh3_64(&hashes),
+                start_line: window[0].1,
+                end_line: window[MIN_TOKENS - 1].2,
+            }
+        })
+        .collect()
+}
+
+/// The duplicates of the files having at least one window of tokens repeated
+/// in themselves or in another file.
+pub(crate) fn detect<'a, I>(files: I) -> HashMap<&'a Path, Duplicates<'a>>
+where
+    I: IntoIterator<Item = (&'a Path, &'a [Fingerprint])>,
+{
+    let mut occurrences: HashMap<u64, Vec<(&Path, &Fingerprint)>> = HashMap::new();
+
+    for (path, fingerprints) in files {
+        for fingerprint in fingerprints {
+            occurrences
+                .entry(fingerprint.hash)
+                .or_default()
+                .push((path, fingerprint));
+        }
+    }
+
+    let mut duplicates: HashMap<&Path, Duplicates> = HashMap::new();
+
+    for occurrences in occurrences
+        .values()
+        .filter(|occurrences| occurrences.len() > 1)
+    {
+        for (path, fingerprint) in occurrences {
+            let file = duplicates.entry(path).or_default();
+
+            file.lines
+                .extend(fingerprint.start_line..=fingerprint.end_line);
+            file.partners.extend(
+                occurrences
+                    .iter()
+                    .map(|(partner, _)| *partner)
+                    .filter(|partner| partner != path),
+            );
+        }
+    }
+
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: &str = "def total(items):
+    result = 0
+    for item in items:
+        if item.price > 0 and item.quantity > 0:
+            result += item.price * item.quantity
+        else:
+            result -= item.discount
+    print(result, len(items))
+    return result
+";
+
+    #[test]
+    fn test_detect() {
+        // ARRANGE
+        let a = fingerprints(&format!("import os\n\n\n{BLOCK}"));
+        let b = fingerprints(&format!("{BLOCK}\n\ndef other():\n    return 1\n"));
+        let c = fingerprints("def other():\n    return 1\n");
+
+        // ACT
+        let actual = detect([
+            (Path::new("a.py"), a.as_slice()),
+            (Path::new("b.py"), b.as_slice()),
+            (Path::new("c.py"), c.as_slice()),
+        ]);
+
+        // ASSERT
+        let a = &actual[Path::new("a.py")];
+        let b = &actual[Path::new("b.py")];
+
+        assert_eq!(a.lines, (4..=12).collect());
+        assert_eq!(a.partners, BTreeSet::from([Path::new("b.py")]));
+        assert_eq!(b.lines, (1..=9).collect());
+        assert_eq!(b.percentage(1, 11, 10), 90.0);
+        assert!(!actual.contains_key(Path::new("c.py")));
+    }
+
+    #[test]
+    fn test_fingerprints_ignore_comments_and_layout() {
+        // ARRANGE
+        let commented = BLOCK.replace("    return result", "\n    # the total\n    return result");
+
+        // ACT
+        let actual = fingerprints(&commented)
+            .into_iter()
+            .map(|fingerprint| fingerprint.hash)
+            .collect::<Vec<_>>();
+
+        // ASSERT
+        let expected = fingerprints(BLOCK)
+            .into_iter()
+            .map(|fingerprint| fingerprint.hash)
+            .collect::<Vec<_>>();
+
+        assert!(!expected.is_empty());
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,142 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+
+/// Errors raised while collecting the hotspots.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{} is not a directory", .0.display())]
+    NotADirectory(PathBuf),
+
+    #[error("no Python files found in {}", .0.display())]
+    NoFiles(PathBuf),
+
+    #[error("{} is not inside a Git repository: {stderr}", .path.display())]
+    NotAGitRepository { path: PathBuf, stderr: String },
+
+    #[error("{} is not inside the Git repository {}", .path.display(), .git_base_path.display())]
+    OutsideGitRepository {
+        path: PathBuf,
+        git_base_path: PathBuf,
+    },
+
+    #[error("failed to execute git: {0}")]
+    GitExecution(#[source] io::Error),
+
+    #[error("git {command} failed with {status}: {stderr}")]
+    GitCommand {
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+
+    #[error("git output is not valid UTF-8: {0}")]
+    GitOutput(#[from] std::string::FromUtf8Error),
+
+    #[error("failed to execute {program}: {source}")]
+    ExternalExecution {
+        program: &'static str,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{program} failed with {status}: {stderr}")]
+    ExternalCommand {
+        program: &'static str,
+        status: ExitStatus,
+        stderr: String,
+    },
+
+    #[error("invalid connection string: {0}")]
+    InvalidDsn(String),
+
+    #[error("failed to email the report: {0}")]
+    Email(String),
+
+    #[error("failed to write the report: {0}")]
+    Output(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("invalid formula {formula:?}: {message}")]
+    InvalidFormula { formula: String, message: String },
+
+    #[error("invalid commit pattern {pattern:?}: {message}")]
+    InvalidCommitPattern { pattern: String, message: String },
+
+    #[error("invalid configuration {}: {message}", .path.display())]
+    Config { path: PathBuf, message: String },
+
+    #[error("invalid coverage report {}: {message}", .path.display())]
+    Coverage { path: PathBuf, message: String },
+
+    #[error("invalid snapshot {}: {message}", .path.display())]
+    Snapshot { path: PathBuf, message: String },
+
+    #[error("history {}: {message}", .path.display())]
+    History { path: PathBuf, message: String },
+
+    #[error("{} is larger than {limit} bytes ({size} bytes)", .path.display())]
+    TooLarge {
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+
+    #[error("{} was deleted during the analysis", .0.display())]
+    Deleted(PathBuf),
+
+    #[error("failed to parse {}: {message}", .path.display())]
+    Parse { path: PathBuf, message: String },
+
+    #[error("invalid plugin {}: {message}", .path.display())]
+    Plugin { path: PathBuf, message: String },
+
+    #[error("plugin {} failed on {}: {message}", .plugin.display(), .path.display())]
+    PluginFailed {
+        plugin: PathBuf,
+        path: PathBuf,
+        message: String,
+    },
+
+    #[error("{}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl Error {
+    /// The file the error is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::NotADirectory(path)
+            | Error::NoFiles(path)
+            | Error::Deleted(path)
+            | Error::NotAGitRepository { path, .. }
+            | Error::OutsideGitRepository { path, .. }
+            | Error::Config { path, .. }
+            | Error::Coverage { path, .. }
+            | Error::Snapshot { path, .. }
+            | Error::History { path, .. }
+            | Error::Parse { path, .. }
+            | Error::Plugin { path, .. }
+            | Error::PluginFailed { path, .. }
+            | Error::TooLarge { path, .. }
+            | Error::Io { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// An IO error on a discovered file or directory, which may have been
+    /// deleted since it was discovered in a busy working directory.
+    pub(crate) fn discovered_io(path: PathBuf, source: io::Error) -> Error {
+        match source.kind() {
+            io::ErrorKind::NotFound => Error::Deleted(path),
+            _ => Error::Io { path, source },
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
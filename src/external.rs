@@ -0,0 +1,89 @@
+use std::ffi::OsStr;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use tracing::debug;
+
+use crate::error::{Error, Result};
+
+/// Run a program with its input on stdin, the way the history is read with
+/// the git CLI.
+pub(crate) fn run_with_stdin<I, S>(program: &'static str, args: I, input: &[u8]) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    run_command_with_stdin(program, command(program, args), input)
+}
+
+/// Run a command set up by the caller, e.g. with its environment, with its
+/// input on stdin.
+pub(crate) fn run_command_with_stdin(
+    program: &'static str,
+    command: Command,
+    input: &[u8],
+) -> Result<()> {
+    execute(program, command, input, Stdio::null()).map(|_| ())
+}
+
+/// Run a program with its input on stdin and return its output, e.g. the
+/// result of a query.
+pub(crate) fn output_with_stdin<I, S>(
+    program: &'static str,
+    args: I,
+    input: &[u8],
+) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let stdout = execute(program, command(program, args), input, Stdio::piped())?;
+
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
+
+fn command<I, S>(program: &'static str, args: I) -> Command
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new(program);
+    command.args(args);
+
+    command
+}
+
+fn execute(
+    program: &'static str,
+    mut command: Command,
+    input: &[u8],
+    stdout: Stdio,
+) -> Result<Vec<u8>> {
+    command
+        .stdin(Stdio::piped())
+        .stdout(stdout)
+        .stderr(Stdio::piped());
+    // The arguments may hold the password of a connection string
+    debug!("Running {program}");
+
+    let execution = |source| Error::ExternalExecution { program, source };
+    let mut child = command.spawn().map_err(execution)?;
+    child
+        .stdin
+        .take()
+        .expect("the stdin is piped")
+        .write_all(input)
+        .map_err(execution)?;
+
+    let output = child.wait_with_output().map_err(execution)?;
+
+    if !output.status.success() {
+        return Err(Error::ExternalCommand {
+            program,
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(output.stdout)
+}
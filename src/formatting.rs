@@ -1,91 +1,1560 @@
+use std::collections::HashMap;
 use std::error::Error;
+use std::io::Write;
+use std::path::Path;
 
 use clap::builder::PossibleValue;
 use clap::ValueEnum;
 use csv::Writer;
-use tabled::settings::object::Segment;
-use tabled::settings::{Alignment, Modify, Style};
-use tabled::Table;
+use serde::Serialize;
+use tabled::builder::Builder;
+use tabled::settings::Style;
 
-use crate::hotspot::HotspotStats;
+use crate::columnar;
+use crate::columns::{Column, Distributions};
+use crate::config::Markers;
+use crate::dashboard;
+use crate::diff::{Diff, DiffRow};
+use crate::hotspot::{FunctionDetail, HotspotStats, PathType, Skipped};
+use crate::metadata::Metadata;
+use crate::overrides::Overrides;
+use crate::ownership::{AuthorOwnership, FileOwnership, Ownership};
+use crate::slack;
+use crate::split::Partition;
+use crate::sqlite::{HistoryPoint, Worsening};
+use crate::suppressions::Suppression;
+use crate::totals::Totals;
+
+/// Number of contributors listed for each file in the markdown ownership report.
+const MARKDOWN_CONTRIBUTORS: usize = 3;
+
+/// Maintainability index below which a file is critical by default.
+pub const CRITICAL_MAINTAINABILITY_INDEX: f64 = 20.0;
+
+const COMMENT_TOP: usize = 10;
+
+/// Below the 65536 characters allowed by GitHub.
+const COMMENT_MAX_LENGTH: usize = 60_000;
+
+pub(crate) const LOW_MAINTAINABILITY_INDEX: f64 = 10.0;
+
+/// The [shields.io endpoint](https://shields.io/badges/endpoint-badge) schema.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Badge {
+    schema_version: u8,
+    label: &'static str,
+    message: String,
+    color: &'static str,
+}
 
 #[derive(Clone, Copy)]
 pub enum OutputFormat {
     Markdown,
     Csv,
+    Ndjson,
+    Badge,
+    Comment,
+    Slack,
+    Html,
+    Arrow,
+    Parquet,
+    Postgres,
 }
 
 impl ValueEnum for OutputFormat {
     fn value_variants<'a>() -> &'a [Self] {
-        &[OutputFormat::Markdown, OutputFormat::Csv]
+        &[
+            OutputFormat::Markdown,
+            OutputFormat::Csv,
+            OutputFormat::Ndjson,
+            OutputFormat::Badge,
+            OutputFormat::Comment,
+            OutputFormat::Slack,
+            OutputFormat::Html,
+            OutputFormat::Arrow,
+            OutputFormat::Parquet,
+            OutputFormat::Postgres,
+        ]
     }
 
     fn to_possible_value(&self) -> Option<PossibleValue> {
         Some(match self {
             OutputFormat::Markdown => PossibleValue::new("markdown").help("Markdown format"),
             OutputFormat::Csv => PossibleValue::new("csv").help("CSV format"),
+            OutputFormat::Ndjson => {
+                PossibleValue::new("ndjson").help("Newline delimited JSON format")
+            }
+            OutputFormat::Badge => PossibleValue::new("badge")
+                .help("shields.io endpoint JSON counting the files of low maintainability"),
+            OutputFormat::Comment => PossibleValue::new("comment")
+                .help("Compact markdown sized for a pull request comment"),
+            OutputFormat::Slack => PossibleValue::new("slack")
+                .help("Slack Block Kit message of the top hotspots for a chat digest"),
+            OutputFormat::Html => PossibleValue::new("html").help(
+                "Self-contained dashboard with a sortable table, a scatter plot and a treemap",
+            ),
+            OutputFormat::Arrow => PossibleValue::new("arrow").help(
+                "Arrow IPC file, also known as Feather, with every column, binary to be redirected to a file",
+            ),
+            OutputFormat::Parquet => PossibleValue::new("parquet")
+                .help("Apache Parquet file with every column, binary to be redirected to a file"),
+            OutputFormat::Postgres => PossibleValue::new("postgres")
+                .help("Upsert every column into a table of the PostgreSQL database of --dsn"),
         })
     }
 }
 
+impl OutputFormat {
+    /// Extension of the files written in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Markdown | OutputFormat::Comment => "md",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Badge | OutputFormat::Slack => "json",
+            OutputFormat::Html => "html",
+            OutputFormat::Arrow => "arrow",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Postgres => "sql",
+        }
+    }
+
+    /// Media type of the reports in this format, e.g. when uploaded.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Markdown | OutputFormat::Comment => "text/markdown",
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Ndjson => "application/x-ndjson",
+            OutputFormat::Badge | OutputFormat::Slack => "application/json",
+            OutputFormat::Html => "text/html",
+            OutputFormat::Arrow => "application/vnd.apache.arrow.file",
+            OutputFormat::Parquet => "application/vnd.apache.parquet",
+            OutputFormat::Postgres => "application/sql",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportKind {
+    Hotspots,
+    Ownership,
+    /// The hotspots with no or low test protection, by decreasing risk.
+    Untested,
+}
+
+impl ValueEnum for ReportKind {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            ReportKind::Hotspots,
+            ReportKind::Ownership,
+            ReportKind::Untested,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ReportKind::Hotspots => PossibleValue::new("hotspots").help("Metrics of the hotspots"),
+            ReportKind::Ownership => PossibleValue::new("ownership")
+                .help("Contributors of the hotspots and what each author owns"),
+            ReportKind::Untested => PossibleValue::new("untested").help(
+                "Changed and complex files without tests or with a low coverage, by decreasing risk",
+            ),
+        })
+    }
+}
+
+/// A record of the NDJSON ownership report.
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum OwnershipRecord<'a> {
+    File(&'a FileOwnership),
+    Author(&'a AuthorOwnership),
+}
+
+/// A skipped file in the NDJSON hotspots report, after the rows.
+#[derive(Serialize)]
+#[serde(tag = "record", rename = "skipped")]
+struct SkippedRecord<'a> {
+    #[serde(flatten)]
+    skipped: &'a Skipped,
+}
+
+/// A file or a function suppressed by a comment in the NDJSON hotspots
+/// report, after the rows.
+#[derive(Serialize)]
+#[serde(tag = "record", rename = "suppressed")]
+struct SuppressedRecord<'a> {
+    #[serde(flatten)]
+    suppression: &'a Suppression,
+}
+
+/// The metadata of the run in the NDJSON hotspots report, before the rows.
+#[derive(Serialize)]
+#[serde(tag = "record", rename = "metadata")]
+struct MetadataRecord<'a> {
+    #[serde(flatten)]
+    metadata: &'a Metadata,
+}
+
+/// The totals of the project in the NDJSON hotspots report, before the rows.
+#[derive(Serialize)]
+#[serde(tag = "record", rename = "totals")]
+struct TotalsRecord<'a> {
+    #[serde(flatten)]
+    totals: &'a Totals,
+}
+
+/// A file row of the detailed NDJSON hotspots report with its functions.
+#[derive(Serialize)]
+struct DetailedRecord<'a> {
+    #[serde(flatten)]
+    stat: &'a HotspotStats,
+    functions: &'a [FunctionDetail],
+}
+
 pub struct Output {
     format: OutputFormat,
+    columns: Vec<Column>,
+    limit: Option<usize>,
+    baseline: HashMap<(String, Option<String>), HotspotStats>,
+    skipped: Vec<Skipped>,
+    suppressions: Vec<Suppression>,
+    details: Option<HashMap<String, Vec<FunctionDetail>>>,
+    metadata: Option<Metadata>,
+    totals: Option<Totals>,
+    markers: Option<Overrides<Markers>>,
+    thresholds: Markers,
+    long: bool,
 }
 
 impl Output {
     pub fn new(format: &OutputFormat) -> Self {
-        Output { format: *format }
+        Output {
+            format: *format,
+            columns: Column::FILE_DEFAULTS.to_vec(),
+            limit: None,
+            baseline: HashMap::new(),
+            skipped: vec![],
+            suppressions: vec![],
+            details: None,
+            metadata: None,
+            totals: None,
+            markers: None,
+            thresholds: Markers::default(),
+            long: false,
+        }
+    }
+
+    /// The columns of the markdown and CSV formats, JSON formats always
+    /// include every field.
+    pub fn with_columns(mut self, columns: &[Column]) -> Self {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    /// Only output the first rows, the normalized columns being still
+    /// computed over every row.
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Show the change of the metrics since the rows of a snapshot in the
+    /// comment format.
+    pub fn with_baseline(mut self, baseline: Vec<HotspotStats>) -> Self {
+        self.baseline = baseline
+            .into_iter()
+            .map(|stat| ((stat.path.clone(), stat.function.clone()), stat))
+            .collect();
+        self
+    }
+
+    /// List the skipped files after the rows of the NDJSON format.
+    pub fn with_skipped(mut self, skipped: Vec<Skipped>) -> Self {
+        self.skipped = skipped;
+        self
+    }
+
+    /// List the files and functions suppressed by comments after the rows
+    /// of the markdown and NDJSON formats.
+    pub fn with_suppressions(mut self, suppressions: Vec<Suppression>) -> Self {
+        self.suppressions = suppressions;
+        self
+    }
+
+    /// Nest the functions of each file, by path, under the file rows of the
+    /// NDJSON format.
+    pub fn with_details(mut self, details: HashMap<String, Vec<FunctionDetail>>) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Start the CSV, NDJSON and HTML formats with what the report was run
+    /// against.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Start the markdown, CSV and NDJSON formats with the totals of the
+    /// project, after the metadata.
+    pub fn with_totals(mut self, totals: Totals) -> Self {
+        self.totals = Some(totals);
+        self
+    }
+
+    /// Mark the maintainability index, hotspot index and cyclomatic
+    /// complexity of the markdown table with their severity, the thresholds
+    /// of the overrides applying to the rows with a path below their
+    /// directory.
+    pub fn with_markers(mut self, markers: impl Into<Overrides<Markers>>) -> Self {
+        self.markers = Some(markers.into());
+        self
+    }
+
+    /// The thresholds of the badge, the default markers unless set.
+    pub fn with_thresholds(mut self, thresholds: Markers) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Write one row per metric of each row in the CSV format, identified by
+    /// the path and the function, instead of one row with every metric.
+    pub fn with_long(mut self, long: bool) -> Self {
+        self.long = long;
+        self
     }
 
     pub fn format(&self, stats: &[HotspotStats]) -> Result<String, Box<dyn Error>> {
+        // The badge summarises every row, and the comment and the slack
+        // message count the rows after the limit
         match self.format {
-            OutputFormat::Markdown => Self::format_markdown(stats),
-            OutputFormat::Csv => Self::format_csv(stats),
+            OutputFormat::Badge => {
+                return Ok(serde_json::to_string(&badge(stats, &self.thresholds))?)
+            }
+            OutputFormat::Comment => return Ok(self.format_comment(stats)),
+            OutputFormat::Slack => {
+                let payload =
+                    slack::payload(stats, self.limit.unwrap_or(COMMENT_TOP), &self.baseline);
+
+                return Ok(serde_json::to_string(&payload)?);
+            }
+            _ => {}
+        }
+
+        let distributions = Distributions::new(&self.columns, stats);
+        let stats = &stats[..self.limit.unwrap_or(stats.len()).min(stats.len())];
+
+        match self.format {
+            OutputFormat::Markdown => self.format_markdown(stats, &distributions),
+            OutputFormat::Csv => self.format_csv(stats, &distributions),
+            OutputFormat::Ndjson => self.format_ndjson(stats),
+            OutputFormat::Html => Ok(dashboard::render(
+                &self.columns,
+                stats,
+                &distributions,
+                self.metadata.as_ref(),
+            )?),
+            OutputFormat::Badge | OutputFormat::Comment | OutputFormat::Slack => {
+                unreachable!("the badge, the comment and the slack message are formatted above")
+            }
+            OutputFormat::Arrow | OutputFormat::Parquet => {
+                Err("the arrow and parquet formats are binary and cannot be formatted".into())
+            }
+            OutputFormat::Postgres => {
+                Err("the postgres format is written to the database of --dsn".into())
+            }
         }
     }
 
-    fn format_csv(stats: &[HotspotStats]) -> Result<String, Box<dyn Error>> {
-        let mut writer = Writer::from_writer(vec![]);
+    /// Format the contributors of each file followed by the summary of the
+    /// authors, as two tables for markdown and CSV.
+    pub fn format_ownership(&self, ownership: &Ownership) -> Result<String, Box<dyn Error>> {
+        match self.format {
+            OutputFormat::Markdown => Ok(Self::format_ownership_markdown(ownership)),
+            OutputFormat::Csv => Self::format_ownership_csv(ownership),
+            OutputFormat::Ndjson => {
+                let files = ownership.files.iter().map(OwnershipRecord::File);
+                let authors = ownership.authors.iter().map(OwnershipRecord::Author);
+                let lines = files
+                    .chain(authors)
+                    .map(|record| serde_json::to_string(&record))
+                    .collect::<Result<Vec<_>, _>>()?;
 
-        writer
-            .write_record([
-                "path",
-                "halstead_volume",
+                Ok(lines.join("\n"))
+            }
+            OutputFormat::Badge
+            | OutputFormat::Comment
+            | OutputFormat::Slack
+            | OutputFormat::Html
+            | OutputFormat::Arrow
+            | OutputFormat::Parquet
+            | OutputFormat::Postgres => Err(
+                "the badge, comment, slack, html, arrow, parquet and postgres formats only support the hotspots"
+                    .into(),
+            ),
+        }
+    }
+
+    /// Format the rows of a diff with the value of each compared metric
+    /// before and after, and its delta.
+    pub fn format_diff(&self, diff: &Diff) -> Result<String, Box<dyn Error>> {
+        let rows = &diff.rows[..self.limit.unwrap_or(diff.rows.len()).min(diff.rows.len())];
+        let functions = rows.iter().any(|row| row.function.is_some());
+
+        match self.format {
+            OutputFormat::Markdown => Ok(Self::format_diff_markdown(rows, functions)),
+            OutputFormat::Csv => Self::format_diff_csv(rows, functions),
+            OutputFormat::Ndjson => {
+                let lines = rows
+                    .iter()
+                    .map(|row| serde_json::to_string(&diff_record(row)))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(lines.join("\n"))
+            }
+            OutputFormat::Badge
+            | OutputFormat::Comment
+            | OutputFormat::Slack
+            | OutputFormat::Html
+            | OutputFormat::Arrow
+            | OutputFormat::Parquet
+            | OutputFormat::Postgres => Err(
+                "the badge, comment, slack, html, arrow, parquet and postgres formats only support the hotspots"
+                    .into(),
+            ),
+        }
+    }
+
+    /// Format the metrics of a file or function in each recorded run.
+    pub fn format_history(&self, points: &[HistoryPoint]) -> Result<String, Box<dyn Error>> {
+        self.format_history_rows(
+            &[
+                "run_at",
+                "commit",
+                "hotspot_index",
+                "maintainability_index",
                 "cyclomatic_complexity",
                 "loc",
-                "comments_percentage",
-                "maintainability_index",
                 "changes_count",
-                "hotspot_index",
-            ])
-            .expect("Failed to write CSV header");
+            ],
+            points,
+            |point| {
+                vec![
+                    point.run_at.clone(),
+                    point.commit.clone().unwrap_or_default(),
+                    format_number(point.hotspot_index),
+                    format_number(point.maintainability_index),
+                    format_number(point.cyclomatic_complexity),
+                    format_number(point.loc),
+                    format_number(point.changes_count),
+                ]
+            },
+        )
+    }
 
-        for stat in stats {
-            writer
-                .write_record(&[
-                    stat.path.clone(),
-                    stat.halstead_volume.to_string(),
-                    stat.cyclomatic_complexity.to_string(),
-                    stat.loc.to_string(),
-                    stat.comments_percentage.to_string(),
-                    stat.maintainability_index.to_string(),
-                    stat.changes_count.to_string(),
-                    stat.hotspot_index.to_string(),
-                ])
-                .expect("Failed to write CSV record");
+    /// Format the rows whose hotspot index grew the most over the recorded
+    /// runs.
+    pub fn format_worst(&self, rows: &[Worsening]) -> Result<String, Box<dyn Error>> {
+        self.format_history_rows(
+            &[
+                "path",
+                "function",
+                "runs",
+                "hotspot_index_before",
+                "hotspot_index_after",
+                "hotspot_index_delta",
+            ],
+            rows,
+            |row| {
+                vec![
+                    row.path.clone(),
+                    row.function.clone().unwrap_or_default(),
+                    row.runs.to_string(),
+                    format_number(Some(row.hotspot_index_before)),
+                    format_number(Some(row.hotspot_index_after)),
+                    format_delta(Some(row.hotspot_index_delta())),
+                ]
+            },
+        )
+    }
+
+    fn format_history_rows<T: Serialize>(
+        &self,
+        header: &[&str],
+        rows: &[T],
+        record: impl Fn(&T) -> Vec<String>,
+    ) -> Result<String, Box<dyn Error>> {
+        let rows = &rows[..self.limit.unwrap_or(rows.len()).min(rows.len())];
+
+        match self.format {
+            OutputFormat::Markdown => {
+                let mut builder = Builder::default();
+                builder.push_record(header.iter().copied());
+
+                for row in rows {
+                    builder.push_record(record(row));
+                }
+
+                Ok(builder.build().with(Style::markdown()).to_string())
+            }
+            OutputFormat::Csv => {
+                let mut writer = Writer::from_writer(vec![]);
+                writer.write_record(header)?;
+
+                for row in rows {
+                    writer.write_record(record(row))?;
+                }
+
+                Ok(String::from_utf8(writer.into_inner()?)?)
+            }
+            OutputFormat::Ndjson => {
+                let lines = rows
+                    .iter()
+                    .map(serde_json::to_string)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(lines.join("\n"))
+            }
+            OutputFormat::Badge
+            | OutputFormat::Comment
+            | OutputFormat::Slack
+            | OutputFormat::Html
+            | OutputFormat::Arrow
+            | OutputFormat::Parquet
+            | OutputFormat::Postgres => {
+                Err("the history only supports the markdown, csv and ndjson formats".into())
+            }
+        }
+    }
+
+    /// Write the rows in the format, the text formats being followed by a
+    /// newline and the arrow and parquet formats storing the metadata and the
+    /// totals as key-value metadata of the file.
+    pub fn write<W: Write + Send>(
+        &self,
+        stats: &[HotspotStats],
+        mut writer: W,
+    ) -> Result<(), Box<dyn Error>> {
+        if !matches!(self.format, OutputFormat::Arrow | OutputFormat::Parquet) {
+            writeln!(writer, "{}", self.format(stats)?)?;
+
+            return Ok(());
+        }
+
+        let stats = &stats[..self.limit.unwrap_or(stats.len()).min(stats.len())];
+        let metadata = self
+            .metadata
+            .iter()
+            .flat_map(|metadata| metadata.fields())
+            .map(|(name, value)| (name.to_string(), value))
+            .chain(
+                self.totals
+                    .iter()
+                    .flat_map(|totals| totals.fields())
+                    .map(|(name, value)| (format!("total_{name}"), value)),
+            )
+            .collect();
+
+        match self.format {
+            OutputFormat::Arrow => columnar::write_arrow(writer, stats, metadata)?,
+            _ => columnar::write_parquet(writer, stats, metadata)?,
+        }
+
+        Ok(())
+    }
+
+    /// A compact table of the first rows followed by the others in a
+    /// collapsed section, truncated to fit in a comment.
+    fn format_comment(&self, stats: &[HotspotStats]) -> String {
+        let top = self.limit.unwrap_or(COMMENT_TOP).min(stats.len());
+        let functions = stats.iter().any(|stat| stat.function.is_some());
+        let header = comment_header(functions);
+
+        let mut output = format!("### Tech debt hotspots\n\n{header}");
+
+        for stat in &stats[..top] {
+            output.push_str(&self.comment_row(stat, functions));
+        }
+
+        let others = &stats[top..];
+
+        if others.is_empty() {
+            return output;
+        }
+
+        let footer = "\n</details>\n";
+        let mut details = format!(
+            "\n<details>\n<summary>{} more rows</summary>\n\n{header}",
+            others.len()
+        );
+
+        for (index, stat) in others.iter().enumerate() {
+            let row = self.comment_row(stat, functions);
+            let truncated = format!("\n_{} rows omitted._\n", others.len() - index);
+
+            if output.len() + details.len() + row.len() + truncated.len() + footer.len()
+                > COMMENT_MAX_LENGTH
+            {
+                details.push_str(&truncated);
+                break;
+            }
+
+            details.push_str(&row);
+        }
+
+        format!("{output}{details}{footer}")
+    }
+
+    fn comment_row(&self, stat: &HotspotStats, functions: bool) -> String {
+        let before = self
+            .baseline
+            .get(&(stat.path.clone(), stat.function.clone()));
+        let mut cells = vec![format!("`{}`", stat.path)];
+
+        if before.is_none() && !self.baseline.is_empty() {
+            cells[0].push_str(" 🆕");
+        }
+
+        if functions {
+            cells.push(stat.function.clone().unwrap_or_default());
+        }
+
+        for column in Diff::COLUMNS {
+            let value = column.metric(stat);
+            let arrow = before
+                .map(|before| format_arrow(column.metric(before), value))
+                .unwrap_or_default();
+
+            cells.push(format!("{}{arrow}", format_number(value)));
+        }
+
+        format!("| {} |\n", cells.join(" | "))
+    }
+
+    fn format_diff_markdown(rows: &[DiffRow], functions: bool) -> String {
+        let mut builder = Builder::default();
+        builder.push_record(diff_header(functions, |column| {
+            vec![column.name().into_owned()]
+        }));
+
+        for row in rows {
+            let values = Diff::COLUMNS.iter().map(|column| {
+                let (before, after) = row.values(column);
+
+                match row.delta(column) {
+                    Some(delta) => format!(
+                        "{} → {} ({})",
+                        format_number(before),
+                        format_number(after),
+                        format_delta(Some(delta))
+                    ),
+                    None => format_number(after.or(before)),
+                }
+            });
+
+            builder.push_record(diff_keys(row, functions).chain(values));
+        }
+
+        let mut table = builder.build();
+        table.with(Style::markdown());
+
+        table.to_string()
+    }
+
+    fn format_diff_csv(rows: &[DiffRow], functions: bool) -> Result<String, Box<dyn Error>> {
+        let mut writer = Writer::from_writer(vec![]);
+        writer.write_record(diff_header(functions, |column| {
+            ["before", "after", "delta"]
+                .iter()
+                .map(|suffix| format!("{}_{suffix}", column.name()))
+                .collect()
+        }))?;
+
+        for row in rows {
+            let values = Diff::COLUMNS.iter().flat_map(|column| {
+                let (before, after) = row.values(column);
+
+                [
+                    format_number(before),
+                    format_number(after),
+                    format_delta(row.delta(column)),
+                ]
+            });
+
+            writer.write_record(diff_keys(row, functions).chain(values))?;
+        }
+
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    /// Format the index of the reports of the partitions, linking to the
+    /// report of each partition in the markdown format.
+    pub fn format_index(&self, partitions: &[Partition]) -> Result<String, Box<dyn Error>> {
+        let report =
+            |partition: &Partition| format!("{}.{}", partition.name, self.format.extension());
+
+        match self.format {
+            OutputFormat::Markdown | OutputFormat::Comment => {
+                let mut builder = Builder::default();
+                builder.push_record(PARTITION_HEADER);
+
+                for partition in partitions {
+                    let mut record = partition_record(partition);
+                    record[0] = format!("[{}]({})", partition.name, report(partition));
+                    builder.push_record(record);
+                }
+
+                Ok(builder.build().with(Style::markdown()).to_string())
+            }
+            OutputFormat::Html => {
+                let header = PARTITION_HEADER
+                    .iter()
+                    .map(|name| format!("<th>{name}</th>"))
+                    .collect::<String>();
+                let rows = partitions
+                    .iter()
+                    .map(|partition| {
+                        let mut record = partition_record(partition).map(|cell| escape_html(&cell));
+                        record[0] = format!(
+                            "<a href=\"{}\">{}</a>",
+                            escape_html(&report(partition)),
+                            record[0]
+                        );
+                        let cells = record
+                            .iter()
+                            .map(|cell| format!("<td>{cell}</td>"))
+                            .collect::<String>();
+
+                        format!("<tr>{cells}</tr>\n")
+                    })
+                    .collect::<String>();
+
+                Ok(format!(
+                    "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Tech debt hotspots</title>\n</head>\n<body>\n<table>\n<tr>{header}</tr>\n{rows}</table>\n</body>\n</html>"
+                ))
+            }
+            OutputFormat::Csv => {
+                let mut writer = Writer::from_writer(vec![]);
+                writer.write_record(PARTITION_HEADER.iter().chain(&["report"]))?;
+
+                for partition in partitions {
+                    let record = partition_record(partition);
+                    writer.write_record(record.iter().chain([&report(partition)]))?;
+                }
+
+                Ok(String::from_utf8(writer.into_inner()?)?)
+            }
+            OutputFormat::Ndjson
+            | OutputFormat::Badge
+            | OutputFormat::Slack
+            | OutputFormat::Arrow
+            | OutputFormat::Parquet
+            | OutputFormat::Postgres => {
+                let lines = partitions
+                    .iter()
+                    .map(|partition| {
+                        let mut record = serde_json::to_value(partition)?;
+                        record["report"] = report(partition).into();
+
+                        serde_json::to_string(&record)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(lines.join("\n"))
+            }
+        }
+    }
+
+    fn format_ownership_markdown(ownership: &Ownership) -> String {
+        let mut files = Builder::default();
+        files.push_record(["path", "hotspot_index", "contributors"]);
+
+        for file in &ownership.files {
+            let contributors = file
+                .contributors
+                .iter()
+                .take(MARKDOWN_CONTRIBUTORS)
+                .map(|contribution| format!("{} ({:.0}%)", contribution.author, contribution.share))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            files.push_record([
+                file.path.clone(),
+                file.hotspot_index.to_string(),
+                contributors,
+            ]);
+        }
+
+        let mut authors = Builder::default();
+        authors.push_record(AUTHOR_HEADER);
+
+        for author in &ownership.authors {
+            authors.push_record(author_record(author));
+        }
+
+        format!(
+            "## Contributors\n\n{}\n\n## Authors\n\n{}",
+            files.build().with(Style::markdown()),
+            authors.build().with(Style::markdown())
+        )
+    }
+
+    fn format_ownership_csv(ownership: &Ownership) -> Result<String, Box<dyn Error>> {
+        let mut files = Writer::from_writer(vec![]);
+        files.write_record(["path", "hotspot_index", "author", "changes_count", "share"])?;
+
+        for file in &ownership.files {
+            for contribution in &file.contributors {
+                files.write_record([
+                    file.path.clone(),
+                    file.hotspot_index.to_string(),
+                    contribution.author.clone(),
+                    contribution.changes_count.to_string(),
+                    contribution.share.to_string(),
+                ])?;
+            }
+        }
+
+        let mut authors = Writer::from_writer(vec![]);
+        authors.write_record(AUTHOR_HEADER)?;
+
+        for author in &ownership.authors {
+            authors.write_record(author_record(author))?;
+        }
+
+        Ok(format!(
+            "{}\n{}",
+            String::from_utf8(files.into_inner()?)?,
+            String::from_utf8(authors.into_inner()?)?
+        ))
+    }
+
+    fn format_csv(
+        &self,
+        stats: &[HotspotStats],
+        distributions: &Distributions,
+    ) -> Result<String, Box<dyn Error>> {
+        // Comment lines before the header, which most CSV readers can skip
+        let mut output = self
+            .metadata
+            .iter()
+            .flat_map(Metadata::fields)
+            .map(|(name, value)| format!("# {name}: {value}\n"))
+            .chain(
+                self.totals
+                    .iter()
+                    .flat_map(Totals::fields)
+                    .map(|(name, value)| format!("# total_{name}: {value}\n")),
+            )
+            .collect::<String>();
+        let mut writer = Writer::from_writer(vec![]);
+
+        if self.long {
+            self.write_csv_long(&mut writer, stats, distributions)?;
+        } else {
+            write_csv_header(&mut writer, &self.columns)?;
+
+            for stat in stats {
+                write_csv_record(&mut writer, &self.columns, distributions, stat)?;
+            }
         }
 
         writer.flush()?;
 
-        let output = String::from_utf8(writer.into_inner()?)?;
+        output.push_str(&String::from_utf8(writer.into_inner()?)?);
 
         Ok(output)
     }
 
-    fn format_markdown(stats: &[HotspotStats]) -> Result<String, Box<dyn Error>> {
-        Ok(Table::new(stats)
-            .with(Style::markdown())
-            .with(Modify::new(Segment::new(1.., 2..)).with(Alignment::right()))
-            .to_string())
+    /// The tidy shape of the CSV format: the identifying columns among the
+    /// path and the function, then the name and the value of each metric,
+    /// the unknown values being left out.
+    fn write_csv_long<W: Write>(
+        &self,
+        writer: &mut Writer<W>,
+        stats: &[HotspotStats],
+        distributions: &Distributions,
+    ) -> Result<(), Box<dyn Error>> {
+        let (keys, metrics): (Vec<&Column>, Vec<&Column>) = self
+            .columns
+            .iter()
+            .filter(|column| {
+                matches!(column, Column::Path | Column::Function)
+                    || column.is_metric()
+                    || column.is_normalized()
+            })
+            .partition(|column| matches!(column, Column::Path | Column::Function));
+
+        writer.write_record(
+            keys.iter()
+                .map(|column| column.name().into_owned())
+                .chain(["metric".to_string(), "value".to_string()]),
+        )?;
+
+        for stat in stats {
+            for metric in &metrics {
+                let value = distributions.value(metric, stat);
+
+                if value.is_empty() {
+                    continue;
+                }
+
+                writer.write_record(
+                    keys.iter()
+                        .map(|column| column.value(stat))
+                        .chain([metric.name().into_owned(), value]),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn format_ndjson(&self, stats: &[HotspotStats]) -> Result<String, Box<dyn Error>> {
+        let mut lines = self
+            .metadata
+            .iter()
+            .map(|metadata| serde_json::to_string(&MetadataRecord { metadata }))
+            .chain(
+                self.totals
+                    .iter()
+                    .map(|totals| serde_json::to_string(&TotalsRecord { totals })),
+            )
+            .chain(stats.iter().map(|stat| self.ndjson_row(stat)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for suppression in &self.suppressions {
+            lines.push(serde_json::to_string(&SuppressedRecord { suppression })?);
+        }
+        for skipped in &self.skipped {
+            lines.push(serde_json::to_string(&SkippedRecord { skipped })?);
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    fn ndjson_row(&self, stat: &HotspotStats) -> serde_json::Result<String> {
+        let functions = match &self.details {
+            Some(details) if stat.path_type == PathType::File && stat.function.is_none() => {
+                details.get(&stat.path)
+            }
+            _ => None,
+        };
+
+        match functions {
+            Some(functions) => serde_json::to_string(&DetailedRecord { stat, functions }),
+            None => serde_json::to_string(stat),
+        }
+    }
+
+    fn format_markdown(
+        &self,
+        stats: &[HotspotStats],
+        distributions: &Distributions,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut builder = Builder::default();
+
+        builder.push_record(self.columns.iter().map(Column::name));
+
+        for stat in stats {
+            builder.push_record(self.columns.iter().map(|column| {
+                let value = distributions.value(column, stat);
+
+                match self
+                    .markers
+                    .as_ref()
+                    .and_then(|markers| markers.get(Path::new(&stat.path)).marker(column, stat))
+                {
+                    Some(marker) => format!("{marker} {value}"),
+                    None => value,
+                }
+            }));
+        }
+
+        let mut table = builder.build();
+        table.with(Style::markdown());
+
+        let mut markdown = match &self.totals {
+            Some(totals) => {
+                let (names, values): (Vec<_>, Vec<_>) = totals.fields().into_iter().unzip();
+                let mut builder = Builder::default();
+                builder.push_record(names);
+                builder.push_record(values);
+
+                let mut totals_table = builder.build();
+                totals_table.with(Style::markdown());
+
+                format!("{totals_table}\n\n{table}")
+            }
+            None => table.to_string(),
+        };
+
+        if !self.suppressions.is_empty() {
+            markdown.push_str("\n\nSuppressed:\n");
+
+            for suppression in &self.suppressions {
+                markdown.push_str(&format!("\n- {}", format_suppression(suppression)));
+            }
+        }
+
+        Ok(markdown)
+    }
+}
+
+/// A suppressed file or function as an item of the markdown list, e.g.
+/// `` `a.py` `parse` (line 12): deprecated ``.
+fn format_suppression(suppression: &Suppression) -> String {
+    let mut item = format!("`{}`", suppression.path);
+
+    if let Some(function) = &suppression.function {
+        item.push_str(&format!(" `{function}`"));
+    }
+    item.push_str(&format!(" (line {})", suppression.line));
+    if let Some(reason) = &suppression.reason {
+        item.push_str(&format!(": {reason}"));
+    }
+
+    item
+}
+
+/// An arrow pointing up or down with the change of a metric, nothing when
+/// the change is lost in the rounding.
+pub(crate) fn format_arrow(before: Option<f64>, after: Option<f64>) -> String {
+    let Some(delta) = after.zip(before).map(|(after, before)| after - before) else {
+        return String::new();
+    };
+
+    match format_number(Some(delta.abs())).as_str() {
+        "0" => String::new(),
+        change if delta > 0.0 => format!(" ▲ {change}"),
+        change => format!(" ▼ {change}"),
+    }
+}
+
+fn comment_header(functions: bool) -> String {
+    let mut names = vec!["path".to_string()];
+
+    if functions {
+        names.push("function".to_string());
+    }
+
+    names.extend(
+        Diff::COLUMNS
+            .iter()
+            .map(|column| column.name().into_owned()),
+    );
+
+    format!(
+        "| {} |\n|{}\n",
+        names.join(" | "),
+        "---|".repeat(names.len())
+    )
+}
+
+fn diff_header(functions: bool, metric: impl Fn(&Column) -> Vec<String>) -> Vec<String> {
+    let mut header = vec!["path".to_string()];
+
+    if functions {
+        header.push("function".to_string());
+    }
+
+    header.push("status".to_string());
+    header.extend(Diff::COLUMNS.iter().flat_map(metric));
+    header
+}
+
+fn diff_keys(row: &DiffRow, functions: bool) -> impl Iterator<Item = String> {
+    let function = functions.then(|| row.function.clone().unwrap_or_default());
+
+    [row.path.clone()]
+        .into_iter()
+        .chain(function)
+        .chain([row.status.name().to_string()])
+}
+
+fn diff_record(row: &DiffRow) -> serde_json::Value {
+    let mut record = serde_json::Map::new();
+    record.insert("path".into(), row.path.clone().into());
+
+    if let Some(function) = &row.function {
+        record.insert("function".into(), function.clone().into());
+    }
+
+    record.insert("status".into(), row.status.name().into());
+
+    for column in Diff::COLUMNS {
+        let (before, after) = row.values(column);
+        record.insert(
+            column.name().into_owned(),
+            serde_json::json!({
+                "before": before,
+                "after": after,
+                "delta": row.delta(column),
+            }),
+        );
+    }
+
+    record.into()
+}
+
+/// Round to two decimals so the deltas do not show floating point noise.
+pub(crate) fn format_number(value: Option<f64>) -> String {
+    value
+        .map(|value| ((value * 100.0).round() / 100.0 + 0.0).to_string())
+        .unwrap_or_default()
+}
+
+fn format_delta(delta: Option<f64>) -> String {
+    match delta {
+        Some(delta) if delta > 0.0 => format!("+{}", format_number(Some(delta))),
+        _ => format_number(delta),
+    }
+}
+
+/// Count the files, or functions, with a maintainability index marked 🟡 or
+/// 🔴, coloured by the worst one.
+fn badge(stats: &[HotspotStats], thresholds: &Markers) -> Badge {
+    let [low, high] = thresholds.maintainability_index;
+    let rows = stats
+        .iter()
+        .filter(|stat| stat.path_type == PathType::File)
+        .collect::<Vec<_>>();
+    let critical = rows
+        .iter()
+        .filter(|stat| stat.maintainability_index < high)
+        .count();
+    let worst = rows
+        .iter()
+        .map(|stat| stat.maintainability_index)
+        .fold(f64::INFINITY, f64::min);
+    let kind = match rows.iter().any(|stat| stat.function.is_some()) {
+        true => "function",
+        false => "file",
+    };
+
+    Badge {
+        schema_version: 1,
+        label: "tech debt",
+        message: format!(
+            "{critical} critical {kind}{}",
+            if critical == 1 { "" } else { "s" }
+        ),
+        color: if worst >= high {
+            "brightgreen"
+        } else if worst >= low {
+            "yellow"
+        } else {
+            "red"
+        },
+    }
+}
+
+const PARTITION_HEADER: [&str; 4] = [
+    "partition",
+    "rows",
+    "max_hotspot_index",
+    "min_maintainability_index",
+];
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn partition_record(partition: &Partition) -> [String; 4] {
+    [
+        partition.name.clone(),
+        partition.rows.to_string(),
+        partition.max_hotspot_index.to_string(),
+        partition.min_maintainability_index.to_string(),
+    ]
+}
+
+const AUTHOR_HEADER: [&str; 5] = [
+    "author",
+    "changes_count",
+    "files_owned",
+    "loc_owned",
+    "hotspot_share",
+];
+
+fn author_record(author: &AuthorOwnership) -> [String; 5] {
+    [
+        author.author.clone(),
+        author.changes_count.to_string(),
+        author.files_owned.to_string(),
+        author.loc_owned.to_string(),
+        author.hotspot_share.to_string(),
+    ]
+}
+
+fn write_csv_header<W: Write>(
+    writer: &mut Writer<W>,
+    columns: &[Column],
+) -> Result<(), Box<dyn Error>> {
+    writer.write_record(columns.iter().map(|column| column.name().into_owned()))?;
+
+    Ok(())
+}
+
+fn write_csv_record<W: Write>(
+    writer: &mut Writer<W>,
+    columns: &[Column],
+    distributions: &Distributions,
+    stat: &HotspotStats,
+) -> Result<(), Box<dyn Error>> {
+    writer.write_record(
+        columns
+            .iter()
+            .map(|column| distributions.value(column, stat)),
+    )?;
+
+    Ok(())
+}
+
+/// Writes the rows as soon as they are available, for the formats which do not
+/// need to know every row in advance.
+pub enum StreamingOutput<W: Write> {
+    Csv(Box<Writer<W>>, Vec<Column>),
+    Ndjson(W),
+}
+
+impl<W: Write> StreamingOutput<W> {
+    pub fn new(
+        format: &OutputFormat,
+        columns: &[Column],
+        writer: W,
+    ) -> Result<Self, Box<dyn Error>> {
+        if let Some(column) = columns.iter().find(|column| column.is_normalized()) {
+            return Err(format!(
+                "the {} column depends on every row and cannot be streamed",
+                column.name()
+            )
+            .into());
+        }
+
+        match format {
+            OutputFormat::Csv => {
+                let mut writer = Writer::from_writer(writer);
+                write_csv_header(&mut writer, columns)?;
+
+                Ok(StreamingOutput::Csv(Box::new(writer), columns.to_vec()))
+            }
+            OutputFormat::Ndjson => Ok(StreamingOutput::Ndjson(writer)),
+            OutputFormat::Markdown => Err("the markdown format cannot be streamed".into()),
+            OutputFormat::Badge
+            | OutputFormat::Comment
+            | OutputFormat::Slack
+            | OutputFormat::Html
+            | OutputFormat::Arrow
+            | OutputFormat::Parquet
+            | OutputFormat::Postgres => Err(
+                "the badge, comment, slack, html, arrow, parquet and postgres formats cannot be streamed"
+                    .into(),
+            ),
+        }
+    }
+
+    /// List the skipped files at the end of the NDJSON format, the CSV
+    /// format having no place for them.
+    pub fn write_skipped(&mut self, skipped: &[Skipped]) -> Result<(), Box<dyn Error>> {
+        if let StreamingOutput::Ndjson(writer) = self {
+            for skipped in skipped {
+                serde_json::to_writer(&mut *writer, &SkippedRecord { skipped })?;
+                writeln!(writer)?;
+            }
+
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// List the files and functions suppressed by comments at the end of
+    /// the NDJSON format.
+    pub fn write_suppressions(
+        &mut self,
+        suppressions: &[Suppression],
+    ) -> Result<(), Box<dyn Error>> {
+        if let StreamingOutput::Ndjson(writer) = self {
+            for suppression in suppressions {
+                serde_json::to_writer(&mut *writer, &SuppressedRecord { suppression })?;
+                writeln!(writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write(&mut self, stat: &HotspotStats) -> Result<(), Box<dyn Error>> {
+        match self {
+            StreamingOutput::Csv(writer, columns) => {
+                // Normalized columns are rejected, there is nothing to distribute
+                let distributions = Distributions::new(&[], &[]);
+                write_csv_record(writer.as_mut(), columns, &distributions, stat)?;
+                writer.flush()?;
+            }
+            StreamingOutput::Ndjson(writer) => {
+                serde_json::to_writer(&mut *writer, stat)?;
+                writeln!(writer)?;
+                writer.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preset::Preset;
+
+    #[test]
+    fn test_badge() {
+        // ARRANGE
+        let stat = |path: &str, path_type, maintainability_index| HotspotStats {
+            path: path.to_string(),
+            path_type,
+            maintainability_index,
+            ..Default::default()
+        };
+        let stats = vec![
+            stat("a.py", PathType::File, 15.0),
+            stat("b.py", PathType::File, 80.0),
+            stat(".", PathType::Directory, 5.0),
+        ];
+
+        // ACT
+        let actual = badge(&stats, &Markers::default());
+
+        // ASSERT
+        let expected = Badge {
+            schema_version: 1,
+            label: "tech debt",
+            message: "1 critical file".to_string(),
+            color: "yellow",
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_badge_preset() {
+        // ARRANGE
+        let stats = vec![HotspotStats {
+            path: "a.py".to_string(),
+            maintainability_index: 15.0,
+            ..Default::default()
+        }];
+        let output = |preset: Preset| {
+            Output::new(&OutputFormat::Badge)
+                .with_thresholds(preset.markers())
+                .format(&stats)
+                .unwrap()
+        };
+
+        // ACT
+        let strict = output(Preset::Strict);
+        let lenient = output(Preset::Lenient);
+
+        // ASSERT
+        assert_eq!(
+            strict,
+            r#"{"schemaVersion":1,"label":"tech debt","message":"1 critical file","color":"red"}"#
+        );
+        assert_eq!(
+            lenient,
+            r#"{"schemaVersion":1,"label":"tech debt","message":"0 critical files","color":"brightgreen"}"#
+        );
+    }
+
+    #[test]
+    fn test_format_csv_with_totals() {
+        // ARRANGE
+        let stats = vec![HotspotStats {
+            path: "a.py".to_string(),
+            loc: 10,
+            maintainability_index: 50.0,
+            changes_count: 2,
+            ..Default::default()
+        }];
+        let output = Output::new(&OutputFormat::Csv)
+            .with_columns(&[Column::Path, Column::LinesOfCode])
+            .with_totals(Totals::new(&stats));
+
+        // ACT
+        let actual = output.format(&stats).unwrap();
+
+        // ASSERT
+        let expected = "\
+# total_files: 1
+# total_loc: 10
+# total_changes_count: 2
+# total_maintainability_index: 50
+# total_critical: 0
+# total_high: 0
+# total_medium: 0
+# total_low: 1
+path,loc
+a.py,10
+";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_csv_long() {
+        // ARRANGE
+        let stats = vec![HotspotStats {
+            path: "a.py".to_string(),
+            loc: 10,
+            maintainability_index: 50.0,
+            ..Default::default()
+        }];
+        let output = Output::new(&OutputFormat::Csv)
+            .with_columns(&[
+                Column::Path,
+                Column::PathType,
+                Column::LinesOfCode,
+                Column::MaintainabilityIndex,
+                Column::Coverage,
+                Column::Severity,
+            ])
+            .with_long(true);
+
+        // ACT
+        let actual = output.format(&stats).unwrap();
+
+        // ASSERT
+        let expected = "\
+path,metric,value
+a.py,loc,10
+a.py,maintainability_index,50
+";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_markdown_with_suppressions() {
+        // ARRANGE
+        let stats = vec![HotspotStats {
+            path: "a.py".to_string(),
+            loc: 10,
+            ..Default::default()
+        }];
+        let output = Output::new(&OutputFormat::Markdown)
+            .with_columns(&[Column::Path, Column::LinesOfCode])
+            .with_suppressions(vec![
+                Suppression {
+                    path: "a.py".to_string(),
+                    function: Some("parse".to_string()),
+                    line: 12,
+                    reason: Some("deprecated".to_string()),
+                },
+                Suppression {
+                    path: "b_pb2.py".to_string(),
+                    function: None,
+                    line: 1,
+                    reason: None,
+                },
+            ]);
+
+        // ACT
+        let actual = output.format(&stats).unwrap();
+
+        // ASSERT
+        let expected = "\
+| path | loc |
+|------|-----|
+| a.py | 10  |
+
+Suppressed:
+
+- `a.py` `parse` (line 12): deprecated
+- `b_pb2.py` (line 1)";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_comment() {
+        // ARRANGE
+        let stat = |path: &str, hotspot_index| HotspotStats {
+            path: path.to_string(),
+            hotspot_index,
+            maintainability_index: 50.0,
+            ..Default::default()
+        };
+        let output = Output::new(&OutputFormat::Comment)
+            .with_limit(Some(1))
+            .with_baseline(vec![stat("a.py", 1.0), stat("b.py", 3.0)]);
+
+        // ACT
+        let actual = output
+            .format(&[stat("a.py", 2.5), stat("b.py", 3.0), stat("c.py", 1.0)])
+            .unwrap();
+
+        // ASSERT
+        let header = "| path | hotspot_index | maintainability_index | cyclomatic_complexity | loc |\n|---|---|---|---|---|\n";
+        let expected = format!(
+            "### Tech debt hotspots\n\n{header}| `a.py` | 2.5 ▲ 1.5 | 50 | 0 | 0 |\n\n<details>\n<summary>2 more rows</summary>\n\n{header}| `b.py` | 3 | 50 | 0 | 0 |\n| `c.py` 🆕 | 1 | 50 | 0 | 0 |\n\n</details>\n"
+        );
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_ndjson_with_details() {
+        // ARRANGE
+        let file = HotspotStats {
+            path: "a.py".to_string(),
+            ..Default::default()
+        };
+        let function = HotspotStats {
+            function: Some("f".to_string()),
+            ..file.clone()
+        };
+        let details = HashMap::from([(
+            "a.py".to_string(),
+            vec![FunctionDetail {
+                name: "f".to_string(),
+                start_line: 1,
+                end_line: 3,
+                cyclomatic_complexity: 2.0,
+                cognitive_complexity: 1.0,
+                loc: 3,
+            }],
+        )]);
+        let output = Output::new(&OutputFormat::Ndjson).with_details(details);
+
+        // ACT
+        let actual = output
+            .format(&[file, function])
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .map(|row| row.get("functions").cloned())
+            .collect::<Vec<_>>();
+
+        // ASSERT
+        let expected = vec![
+            Some(serde_json::json!([{
+                "name": "f",
+                "start_line": 1,
+                "end_line": 3,
+                "cyclomatic_complexity": 2.0,
+                "cognitive_complexity": 1.0,
+                "loc": 3,
+            }])),
+            None,
+        ];
+
+        assert_eq!(actual, expected);
     }
 }
@@ -0,0 +1,463 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+use crate::columns::{Column, PLUGIN_PREFIX};
+use crate::error::Error;
+use crate::hotspot::HotspotStats;
+
+/// An arithmetic expression over the metrics of a file or function,
+/// replacing the default hotspot index.
+///
+/// Supports numbers, the numeric column names and the `plugin.` prefixed
+/// metrics of the plugins as variables, `+ - * / ^`, parentheses and the
+/// functions `sqrt`, `ln`, `log10`, `exp`, `abs`, `min` and `max`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Formula {
+    source: String,
+    expression: Expression,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expression {
+    Number(f64),
+    Metric(Column),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Sqrt,
+    Ln,
+    Log10,
+    Exp,
+    Abs,
+    Min,
+    Max,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "sqrt" => Function::Sqrt,
+            "ln" => Function::Ln,
+            "log10" => Function::Log10,
+            "exp" => Function::Exp,
+            "abs" => Function::Abs,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            _ => return None,
+        })
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Min | Function::Max => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(&self, arguments: &[f64]) -> f64 {
+        match self {
+            Function::Sqrt => arguments[0].sqrt(),
+            Function::Ln => arguments[0].ln(),
+            Function::Log10 => arguments[0].log10(),
+            Function::Exp => arguments[0].exp(),
+            Function::Abs => arguments[0].abs(),
+            Function::Min => arguments[0].min(arguments[1]),
+            Function::Max => arguments[0].max(arguments[1]),
+        }
+    }
+}
+
+impl Formula {
+    /// Evaluate the formula, divisions by zero give an infinite or NaN index
+    /// instead of failing.
+    pub fn evaluate(&self, stat: &HotspotStats) -> f64 {
+        self.expression.evaluate(stat)
+    }
+}
+
+impl Expression {
+    fn evaluate(&self, stat: &HotspotStats) -> f64 {
+        match self {
+            Expression::Number(value) => *value,
+            Expression::Metric(column) => column.metric(stat).unwrap_or_default(),
+            Expression::Negate(operand) => -operand.evaluate(stat),
+            Expression::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(stat), right.evaluate(stat));
+
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Power => left.powf(right),
+                }
+            }
+            Expression::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.evaluate(stat))
+                    .collect::<Vec<_>>();
+
+                function.apply(&arguments)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Formula {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(source).map_err(|message| invalid(source, message))?,
+            position: 0,
+        };
+        let expression = parser
+            .expression()
+            .map_err(|message| invalid(source, message))?;
+
+        if let Some(token) = parser.peek() {
+            return Err(invalid(source, format!("unexpected {token}")));
+        }
+
+        Ok(Formula {
+            source: source.to_string(),
+            expression,
+        })
+    }
+}
+
+fn invalid(formula: &str, message: String) -> Error {
+    Error::InvalidFormula {
+        formula: formula.to_string(),
+        message,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    OpenParenthesis,
+    CloseParenthesis,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "number {value}"),
+            Token::Identifier(name) => write!(f, "`{name}`"),
+            Token::Operator(operator) => write!(f, "`{operator}`"),
+            Token::OpenParenthesis => f.write_str("`(`"),
+            Token::CloseParenthesis => f.write_str("`)`"),
+            Token::Comma => f.write_str("`,`"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '+' | '-' | '*' | '/' | '^' => Token::Operator(c),
+            '(' => Token::OpenParenthesis,
+            ')' => Token::CloseParenthesis,
+            ',' => Token::Comma,
+            _ if c.is_ascii_digit() || c == '.' => {
+                let mut end = start + c.len_utf8();
+                while let Some((index, c)) = chars.next_if(|(_, c)| c.is_ascii_digit() || *c == '.')
+                {
+                    end = index + c.len_utf8();
+                }
+
+                let number = &source[start..end];
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| format!("invalid number {number}"))?,
+                )
+            }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                // The dot of the `plugin.` prefix of the metrics of the plugins
+                while let Some((index, c)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_' || *c == '.')
+                {
+                    end = index + c.len_utf8();
+                }
+
+                Token::Identifier(source[start..end].to_string())
+            }
+            _ => return Err(format!("unexpected character `{c}`")),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, `^` binding tighter than unary minus, itself
+/// tighter than `*` and `/`, and `+` and `-` last.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected {expected}, found {token}")),
+            None => Err(format!("expected {expected}, found the end of the formula")),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut expression = self.term()?;
+
+        while let Some(Token::Operator(c @ ('+' | '-'))) = self.peek() {
+            let operator = match c {
+                '+' => Operator::Add,
+                _ => Operator::Subtract,
+            };
+            self.position += 1;
+
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(self.term()?));
+        }
+
+        Ok(expression)
+    }
+
+    fn term(&mut self) -> Result<Expression, String> {
+        let mut expression = self.unary()?;
+
+        while let Some(Token::Operator(c @ ('*' | '/'))) = self.peek() {
+            let operator = match c {
+                '*' => Operator::Multiply,
+                _ => Operator::Divide,
+            };
+            self.position += 1;
+
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.unary()?));
+        }
+
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+        if let Some(Token::Operator('-')) = self.peek() {
+            self.position += 1;
+
+            return Ok(Expression::Negate(Box::new(self.unary()?)));
+        }
+
+        self.power()
+    }
+
+    /// `^` is right associative, `2 ^ 3 ^ 2` being `2 ^ 9`.
+    fn power(&mut self) -> Result<Expression, String> {
+        let base = self.primary()?;
+
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.position += 1;
+
+            return Ok(Expression::Binary(
+                Operator::Power,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ));
+        }
+
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expression::Number(value)),
+            Some(Token::OpenParenthesis) => {
+                let expression = self.expression()?;
+                self.expect(Token::CloseParenthesis)?;
+
+                Ok(expression)
+            }
+            Some(Token::Identifier(name)) if self.peek() == Some(&Token::OpenParenthesis) => {
+                let function = Function::from_name(&name)
+                    .ok_or_else(|| format!("unknown function `{name}`"))?;
+                self.position += 1;
+
+                let mut arguments = vec![self.expression()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.position += 1;
+                    arguments.push(self.expression()?);
+                }
+                self.expect(Token::CloseParenthesis)?;
+
+                if arguments.len() != function.arity() {
+                    return Err(format!(
+                        "`{name}` takes {} argument(s), found {}",
+                        function.arity(),
+                        arguments.len()
+                    ));
+                }
+
+                Ok(Expression::Call(function, arguments))
+            }
+            Some(Token::Identifier(name)) if name.starts_with(PLUGIN_PREFIX) => {
+                Column::plugin(&name[PLUGIN_PREFIX.len()..])
+                    .map(Expression::Metric)
+                    .ok_or_else(|| format!("invalid plugin metric `{name}`"))
+            }
+            Some(Token::Identifier(name)) => match <Column as ValueEnum>::from_str(&name, false) {
+                Ok(Column::HotspotIndex) => {
+                    Err("`hotspot_index` cannot be used in its own formula".to_string())
+                }
+                Ok(column) if column.is_metric() => Ok(Expression::Metric(column)),
+                _ => Err(format!("unknown metric `{name}`")),
+            },
+            Some(token) => Err(format!("unexpected {token}")),
+            None => Err("unexpected end of the formula".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotspot::PathType;
+    use rstest::*;
+
+    fn stat() -> HotspotStats {
+        HotspotStats {
+            path: "module.py".to_string(),
+            path_type: PathType::File,
+            function: None,
+            start_line: None,
+            end_line: None,
+            halstead_volume: 10.0,
+            cyclomatic_complexity: 6.0,
+            cyclomatic_average: 6.0,
+            cyclomatic_total: 6.0,
+            max_nesting: 2,
+            nom: 3,
+            longest_function: 20,
+            loc: 16,
+            comments_percentage: 25.0,
+            maintainability_index: 50.0,
+            changes_count: 4,
+            changes_30d: 0,
+            changes_90d: 0,
+            changes_365d: 0,
+            lines_changed: 0,
+            feature_changes: 0,
+            fix_changes: 0,
+            refactor_changes: 0,
+            chore_changes: 0,
+            author_entropy: 0.0,
+            knowledge_loss: None,
+            monthly_changes: None,
+            first_commit: None,
+            last_author: None,
+            primary_owner: None,
+            hotspot_index: 8.0,
+            coverage: None,
+            risk: None,
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: 0.0,
+            duplication_partners: vec![],
+            debt_comments: 0,
+            type_annotations: None,
+            test_ratio: None,
+            test_loc: None,
+            fan_in: None,
+            fan_out: None,
+            centrality: None,
+            plugins: [("sql_strings".to_string(), 3.0)].into(),
+            severity: Default::default(),
+        }
+    }
+
+    #[rstest]
+    #[case("42", 42.0)]
+    #[case("changes_count * cyclomatic_complexity / sqrt(loc)", 6.0)]
+    #[case("1 + 2 * 3", 7.0)]
+    #[case("(1 + 2) * 3", 9.0)]
+    #[case("-2 ^ 2", -4.0)]
+    #[case("2 ^ 3 ^ 2", 512.0)]
+    #[case("max(nom, max_nesting) - min(1, 2)", 2.0)]
+    #[case("changes_count / (maintainability_index / 100)", 8.0)]
+    #[case("changes_count * plugin.sql_strings", 12.0)]
+    #[case("plugin.unknown + 1", 1.0)]
+    fn test_formula_evaluate(#[case] formula: &str, #[case] expected: f64) {
+        // ARRANGE
+        let formula = formula.parse::<Formula>().unwrap();
+
+        // ACT
+        let actual = formula.evaluate(&stat());
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("", "unexpected end of the formula")]
+    #[case("loc +", "unexpected end of the formula")]
+    #[case("(loc", "expected `)`, found the end of the formula")]
+    #[case("loc loc", "unexpected `loc`")]
+    #[case("churn * 2", "unknown metric `churn`")]
+    #[case("path", "unknown metric `path`")]
+    #[case("plugin.SQL", "invalid plugin metric `plugin.SQL`")]
+    #[case("hotspot_index", "`hotspot_index` cannot be used in its own formula")]
+    #[case("log(loc)", "unknown function `log`")]
+    #[case("min(loc)", "`min` takes 2 argument(s), found 1")]
+    #[case("loc % 2", "unexpected character `%`")]
+    fn test_formula_invalid(#[case] formula: &str, #[case] expected: &str) {
+        // ACT
+        let actual = formula.parse::<Formula>();
+
+        // ASSERT
+        match actual {
+            Err(Error::InvalidFormula { message, .. }) => assert_eq!(message, expected),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tracing::{debug, warn};
+
+use crate::error::{Error, Result};
+
+/// Run a git command in the given directory and return its stdout.
+pub(crate) fn run<I, S>(directory: &Path, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new("git");
+
+    command.current_dir(directory).args(args);
+    debug!("Running {command:?}");
+
+    let output = command.output().map_err(Error::GitExecution)?;
+
+    if !output.status.success() {
+        let subcommand = command
+            .get_args()
+            .next()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        return Err(Error::GitCommand {
+            command: subcommand,
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The most recent tag reachable from `HEAD`, among the tags matching the
+/// glob pattern if any.
+pub(crate) fn latest_tag(directory: &Path, pattern: Option<&str>) -> Result<String> {
+    let mut args = vec!["describe", "--tags", "--abbrev=0"];
+
+    if let Some(pattern) = pattern {
+        args.extend(["--match", pattern]);
+    }
+
+    Ok(run(directory, args)?.trim().to_string())
+}
+
+/// A path printed by git, always with forward slashes, with the separators
+/// of the platform so it matches the discovered paths on Windows.
+pub(crate) fn native_path(path: &str) -> PathBuf {
+    path.split('/').collect()
+}
+
+/// The files of the directory tracked by git, and the untracked ones which
+/// are not ignored if asked, as absolute paths.
+pub(crate) fn tracked_files(directory: &Path, include_untracked: bool) -> Result<Vec<PathBuf>> {
+    let mut args = vec!["ls-files", "-z"];
+
+    if include_untracked {
+        args.extend(["--cached", "--others", "--exclude-standard"]);
+    }
+
+    let stdout = run(directory, args)?;
+
+    Ok(stdout
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| directory.join(native_path(path)))
+        .collect())
+}
+
+/// The absolute path of the `.git` directory of the repository.
+pub(crate) fn git_dir(directory: &Path) -> Result<PathBuf> {
+    let stdout = run(directory, ["rev-parse", "--absolute-git-dir"])?;
+
+    Ok(PathBuf::from(stdout.trim()))
+}
+
+/// A revision of the repository checked out in a detached worktree, removed
+/// when dropped.
+#[derive(Debug)]
+pub(crate) struct Worktree {
+    repository: PathBuf,
+    pub path: PathBuf,
+}
+
+impl Worktree {
+    /// Check out the revision of the repository of `directory` in `path`.
+    pub fn add(directory: &Path, revision: &str, path: PathBuf) -> Result<Worktree> {
+        let mut args = vec![
+            OsStr::new("worktree"),
+            OsStr::new("add"),
+            OsStr::new("--detach"),
+        ];
+        args.extend([path.as_os_str(), OsStr::new(revision)]);
+
+        run(directory, args)?;
+
+        Ok(Worktree {
+            repository: directory.to_path_buf(),
+            path,
+        })
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let args = [
+            OsStr::new("worktree"),
+            OsStr::new("remove"),
+            OsStr::new("--force"),
+            self.path.as_os_str(),
+        ];
+
+        if let Err(error) = run(&self.repository, args) {
+            warn!(
+                path = %self.path.display(),
+                reason = %error,
+                "Failed to remove the worktree"
+            );
+        }
+    }
+}
+
+/// A remote repository cloned in a temporary directory, deleted when dropped.
+#[derive(Debug)]
+pub struct ClonedRepository {
+    path: PathBuf,
+}
+
+impl ClonedRepository {
+    /// Clone the repository at `url`, only fetching the last `depth` commits
+    /// when set, which limits the history counted in the changes.
+    pub fn clone(url: &str, depth: Option<u32>) -> Result<ClonedRepository> {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join(format!("tech_debt_hotspot-{}", std::process::id()));
+        let depth = depth.map(|depth| format!("--depth={depth}"));
+
+        let mut args = vec![OsStr::new("clone"), OsStr::new("--quiet")];
+        args.extend(depth.as_deref().map(OsStr::new));
+        args.extend([OsStr::new(url), path.as_os_str()]);
+
+        // Clean up a partial clone, the directory is not owned yet
+        if let Err(error) = run(&temp_dir, args) {
+            let _ = fs::remove_dir_all(&path);
+            return Err(error);
+        }
+
+        Ok(ClonedRepository { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ClonedRepository {
+    fn drop(&mut self) {
+        if let Err(error) = fs::remove_dir_all(&self.path) {
+            warn!(
+                path = %self.path.display(),
+                reason = %error,
+                "Failed to remove the clone"
+            );
+        }
+    }
+}
+
+/// Whether the directory argument is the URL of a remote repository rather
+/// than a local path, e.g. `https://github.com/org/repo.git` or
+/// `git@github.com:org/repo.git`.
+pub fn is_remote_url(value: &str) -> bool {
+    value.contains("://") || (value.starts_with("git@") && value.contains(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use tempfile::{tempdir, TempDir};
+
+    fn commit(directory: &Path, message: &str) {
+        run(directory, ["add", "--all"]).unwrap();
+        run(
+            directory,
+            [
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-m",
+                message,
+            ],
+        )
+        .unwrap();
+    }
+
+    #[fixture]
+    fn repository() -> TempDir {
+        let temp_dir = tempdir().unwrap();
+        run(temp_dir.path(), ["init"]).unwrap();
+        fs::write(temp_dir.path().join("a.py"), "x = 1\n").unwrap();
+        commit(temp_dir.path(), "First");
+        fs::write(temp_dir.path().join("a.py"), "x = 2\n").unwrap();
+        commit(temp_dir.path(), "Second");
+
+        temp_dir
+    }
+
+    #[rstest]
+    #[case("https://github.com/org/repo.git", true)]
+    #[case("ssh://git@host/repo", true)]
+    #[case("git@github.com:org/repo.git", true)]
+    #[case("file:///srv/repo", true)]
+    #[case("/srv/repo", false)]
+    #[case("git@home", false)]
+    #[case(".", false)]
+    fn test_is_remote_url(#[case] value: &str, #[case] expected: bool) {
+        // ACT
+        let actual = is_remote_url(value);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_native_path() {
+        // ACT
+        let actual = native_path("pkg/sub/a.py");
+
+        // ASSERT
+        assert_eq!(actual, Path::new("pkg").join("sub").join("a.py"));
+    }
+
+    #[rstest]
+    fn test_worktree(repository: TempDir) {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("worktree");
+
+        // ACT
+        let worktree = Worktree::add(repository.path(), "HEAD~1", path.clone()).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            fs::read_to_string(worktree.path.join("a.py")).unwrap(),
+            "x = 1\n"
+        );
+
+        drop(worktree);
+
+        assert!(!path.exists());
+    }
+
+    #[rstest]
+    #[case(None, "nightly")]
+    #[case(Some("v*"), "v1.0")]
+    fn test_latest_tag(repository: TempDir, #[case] pattern: Option<&str>, #[case] expected: &str) {
+        // ARRANGE
+        run(repository.path(), ["tag", "v1.0", "HEAD~1"]).unwrap();
+        run(repository.path(), ["tag", "nightly"]).unwrap();
+
+        // ACT
+        let actual = latest_tag(repository.path(), pattern).unwrap();
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,187 @@
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use tech_debt_hotspot::{HotspotStats, Markers, Overrides, PathType};
+
+const SUMMARY_ENV: &str = "GITHUB_STEP_SUMMARY";
+
+/// Print a `::warning` workflow command for each file, or function, marked 🔴
+/// by the markers or of critical severity.
+pub fn annotate(stats: &[HotspotStats], markers: &Overrides<Markers>) {
+    let annotations = stats
+        .iter()
+        .filter(|stat| stat.path_type == PathType::File)
+        .filter_map(|stat| annotation(stat, markers.get(Path::new(&stat.path))));
+
+    for annotation in annotations {
+        println!("{annotation}");
+    }
+}
+
+/// Append the report to the job summary, skipped outside GitHub Actions.
+pub fn write_summary(report: &str) -> Result<(), Box<dyn Error>> {
+    let Some(path) = std::env::var_os(SUMMARY_ENV).map(PathBuf::from) else {
+        warn!("{SUMMARY_ENV} is not set, skipping the job summary");
+        return Ok(());
+    };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "## Tech debt hotspots\n\n{report}\n")?;
+
+    Ok(())
+}
+
+fn annotation(stat: &HotspotStats, markers: &Markers) -> Option<String> {
+    let violations = markers.violations(stat);
+
+    if violations.is_empty() {
+        return None;
+    }
+
+    let mut properties = vec![
+        format!("file={}", escape_property(&stat.path)),
+        format!("title={}", escape_property("Tech debt hotspot")),
+    ];
+
+    if let (Some(start_line), Some(end_line)) = (stat.start_line, stat.end_line) {
+        properties.push(format!("line={start_line}"));
+        properties.push(format!("endLine={end_line}"));
+    }
+
+    let subject = match &stat.function {
+        Some(function) => format!("{function}: "),
+        None => String::new(),
+    };
+    let message = format!(
+        "{subject}{} with {} changes",
+        violations.join(", "),
+        stat.changes_count
+    );
+
+    Some(format!(
+        "::warning {}::{}",
+        properties.join(","),
+        escape_data(&message)
+    ))
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape the value of a property of a workflow command.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use tech_debt_hotspot::Severity;
+
+    #[rstest]
+    #[case("plain", "plain")]
+    #[case("100%", "100%25")]
+    #[case("a\r\nb", "a%0D%0Ab")]
+    #[case("a:b,c", "a:b,c")]
+    fn test_escape_data(#[case] value: &str, #[case] expected: &str) {
+        // ACT
+        let result = escape_data(value);
+
+        // ASSERT
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case("src/a.py", "src/a.py")]
+    #[case("a:b,c", "a%3Ab%2Cc")]
+    #[case("50%\n", "50%25%0A")]
+    fn test_escape_property(#[case] value: &str, #[case] expected: &str) {
+        // ACT
+        let result = escape_property(value);
+
+        // ASSERT
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::healthy(50.0, 1.0, Severity::Low, None)]
+    #[case::maintainability_index(
+        5.0,
+        1.0,
+        Severity::Low,
+        Some("::warning file=src/a.py,title=Tech debt hotspot::maintainability index 5.0 is below 10 with 3 changes")
+    )]
+    #[case::hotspot_index(
+        50.0,
+        25.0,
+        Severity::High,
+        Some("::warning file=src/a.py,title=Tech debt hotspot::hotspot index 25.00 is from 20 with 3 changes")
+    )]
+    #[case::severity(
+        50.0,
+        1.0,
+        Severity::Critical,
+        Some(
+            "::warning file=src/a.py,title=Tech debt hotspot::severity is critical with 3 changes"
+        )
+    )]
+    fn test_annotation(
+        #[case] maintainability_index: f64,
+        #[case] hotspot_index: f64,
+        #[case] severity: Severity,
+        #[case] expected: Option<&str>,
+    ) {
+        // ARRANGE
+        let stat = HotspotStats {
+            path: "src/a.py".to_string(),
+            maintainability_index,
+            hotspot_index,
+            severity,
+            changes_count: 3,
+            ..Default::default()
+        };
+
+        // ACT
+        let result = annotation(&stat, &Markers::default());
+
+        // ASSERT
+        assert_eq!(result.as_deref(), expected);
+    }
+
+    #[test]
+    fn test_annotation_of_function() {
+        // ARRANGE
+        let stat = HotspotStats {
+            path: "src/a.py".to_string(),
+            function: Some("parse".to_string()),
+            start_line: Some(4),
+            end_line: Some(12),
+            maintainability_index: 15.0,
+            changes_count: 2,
+            ..Default::default()
+        };
+        let markers = Markers {
+            maintainability_index: [20.0, 40.0],
+            ..Default::default()
+        };
+
+        // ACT
+        let result = annotation(&stat, &markers);
+
+        // ASSERT
+        assert_eq!(
+            result.as_deref(),
+            Some("::warning file=src/a.py,title=Tech debt hotspot,line=4,endLine=12::parse: maintainability index 15.0 is below 20 with 2 changes")
+        );
+    }
+}
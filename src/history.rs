@@ -0,0 +1,482 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use chrono::NaiveDate;
+use clap::{builder::PossibleValue, ValueEnum};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::git;
+
+/// Separates the commits in the output of `git log`.
+const RECORD_SEPARATOR: char = '\x1e';
+
+/// The number of a pull request in the subject of its merge commit, e.g.
+/// `Merge pull request #12 from org/branch` or `Add a column (#12)` when
+/// squashed.
+static PULL_REQUEST: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Merge pull request #(\d+)|\(#(\d+)\)$").expect("the regex is valid")
+});
+
+/// What counts as one change of a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChurnUnit {
+    /// Every commit changing the file.
+    #[default]
+    Commit,
+    /// Every commit of the first-parent history, a merge counting once with
+    /// the changes of all the commits it merged.
+    Merge,
+    /// Every pull request merged in the first-parent history, told by the
+    /// number in the subject of its commit, the commits without a number
+    /// counting once each.
+    PullRequest,
+}
+
+impl ChurnUnit {
+    pub(crate) fn is_first_parent(&self) -> bool {
+        !matches!(self, ChurnUnit::Commit)
+    }
+
+    /// The pull request of the commit, none when counting commits or merges.
+    pub(crate) fn pull_request(&self, commit: &Commit) -> Option<u32> {
+        match self {
+            ChurnUnit::PullRequest => pull_request(&commit.subject),
+            ChurnUnit::Commit | ChurnUnit::Merge => None,
+        }
+    }
+}
+
+impl ValueEnum for ChurnUnit {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ChurnUnit::Commit, ChurnUnit::Merge, ChurnUnit::PullRequest]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ChurnUnit::Commit => PossibleValue::new("commit").help("Count every commit"),
+            ChurnUnit::Merge => {
+                PossibleValue::new("merge").help("Count the commits of the first-parent history")
+            }
+            ChurnUnit::PullRequest => PossibleValue::new("pull-request")
+                .help("Count the distinct pull requests of the first-parent history"),
+        })
+    }
+}
+
+/// A commit of the Git history with the first line of its message and the
+/// files it changed.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Commit {
+    pub hash: String,
+    pub author: String,
+    /// Day the commit was committed, unknown if git printed no valid date.
+    pub date: Option<NaiveDate>,
+    pub subject: String,
+    pub files: Vec<FileChange>,
+}
+
+/// A file changed by a commit, relative to the root of the repository, with
+/// the number of lines added and deleted, 0 for binary files.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FileChange {
+    pub path: PathBuf,
+    pub lines: u32,
+}
+
+/// The tag from which the changes are counted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SinceTag {
+    /// The given tag.
+    Tag(String),
+    /// The most recent tag reachable from `HEAD`, among the tags matching a
+    /// glob pattern if any, e.g. the last release.
+    Latest(Option<String>),
+}
+
+/// Commits left out of the history, such as the bulk reformatting of the
+/// code which would count as a change of every file.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct IgnoreCommits {
+    /// Ignore the changes to whitespace, leaving out the commits only
+    /// changing whitespace.
+    pub whitespace: bool,
+    /// Hashes of the commits, or prefixes of them.
+    pub revs: Vec<String>,
+    /// File listing hashes of commits one per line with `#` comments, such
+    /// as `.git-blame-ignore-revs`, relative to the root of the repository.
+    pub revs_file: Option<PathBuf>,
+    /// Regexes matched against the subject of the commits.
+    pub messages: Vec<String>,
+}
+
+impl IgnoreCommits {
+    /// Read the file of hashes and compile the regexes.
+    pub(crate) fn filter(&self, root: &Path) -> Result<CommitFilter> {
+        let mut revs = self.revs.clone();
+
+        if let Some(revs_file) = &self.revs_file {
+            let path = root.join(revs_file);
+            let content = fs::read_to_string(&path).map_err(|source| Error::Io {
+                path: path.clone(),
+                source,
+            })?;
+
+            revs.extend(
+                content
+                    .lines()
+                    .map(|line| line.split('#').next().unwrap_or_default().trim())
+                    .filter(|rev| !rev.is_empty())
+                    .map(str::to_string),
+            );
+        }
+
+        let messages = self
+            .messages
+            .iter()
+            .map(|message| {
+                Regex::new(message).map_err(|error| Error::InvalidCommitPattern {
+                    pattern: message.clone(),
+                    message: error.to_string(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(CommitFilter { revs, messages })
+    }
+}
+
+pub(crate) struct CommitFilter {
+    revs: Vec<String>,
+    messages: Vec<Regex>,
+}
+
+impl CommitFilter {
+    pub fn is_ignored(&self, commit: &Commit) -> bool {
+        self.revs
+            .iter()
+            .any(|rev| commit.hash.starts_with(rev.as_str()))
+            || self
+                .messages
+                .iter()
+                .any(|message| message.is_match(&commit.subject))
+    }
+}
+
+/// Read the commits changing the directory, most recent first, committed
+/// since the date and after the tag.
+pub(crate) fn read(
+    directory: &Path,
+    since: Option<&NaiveDate>,
+    since_tag: Option<&str>,
+    first_parent: bool,
+    ignore_whitespace: bool,
+) -> Result<Vec<Commit>> {
+    let mut args = vec![
+        "log".to_string(),
+        "--numstat".to_string(),
+        "--no-renames".to_string(),
+        format!("--pretty=format:{RECORD_SEPARATOR}%H%n%aN%n%cs%n%s"),
+    ];
+
+    // The files whose changes are only whitespace are not listed
+    if ignore_whitespace {
+        args.push("-w".to_string());
+    }
+
+    if let Some(since) = since {
+        args.push(format!("--since={since}"));
+    }
+
+    // A merge lists the files it changed compared to its first parent
+    if first_parent {
+        args.extend(["-m".to_string(), "--first-parent".to_string()]);
+    }
+
+    if let Some(tag) = since_tag {
+        args.push(format!("{tag}..HEAD"));
+    }
+
+    args.extend(["--".to_string(), ".".to_string()]);
+
+    Ok(parse(&git::run(directory, &args)?))
+}
+
+/// The day each file of the directory was first added in the whole history,
+/// whatever the `--since` window, by path relative to the root of the
+/// repository. A renamed file is added again under its new path.
+pub(crate) fn first_commits(directory: &Path) -> Result<HashMap<PathBuf, NaiveDate>> {
+    let stdout = git::run(
+        directory,
+        [
+            "log".to_string(),
+            "--name-only".to_string(),
+            "--no-renames".to_string(),
+            "--diff-filter=A".to_string(),
+            format!("--pretty=format:{RECORD_SEPARATOR}%cs"),
+            "--".to_string(),
+            ".".to_string(),
+        ],
+    )?;
+
+    Ok(parse_first_commits(&stdout))
+}
+
+/// The authors of a commit since the date anywhere in the repository.
+pub(crate) fn authors_since(directory: &Path, since: &NaiveDate) -> Result<HashSet<String>> {
+    let stdout = git::run(
+        directory,
+        [
+            "log".to_string(),
+            "--pretty=format:%aN".to_string(),
+            format!("--since={since}"),
+        ],
+    )?;
+
+    Ok(stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn pull_request(subject: &str) -> Option<u32> {
+    let captures = PULL_REQUEST.captures(subject)?;
+
+    captures
+        .get(1)
+        .or_else(|| captures.get(2))?
+        .as_str()
+        .parse()
+        .ok()
+}
+
+/// Parse a `<added>\t<deleted>\t<path>` line of `--numstat`, the counts
+/// being `-` for binary files.
+fn parse_file_change(line: &str) -> FileChange {
+    let mut fields = line.splitn(3, '\t');
+
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(added), Some(deleted), Some(path)) => FileChange {
+            path: git::native_path(path),
+            lines: added.parse::<u32>().unwrap_or_default()
+                + deleted.parse::<u32>().unwrap_or_default(),
+        },
+        _ => FileChange {
+            path: git::native_path(line),
+            lines: 0,
+        },
+    }
+}
+
+/// The earliest day of the commits adding each file, as a file deleted and
+/// added again is listed more than once.
+fn parse_first_commits(stdout: &str) -> HashMap<PathBuf, NaiveDate> {
+    let mut first_commits: HashMap<PathBuf, NaiveDate> = HashMap::new();
+
+    for record in stdout.split(RECORD_SEPARATOR) {
+        let mut lines = record.lines();
+        let Some(date) = lines.next().and_then(|date| date.parse::<NaiveDate>().ok()) else {
+            continue;
+        };
+
+        for path in lines.filter(|line| !line.trim().is_empty()) {
+            first_commits
+                .entry(git::native_path(path))
+                .and_modify(|first_commit| *first_commit = date.min(*first_commit))
+                .or_insert(date);
+        }
+    }
+
+    first_commits
+}
+
+fn parse(stdout: &str) -> Vec<Commit> {
+    stdout
+        .split(RECORD_SEPARATOR)
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| {
+            let mut lines = record.lines();
+            let hash = lines.next().unwrap_or_default().to_string();
+            let author = lines.next().unwrap_or_default().to_string();
+            let date = lines.next().and_then(|date| date.parse().ok());
+            let subject = lines.next().unwrap_or_default().to_string();
+            let files = lines
+                .filter(|line| !line.trim().is_empty())
+                .map(parse_file_change)
+                .collect();
+
+            Commit {
+                hash,
+                author,
+                date,
+                subject,
+                files,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[test]
+    fn test_parse() {
+        // ARRANGE
+        let stdout = "\x1ea1b2\nBob\n2024-03-02\nfix: handle empty files\n3\t1\tpkg/b.py\n\n\x1ec3d4\nAlice Smith\n2024-02-28\nAdd a\n10\t0\tpkg/a.py\n-\t-\tpkg/b.py\n\x1ee5f6\nAlice Smith\n2024-02-01\nEmpty\n";
+
+        // ACT
+        let actual = parse(stdout);
+
+        // ASSERT
+        let expected = vec![
+            Commit {
+                hash: "a1b2".to_string(),
+                author: "Bob".to_string(),
+                date: NaiveDate::from_ymd_opt(2024, 3, 2),
+                subject: "fix: handle empty files".to_string(),
+                files: vec![FileChange {
+                    path: PathBuf::from("pkg/b.py"),
+                    lines: 4,
+                }],
+            },
+            Commit {
+                hash: "c3d4".to_string(),
+                author: "Alice Smith".to_string(),
+                date: NaiveDate::from_ymd_opt(2024, 2, 28),
+                subject: "Add a".to_string(),
+                files: vec![
+                    FileChange {
+                        path: PathBuf::from("pkg/a.py"),
+                        lines: 10,
+                    },
+                    FileChange {
+                        path: PathBuf::from("pkg/b.py"),
+                        lines: 0,
+                    },
+                ],
+            },
+            Commit {
+                hash: "e5f6".to_string(),
+                author: "Alice Smith".to_string(),
+                date: NaiveDate::from_ymd_opt(2024, 2, 1),
+                subject: "Empty".to_string(),
+                files: vec![],
+            },
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_first_commits() {
+        // ARRANGE
+        let stdout = "\x1e2024-03-02\npkg/b.py\n\n\x1e2024-02-28\npkg/a.py\npkg/b.py\n\x1e2023-05-01\npkg/b.py\n";
+
+        // ACT
+        let actual = parse_first_commits(stdout);
+
+        // ASSERT
+        let expected = HashMap::from([
+            (
+                PathBuf::from("pkg/a.py"),
+                NaiveDate::from_ymd_opt(2024, 2, 28).unwrap(),
+            ),
+            (
+                PathBuf::from("pkg/b.py"),
+                NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+            ),
+        ]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("Merge pull request #12 from org/branch", Some(12))]
+    #[case("Add a column (#34)", Some(34))]
+    #[case("Fix #56 in the parser", None)]
+    #[case("Merge branch 'main' into feature", None)]
+    fn test_pull_request(#[case] subject: &str, #[case] expected: Option<u32>) {
+        // ACT
+        let actual = pull_request(subject);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_commit_filter() {
+        // ARRANGE
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join(".git-blame-ignore-revs"),
+            "# Format with black\n9f19dd40cbc4\n",
+        )
+        .unwrap();
+        let ignore_commits = IgnoreCommits {
+            revs_file: Some(PathBuf::from(".git-blame-ignore-revs")),
+            messages: vec!["^style:".to_string()],
+            ..Default::default()
+        };
+        let commit = |hash: &str, subject: &str| Commit {
+            hash: hash.to_string(),
+            author: "Bob".to_string(),
+            date: None,
+            subject: subject.to_string(),
+            files: vec![],
+        };
+
+        // ACT
+        let filter = ignore_commits.filter(temp_dir.path()).unwrap();
+
+        // ASSERT
+        assert!(filter.is_ignored(&commit("9f19dd40cbc4832bc5d1", "Format")));
+        assert!(filter.is_ignored(&commit("25fbbb50c91c", "style: format")));
+        assert!(!filter.is_ignored(&commit("d27b4c60ff05", "fix: b")));
+    }
+
+    #[rstest]
+    #[case(None, vec!["Second", "First"])]
+    #[case(Some("v1.0"), vec!["Second"])]
+    fn test_read_since_tag(#[case] since_tag: Option<&str>, #[case] expected: Vec<&str>) {
+        // ARRANGE
+        let temp_dir = tempfile::tempdir().unwrap();
+        let directory = temp_dir.path();
+        git::run(directory, ["init"]).unwrap();
+        for (content, message) in [("x = 1\n", "First"), ("x = 2\n", "Second")] {
+            fs::write(directory.join("a.py"), content).unwrap();
+            git::run(directory, ["add", "--all"]).unwrap();
+            git::run(
+                directory,
+                [
+                    "-c",
+                    "user.name=test",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "-m",
+                    message,
+                ],
+            )
+            .unwrap();
+        }
+        git::run(directory, ["tag", "v1.0", "HEAD~1"]).unwrap();
+
+        // ACT
+        let actual = read(directory, None, since_tag, false, false).unwrap();
+
+        // ASSERT
+        let actual = actual
+            .iter()
+            .map(|commit| commit.subject.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,50 +1,825 @@
-use chrono::NaiveDate;
-use core::panic;
+use chrono::{Local, NaiveDate};
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
 use rust_code_analysis::ParserTrait;
-use rust_code_analysis::{metrics, PythonParser};
+use rust_code_analysis::{metrics, mi, CodeMetrics, FuncSpace, PythonParser, SpaceKind};
+use rustpython_parser::{ast, Parse};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
-use std::{collections::HashMap, fs, path::Path, process::Command};
-use tabled::Tabled;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+use tracing::{debug, info, trace, warn};
 
-#[derive(Clone, Default, Debug, PartialEq)]
-struct FileStats {
+use crate::aggregation;
+use crate::annotations::Annotations;
+use crate::cache::Cache;
+use crate::commit_type::{last_months, Churn, CommitTypes};
+use crate::config::Markers;
+use crate::coverage::Coverage;
+use crate::debt_comments::{self, DEFAULT_DEBT_MARKERS};
+use crate::discovery::{self, Discovery, StubFiles};
+use crate::duplication::{self, Duplicates, Fingerprint};
+use crate::error::{Error, Result};
+use crate::formula::Formula;
+use crate::git::{self, Worktree};
+use crate::history::{self, ChurnUnit, Commit, FileChange, IgnoreCommits};
+use crate::imports::{self, ImportGraph};
+use crate::metadata::{Metadata, FORMAT_VERSION};
+use crate::nesting::Nesting;
+use crate::overrides::Overrides;
+use crate::ownership::{
+    author_entropy, knowledge_loss, primary_owner, ActiveAuthors, Contribution, Ownership,
+};
+use crate::plugins::Plugin;
+use crate::progress::Progress;
+use crate::severity::{Severity, SeverityCutoffs};
+use crate::summary::Summary;
+use crate::suppressions::{self, Suppression};
+use crate::timings::Timings;
+use crate::totals::Totals;
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FileStats {
     pub path: PathBuf,
     pub halstead_volume: f64,
+    /// The highest cyclomatic complexity of the functions, classes and code
+    /// at the top level of the module, their average and their sum.
     pub cyclomatic_complexity: f64,
+    pub cyclomatic_average: f64,
+    pub cyclomatic_total: f64,
+    pub max_nesting: u32,
+    pub nom: u32,
+    pub longest_function: u32,
     pub loc: u32,
     pub comments_percentage: f64,
     pub maintainability_index: f64,
-    pub changes_count: u32,
+    /// Changes count with its breakdown by commit type.
+    pub churn: Churn,
+    /// Percentage of the instrumented lines executed by the tests.
+    #[serde(default)]
+    pub coverage: Option<f64>,
+    /// Percentage of the lines of code duplicated in this or other files.
+    pub duplication: f64,
+    /// The other files sharing duplicated code with this one.
+    pub duplication_partners: Vec<PathBuf>,
+    pub fingerprints: Vec<Fingerprint>,
+    /// Number of debt markers such as `TODO` in the comments.
+    pub debt_comments: u32,
+    /// Percentage of the functions with a type annotated signature.
+    pub type_annotations: Option<f64>,
+    pub functions: Vec<FunctionStats>,
+    /// Metrics of the plugins by name, computed on every run.
+    #[serde(default)]
+    pub plugins: BTreeMap<String, f64>,
+    /// Whether some metrics were NaN or infinite and were replaced.
+    #[serde(default)]
+    pub non_finite: bool,
+    /// Lines of code of the test files named after the module, unknown for
+    /// a test file.
+    #[serde(default)]
+    pub test_loc: Option<u32>,
+    /// The modules imported, see [`imports::imports`].
+    pub imports: Vec<String>,
+    /// Number of analysed modules importing this one, and imported by it.
+    #[serde(default)]
+    pub fan_in: Option<u32>,
+    #[serde(default)]
+    pub fan_out: Option<u32>,
+    /// See [`ImportGraph::centrality`].
+    #[serde(default)]
+    pub centrality: Option<f64>,
+}
+
+/// Metrics of a single function or method, the name of methods being
+/// qualified by their enclosing classes.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FunctionStats {
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub halstead_volume: f64,
+    pub cyclomatic_complexity: f64,
+    pub cognitive_complexity: f64,
+    pub max_nesting: u32,
+    pub nom: u32,
+    pub loc: u32,
+    pub comments_percentage: f64,
+    pub maintainability_index: f64,
+    #[serde(default)]
+    pub coverage: Option<f64>,
+    pub duplication: f64,
+    pub debt_comments: u32,
+    /// 100 when the signature is type annotated, 0 otherwise.
+    pub type_annotations: Option<f64>,
+}
+
+impl FunctionStats {
+    /// Number of lines spanned by the function, blank and comment lines included.
+    fn length(&self) -> u32 {
+        (self.end_line + 1).saturating_sub(self.start_line) as u32
+    }
+}
+
+impl FileStats {
+    fn display_duplication_partners(&self) -> Vec<String> {
+        self.duplication_partners
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect()
+    }
+
+    /// Replace the metrics which are NaN or infinite, e.g. from a division by
+    /// zero in a function without code, with the ones of an empty file,
+    /// returning whether any metric was replaced.
+    fn replace_non_finite(&mut self) -> bool {
+        let mut replaced = false;
+        let mut replace = |metric: &mut f64, value: f64| {
+            if !metric.is_finite() {
+                *metric = value;
+                replaced = true;
+            }
+        };
+
+        // The maintainability index is computed from the Halstead volume, the
+        // Visual Studio one clamping a NaN volume to 0
+        if !self.halstead_volume.is_finite() {
+            self.maintainability_index = f64::NAN;
+        }
+        replace(&mut self.halstead_volume, 0.0);
+        replace(&mut self.cyclomatic_complexity, 0.0);
+        replace(&mut self.cyclomatic_average, 0.0);
+        replace(&mut self.cyclomatic_total, 0.0);
+        replace(&mut self.comments_percentage, 0.0);
+        replace(&mut self.maintainability_index, 100.0);
+        for function in &mut self.functions {
+            if !function.halstead_volume.is_finite() {
+                function.maintainability_index = f64::NAN;
+            }
+            replace(&mut function.halstead_volume, 0.0);
+            replace(&mut function.cyclomatic_complexity, 0.0);
+            replace(&mut function.cognitive_complexity, 0.0);
+            replace(&mut function.comments_percentage, 0.0);
+            replace(&mut function.maintainability_index, 100.0);
+        }
+
+        replaced
+    }
+
+    /// Count the debt markers of the file and its functions from the line of
+    /// each marker.
+    fn set_debt_comments(&mut self, lines: &[usize]) {
+        self.debt_comments = lines.len() as u32;
+
+        for function in &mut self.functions {
+            function.debt_comments = lines
+                .iter()
+                .filter(|&&line| (function.start_line..=function.end_line).contains(&line))
+                .count() as u32;
+        }
+    }
+
+    /// Set the duplication of the file and its functions, none of their code
+    /// being duplicated without `duplicates`.
+    fn set_duplicates(&mut self, duplicates: Option<&Duplicates>) {
+        let Some(duplicates) = duplicates else {
+            self.duplication = 0.0;
+            self.duplication_partners = vec![];
+            for function in &mut self.functions {
+                function.duplication = 0.0;
+            }
+            return;
+        };
+
+        self.duplication = duplicates.percentage(0, usize::MAX, self.loc);
+        self.duplication_partners = duplicates
+            .partners
+            .iter()
+            .map(|path| path.to_path_buf())
+            .collect();
+
+        for function in &mut self.functions {
+            function.duplication =
+                duplicates.percentage(function.start_line, function.end_line, function.loc);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    #[default]
+    File,
+    Function,
+}
+
+impl ValueEnum for Granularity {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Granularity::File, Granularity::Function]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Granularity::File => PossibleValue::new("file").help("One row per file"),
+            Granularity::Function => {
+                PossibleValue::new("function").help("One row per function or method")
+            }
+        })
+    }
+}
+
+/// What the churn of the default hotspot index counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChurnWeight {
+    #[default]
+    Commits,
+    Lines,
+}
+
+impl ChurnWeight {
+    pub(crate) fn churn(&self, stat: &HotspotStats) -> u32 {
+        match self {
+            ChurnWeight::Commits => stat.changes_count,
+            ChurnWeight::Lines => stat.lines_changed,
+        }
+    }
+}
+
+impl ValueEnum for ChurnWeight {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ChurnWeight::Commits, ChurnWeight::Lines]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ChurnWeight::Commits => PossibleValue::new("commits").help("The changes count"),
+            ChurnWeight::Lines => {
+                PossibleValue::new("lines").help("The lines added and deleted by the changes")
+            }
+        })
+    }
+}
+
+/// How the default hotspot index weighs a row, ignored with a formula.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct IndexWeights {
+    pub churn: ChurnWeight,
+    /// Multiply the index by the centrality of the module in the import
+    /// graph, see [`ImportGraph::centrality`].
+    pub centrality: bool,
+}
+
+impl IndexWeights {
+    pub(crate) fn hotspot_index(
+        &self,
+        stat: &HotspotStats,
+        mi_range: MiRange,
+        zero_mi: ZeroMi,
+    ) -> f64 {
+        let hotspot_index = hotspot_index(
+            self.churn.churn(stat),
+            mi_range.percentage(stat.maintainability_index),
+            zero_mi,
+        );
+
+        match (self.centrality, stat.centrality) {
+            (true, Some(centrality)) => hotspot_index * centrality,
+            _ => hotspot_index,
+        }
+    }
+}
+
+/// Formula of the maintainability index computed by `rust-code-analysis`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MiVariant {
+    #[default]
+    VisualStudio,
+    Original,
+    Sei,
+}
+
+impl MiVariant {
+    fn maintainability_index(&self, mi: &mi::Stats) -> f64 {
+        match self {
+            MiVariant::VisualStudio => mi.mi_visual_studio(),
+            MiVariant::Original => mi.mi_original(),
+            MiVariant::Sei => mi.mi_sei(),
+        }
+    }
+}
+
+impl ValueEnum for MiVariant {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[MiVariant::Original, MiVariant::Sei, MiVariant::VisualStudio]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            MiVariant::Original => PossibleValue::new("original")
+                .help("171 - 5.2 ln(volume) - 0.23 complexity - 16.2 ln(loc), up to 171 and possibly negative"),
+            MiVariant::Sei => PossibleValue::new("sei")
+                .help("The original one with a bonus for the comments, possibly negative"),
+            MiVariant::VisualStudio => PossibleValue::new("visual-studio")
+                .help("The original one rescaled to 0-100"),
+        })
+    }
+}
+
+/// Range of the maintainability index of the rows, the `original` and `sei`
+/// variants going above 100 or below 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MiRange {
+    #[default]
+    Raw,
+    Clamp,
+    Unit,
+}
+
+impl MiRange {
+    pub(crate) fn apply(&self, maintainability_index: f64) -> f64 {
+        match self {
+            MiRange::Raw => maintainability_index,
+            MiRange::Clamp => maintainability_index.clamp(0.0, 100.0),
+            MiRange::Unit => maintainability_index.clamp(0.0, 100.0) / 100.0,
+        }
+    }
+
+    /// The maintainability index of a row back on the 0-100 scale of the
+    /// default hotspot index, so the range does not change it.
+    pub(crate) fn percentage(&self, maintainability_index: f64) -> f64 {
+        match self {
+            MiRange::Unit => maintainability_index * 100.0,
+            _ => maintainability_index,
+        }
+    }
+}
+
+impl ValueEnum for MiRange {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[MiRange::Raw, MiRange::Clamp, MiRange::Unit]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            MiRange::Raw => PossibleValue::new("raw").help("As computed by the variant"),
+            MiRange::Clamp => PossibleValue::new("clamp").help("Clamped to 0-100"),
+            MiRange::Unit => {
+                PossibleValue::new("unit").help("Clamped to 0-100 and rescaled to 0-1")
+            }
+        })
+    }
+}
+
+/// Hotspot index given when the maintainability index is 0, the changes
+/// count over it being infinite.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ZeroMi {
+    #[default]
+    Infinity,
+    Cap,
+    Epsilon,
+    Null,
+}
+
+impl ValueEnum for ZeroMi {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ZeroMi::Infinity, ZeroMi::Cap, ZeroMi::Epsilon, ZeroMi::Null]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ZeroMi::Infinity => PossibleValue::new("infinity").help("An infinite hotspot index"),
+            ZeroMi::Cap => PossibleValue::new("cap").help("A hotspot index of 1e9"),
+            ZeroMi::Epsilon => PossibleValue::new("epsilon")
+                .help("Compute the hotspot index with a maintainability index of 0.01"),
+            ZeroMi::Null => PossibleValue::new("null").help(
+                "An unknown hotspot index, null in JSON, empty in the tables and sorted last",
+            ),
+        })
+    }
 }
 
-#[derive(Tabled, Debug, PartialEq)]
+/// Hotspot index of the files with a maintainability index of 0 with
+/// `--zero-mi cap`.
+const CAPPED_HOTSPOT_INDEX: f64 = 1e9;
+/// Maintainability index of the files with a maintainability index of 0 with
+/// `--zero-mi epsilon`.
+const EPSILON_MAINTAINABILITY_INDEX: f64 = 0.01;
+
+/// What the path of a row refers to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathType {
+    #[default]
+    File,
+    Directory,
+}
+
+impl PathType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PathType::File => "file",
+            PathType::Directory => "directory",
+        }
+    }
+}
+
+/// A row of the report, read back with `#[serde(default)]` so snapshots
+/// written by older versions load with the missing metrics zeroed.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
 pub struct HotspotStats {
     pub path: String,
+    pub path_type: PathType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
     pub halstead_volume: f64,
+    /// The highest cyclomatic complexity of the functions of a file, the
+    /// average of them and their sum, all three the complexity of the
+    /// function for its rows. A directory has the highest of its files, the
+    /// average of their averages and the sum of their totals.
     pub cyclomatic_complexity: f64,
+    pub cyclomatic_average: f64,
+    pub cyclomatic_total: f64,
+    pub max_nesting: u32,
+    pub nom: u32,
+    pub longest_function: u32,
     pub loc: u32,
     pub comments_percentage: f64,
     pub maintainability_index: f64,
     pub changes_count: u32,
+    /// Changes in the last 30, 90 and 365 days, counted in the same unit as
+    /// the changes count and within its `--since` window.
+    pub changes_30d: u32,
+    pub changes_90d: u32,
+    pub changes_365d: u32,
+    /// Lines added and deleted by the changes.
+    pub lines_changed: u32,
+    /// Changes of the commits of each type, their sum being at most the
+    /// changes count.
+    pub feature_changes: u32,
+    pub fix_changes: u32,
+    pub refactor_changes: u32,
+    pub chore_changes: u32,
+    /// Shannon entropy in bits of the changes across the authors, 0 when a
+    /// single author made all the changes.
+    pub author_entropy: f64,
+    /// Percentage of the changes made by authors no longer active, unknown
+    /// unless the active authors are given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub knowledge_loss: Option<f64>,
+    /// Changes count of each of the last 12 months by `YYYY-MM`, of the file
+    /// for its functions, only with the monthly changes requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_changes: Option<BTreeMap<String, u32>>,
+    /// Day the file was first committed, of the file for its functions and
+    /// of the oldest file of a directory, unknown for a file never
+    /// committed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_commit: Option<NaiveDate>,
+    /// Author of the most recent change, of the file for its functions,
+    /// unknown for a directory or a file without changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_author: Option<String>,
+    /// Author of the most changes with their share of the changes, of the
+    /// file for its functions, unknown for a directory or a file without
+    /// changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_owner: Option<Contribution>,
     pub hotspot_index: f64,
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk: Option<f64>,
+    /// Places moved up the ranking by hotspot index since the baseline,
+    /// negative when moved down, unknown without a baseline or for a new
+    /// row.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank_delta: Option<i64>,
+    /// Change of the hotspot index since the baseline, unknown without a
+    /// baseline or for a new row.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hotspot_index_delta: Option<f64>,
+    pub duplication: f64,
+    pub duplication_partners: Vec<String>,
+    pub debt_comments: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_annotations: Option<f64>,
+    /// Lines of code of the tests over the lines of code of the rest of the
+    /// files of a directory, unknown for files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_ratio: Option<f64>,
+    /// Lines of code of the test files named after the module of a file,
+    /// of the file for its functions and summed over the files of a
+    /// directory, unknown for the test files and when streaming.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_loc: Option<u32>,
+    /// Number of analysed modules importing the module of a file and
+    /// imported by it, of the file for its functions and the highest of the
+    /// files of a directory, unknown when streaming.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_in: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_out: Option<u32>,
+    /// The PageRank of the module of a file in the import graph, 1 on
+    /// average, of the file for its functions and the highest of the files
+    /// of a directory, unknown when streaming.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub centrality: Option<f64>,
+    /// Metrics of the plugins by name, the ones of the file for its
+    /// functions and summed over the files of a directory.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, f64>,
 }
 
 impl HotspotStats {
     fn new(file_stats: &FileStats) -> HotspotStats {
-        let hotspot_index = match file_stats.maintainability_index {
-            0.0 => f64::INFINITY,
-            _ => file_stats.changes_count as f64 / (file_stats.maintainability_index / 100.0),
-        };
-
         HotspotStats {
             path: file_stats.path.display().to_string(),
+            path_type: PathType::File,
+            function: None,
+            start_line: None,
+            end_line: None,
             halstead_volume: file_stats.halstead_volume,
             cyclomatic_complexity: file_stats.cyclomatic_complexity,
+            cyclomatic_average: file_stats.cyclomatic_average,
+            cyclomatic_total: file_stats.cyclomatic_total,
+            max_nesting: file_stats.max_nesting,
+            nom: file_stats.nom,
+            longest_function: file_stats.longest_function,
             loc: file_stats.loc,
             comments_percentage: file_stats.comments_percentage,
             maintainability_index: file_stats.maintainability_index,
-            changes_count: file_stats.changes_count,
-            hotspot_index,
+            changes_count: file_stats.churn.changes_count,
+            changes_30d: file_stats.churn.changes_30d,
+            changes_90d: file_stats.churn.changes_90d,
+            changes_365d: file_stats.churn.changes_365d,
+            lines_changed: file_stats.churn.lines_changed,
+            feature_changes: file_stats.churn.feature,
+            fix_changes: file_stats.churn.fix,
+            refactor_changes: file_stats.churn.refactor,
+            chore_changes: file_stats.churn.chore,
+            author_entropy: file_stats.churn.author_entropy,
+            knowledge_loss: file_stats.churn.knowledge_loss,
+            monthly_changes: file_stats.churn.monthly_changes.clone(),
+            first_commit: file_stats.churn.first_commit,
+            last_author: file_stats.churn.last_author.clone(),
+            primary_owner: file_stats.churn.primary_owner.clone(),
+            hotspot_index: hotspot_index(
+                file_stats.churn.changes_count,
+                file_stats.maintainability_index,
+                ZeroMi::default(),
+            ),
+            coverage: file_stats.coverage,
+            risk: risk(
+                file_stats.churn.changes_count,
+                file_stats.cyclomatic_complexity,
+                file_stats.coverage,
+            ),
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: file_stats.duplication,
+            duplication_partners: file_stats.display_duplication_partners(),
+            debt_comments: file_stats.debt_comments,
+            type_annotations: file_stats.type_annotations,
+            test_ratio: None,
+            test_loc: file_stats.test_loc,
+            fan_in: file_stats.fan_in,
+            fan_out: file_stats.fan_out,
+            centrality: file_stats.centrality,
+            plugins: file_stats.plugins.clone(),
+            severity: Default::default(),
+        }
+    }
+
+    /// The changes count of a function is the one of its file, Git does not
+    /// track functions.
+    fn for_function(file_stats: &FileStats, function_stats: &FunctionStats) -> HotspotStats {
+        HotspotStats {
+            path: file_stats.path.display().to_string(),
+            path_type: PathType::File,
+            function: Some(function_stats.name.clone()),
+            start_line: Some(function_stats.start_line),
+            end_line: Some(function_stats.end_line),
+            halstead_volume: function_stats.halstead_volume,
+            cyclomatic_complexity: function_stats.cyclomatic_complexity,
+            cyclomatic_average: function_stats.cyclomatic_complexity,
+            cyclomatic_total: function_stats.cyclomatic_complexity,
+            max_nesting: function_stats.max_nesting,
+            nom: function_stats.nom,
+            longest_function: function_stats.length(),
+            loc: function_stats.loc,
+            comments_percentage: function_stats.comments_percentage,
+            maintainability_index: function_stats.maintainability_index,
+            changes_count: file_stats.churn.changes_count,
+            changes_30d: file_stats.churn.changes_30d,
+            changes_90d: file_stats.churn.changes_90d,
+            changes_365d: file_stats.churn.changes_365d,
+            lines_changed: file_stats.churn.lines_changed,
+            feature_changes: file_stats.churn.feature,
+            fix_changes: file_stats.churn.fix,
+            refactor_changes: file_stats.churn.refactor,
+            chore_changes: file_stats.churn.chore,
+            author_entropy: file_stats.churn.author_entropy,
+            knowledge_loss: file_stats.churn.knowledge_loss,
+            monthly_changes: file_stats.churn.monthly_changes.clone(),
+            first_commit: file_stats.churn.first_commit,
+            last_author: file_stats.churn.last_author.clone(),
+            primary_owner: file_stats.churn.primary_owner.clone(),
+            hotspot_index: hotspot_index(
+                file_stats.churn.changes_count,
+                function_stats.maintainability_index,
+                ZeroMi::default(),
+            ),
+            coverage: function_stats.coverage,
+            risk: risk(
+                file_stats.churn.changes_count,
+                function_stats.cyclomatic_complexity,
+                function_stats.coverage,
+            ),
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: function_stats.duplication,
+            duplication_partners: file_stats.display_duplication_partners(),
+            debt_comments: function_stats.debt_comments,
+            type_annotations: function_stats.type_annotations,
+            test_ratio: None,
+            test_loc: file_stats.test_loc,
+            fan_in: file_stats.fan_in,
+            fan_out: file_stats.fan_out,
+            centrality: file_stats.centrality,
+            plugins: file_stats.plugins.clone(),
+            severity: Default::default(),
+        }
+    }
+
+    fn from_file_stats(
+        file_stats: &FileStats,
+        granularity: Granularity,
+        formula: Option<&Formula>,
+        weights: IndexWeights,
+        mi_range: MiRange,
+        zero_mi: ZeroMi,
+        severity_cutoffs: &SeverityCutoffs,
+    ) -> Vec<HotspotStats> {
+        let mut stats = match granularity {
+            Granularity::File => vec![HotspotStats::new(file_stats)],
+            Granularity::Function => file_stats
+                .functions
+                .iter()
+                .map(|function_stats| HotspotStats::for_function(file_stats, function_stats))
+                .collect(),
+        };
+
+        for stat in &mut stats {
+            stat.maintainability_index = mi_range.apply(stat.maintainability_index);
+            stat.hotspot_index = match formula {
+                Some(formula) => formula.evaluate(stat),
+                None => weights.hotspot_index(stat, mi_range, zero_mi),
+            };
+            stat.severity = severity_cutoffs.severity(stat.hotspot_index);
+        }
+
+        stats
+    }
+}
+
+/// The severity cutoffs of a file or directory relative to the root of the
+/// repository, the directories of the overrides being relative to the
+/// analysed directory.
+fn severity_cutoffs_of<'a>(
+    overrides: &'a Overrides<SeverityCutoffs>,
+    git_base_path: &Path,
+    directory: &Path,
+    path: &Path,
+) -> &'a SeverityCutoffs {
+    let path = git_base_path.join(path);
+
+    overrides.get(path.strip_prefix(directory).unwrap_or(&path))
+}
+
+/// The changes count over the maintainability index, a `NaN` index being
+/// unknown.
+pub(crate) fn hotspot_index(
+    changes_count: u32,
+    maintainability_index: f64,
+    zero_mi: ZeroMi,
+) -> f64 {
+    let maintainability_index = match (maintainability_index, zero_mi) {
+        (0.0, ZeroMi::Infinity) => return f64::INFINITY,
+        (0.0, ZeroMi::Cap) => return CAPPED_HOTSPOT_INDEX,
+        (0.0, ZeroMi::Null) => return f64::NAN,
+        (0.0, ZeroMi::Epsilon) => EPSILON_MAINTAINABILITY_INDEX,
+        (maintainability_index, _) => maintainability_index,
+    };
+
+    changes_count as f64 / (maintainability_index / 100.0)
+}
+
+/// Changes count times complexity scaled by the share of uncovered lines,
+/// unknown without coverage.
+pub(crate) fn risk(
+    changes_count: u32,
+    cyclomatic_complexity: f64,
+    coverage: Option<f64>,
+) -> Option<f64> {
+    coverage.map(|coverage| changes_count as f64 * cyclomatic_complexity * (1.0 - coverage / 100.0))
+}
+
+/// The metrics of a function nested under the row of its file in the
+/// detailed NDJSON output.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDetail {
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub cyclomatic_complexity: f64,
+    pub cognitive_complexity: f64,
+    pub loc: u32,
+}
+
+impl FunctionDetail {
+    fn new(function_stats: &FunctionStats) -> FunctionDetail {
+        FunctionDetail {
+            name: function_stats.name.clone(),
+            start_line: function_stats.start_line,
+            end_line: function_stats.end_line,
+            cyclomatic_complexity: function_stats.cyclomatic_complexity,
+            cognitive_complexity: function_stats.cognitive_complexity,
+            loc: function_stats.loc,
+        }
+    }
+}
+
+/// A file left out of the analysis.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Skipped {
+    pub path: String,
+    pub error: String,
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "the parser panicked".to_string())
+}
+
+/// Capacity of the channels between the stages of the pipeline, bounding the
+/// number of files held in memory at any time.
+const CHANNEL_BOUND: usize = 256;
+
+/// A file parsed by a worker of the pipeline.
+struct ParsedFile {
+    path: PathBuf,
+    stats: FileStats,
+    /// Hash of the content, set when the metrics were not found in the cache.
+    new_hash: Option<u64>,
+    /// Whether the content is not valid UTF-8 and was decoded lossily.
+    lossy: bool,
+    /// Time spent reading and parsing the file.
+    duration: Duration,
+    /// The file or the functions suppressed by comments.
+    suppressions: Vec<Suppression>,
+    /// Whether the whole file is suppressed, its metrics being left out.
+    ignored: bool,
+}
+
+impl ParsedFile {
+    /// Remove the suppressed functions, once the metrics are cached with
+    /// them so the comments are matched to the same functions on the next
+    /// runs.
+    fn remove_suppressed_functions(&mut self) {
+        let names = self
+            .suppressions
+            .iter()
+            .filter_map(|suppression| suppression.function.as_deref())
+            .collect::<HashSet<_>>();
+
+        self.stats
+            .functions
+            .retain(|function| !names.contains(function.name.as_str()));
+    }
+
+    /// Set the path of the suppressions once the path of the stats is
+    /// relative to the root of the repository.
+    fn set_suppressions_path(&mut self) {
+        for suppression in &mut self.suppressions {
+            suppression.path = self.stats.path.display().to_string();
         }
     }
 }
@@ -53,221 +828,1783 @@ impl HotspotStats {
 pub struct TechDebtHotspots {
     git_base_path: PathBuf,
     path: PathBuf,
-    exclude: Option<PathBuf>,
+    excludes: Vec<PathBuf>,
     since: Option<NaiveDate>,
+    since_tag: Option<String>,
+    churn_unit: ChurnUnit,
+    churn_weight: ChurnWeight,
+    centrality_weight: bool,
+    ignore_commits: IgnoreCommits,
+    files: Option<Vec<PathBuf>>,
     stats: HashMap<PathBuf, FileStats>,
+    errors: Vec<Error>,
+    lossy: Vec<PathBuf>,
+    non_finite: Vec<PathBuf>,
+    suppressions: HashMap<PathBuf, Vec<Suppression>>,
+    timings: Timings,
+    files_found: usize,
+    cache: Option<Cache>,
+    progress: Progress,
+    formula: Option<Formula>,
+    coverage: Option<Coverage>,
+    debt_markers: Vec<String>,
+    plugins: Vec<Plugin>,
+    mi_variant: MiVariant,
+    mi_range: MiRange,
+    zero_mi: ZeroMi,
+    max_file_size: Option<u64>,
+    nan_safe: bool,
+    follow_symlinks: bool,
+    stubs: StubFiles,
+    default_excludes: bool,
+    tracked_only: bool,
+    include_untracked: bool,
+    severity_cutoffs: Overrides<SeverityCutoffs>,
+    commit_types: CommitTypes,
+    active_authors: Option<ActiveAuthors>,
+    monthly_changes: bool,
+    /// The checkout of the analysed revision, kept until the analysis is
+    /// dropped.
+    worktree: Option<Worktree>,
 }
 
 impl TechDebtHotspots {
-    pub fn new(directory: &Path, exclude: Option<&Path>, since: Option<&NaiveDate>) -> Self {
-        Self {
+    pub fn new(
+        directory: &Path,
+        exclude: Option<&Path>,
+        since: Option<&NaiveDate>,
+    ) -> Result<Self> {
+        Ok(Self {
             path: directory.to_path_buf(),
-            exclude: exclude.map(|p| p.to_path_buf()),
+            excludes: exclude.map(|p| p.to_path_buf()).into_iter().collect(),
             since: since.cloned(),
-            git_base_path: Self::get_git_base_path(directory),
+            git_base_path: Self::get_git_base_path(directory)?,
+            debt_markers: DEFAULT_DEBT_MARKERS
+                .iter()
+                .map(|marker| marker.to_string())
+                .collect(),
+            default_excludes: true,
             ..Default::default()
-        }
+        })
     }
 
-    pub fn stats(&self) -> Vec<HotspotStats> {
-        self.stats.values().map(HotspotStats::new).collect()
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 
-    pub fn collect(&mut self) {
-        self.collect_filenames()
-            .get_stats_from_filenames()
-            .collect_changes_count()
-            .normalise_to_git_root();
+    /// The root of the repository, the paths of the rows being relative to
+    /// it.
+    pub fn root(&self) -> &Path {
+        &self.git_base_path
     }
 
-    fn collect_filenames(&mut self) -> &mut Self {
-        let mut paths_to_visit = vec![self.path.clone()];
+    /// Analyse the given files instead of walking the directory.
+    pub fn with_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = Some(files);
+        self
+    }
 
-        while let Some(current_path) = paths_to_visit.pop() {
-            if let Some(ref exclude) = self.exclude {
-                if current_path.starts_with(exclude) {
-                    continue;
-                }
-            }
+    /// Reuse the metrics of unchanged files and the changes count of an
+    /// unchanged history from the given cache directory.
+    pub fn with_cache(mut self, directory: &Path) -> Self {
+        self.cache = Some(Cache::load(directory));
+        self
+    }
 
-            match current_path.is_dir() {
-                true => {
-                    current_path.read_dir().unwrap().for_each(|entry| {
-                        let path_to_visit = entry.unwrap().path();
-                        paths_to_visit.push(path_to_visit);
-                    });
-                }
-                false if current_path.extension().and_then(|s| s.to_str()) == Some("py") => {
-                    self.stats.insert(
-                        current_path.to_path_buf(),
-                        FileStats {
-                            path: current_path,
-                            ..Default::default()
-                        },
-                    );
-                }
-                _ => {}
-            }
-        }
+    pub fn with_progress(mut self, enabled: bool) -> Self {
+        self.progress = Progress::new(enabled);
+        self
+    }
 
+    /// Compute the hotspot index with the given formula instead of the
+    /// changes count over the maintainability index.
+    pub fn with_formula(mut self, formula: Formula) -> Self {
+        self.formula = Some(formula);
         self
     }
 
-    pub fn collect_changes_count(&mut self) -> &mut Self {
-        let mut command = Command::new("git");
+    /// Join the line coverage of the report with the stats of the files and
+    /// functions.
+    pub fn with_coverage(mut self, coverage: Coverage) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
 
-        command
-            .current_dir(self.path.clone())
-            .arg("log")
-            .arg("--name-only")
-            .arg("--pretty=format:");
+    /// Count the given markers in the comments instead of `TODO`, `FIXME`,
+    /// `HACK` and `XXX`.
+    pub fn with_debt_markers(mut self, markers: Vec<String>) -> Self {
+        self.debt_markers = markers;
+        self
+    }
 
-        if let Some(since) = self.since {
-            command.arg(format!("--since={since}"));
-        }
+    /// Add the metrics of the plugins to the ones of each file.
+    pub fn with_plugins(mut self, plugins: Vec<Plugin>) -> Self {
+        self.plugins = plugins;
+        self
+    }
 
-        let output = command
-            .arg(".")
-            .output()
-            .map_err(|e| format!("Failed to execute git command: {e}"))
-            .unwrap();
+    /// Compute the maintainability index with the given formula instead of
+    /// the one of Visual Studio.
+    pub fn with_mi_variant(mut self, mi_variant: MiVariant) -> Self {
+        self.mi_variant = mi_variant;
+        self
+    }
 
-        if !output.status.success() {
-            panic!(
-                "Git command failed with status {}: {:?}",
-                output.status,
-                String::from_utf8_lossy(&output.stderr)
-            );
-        }
+    /// Clamp or rescale the maintainability index of the rows.
+    pub fn with_mi_range(mut self, mi_range: MiRange) -> Self {
+        self.mi_range = mi_range;
+        self
+    }
 
-        let stdout = String::from_utf8(output.stdout)
-            .map_err(|e| format!("Failed to parse git output: {e}"))
-            .unwrap();
-        let lines = stdout.lines().filter(|line| !line.trim().is_empty());
+    /// Give the files with a maintainability index of 0 a finite or unknown
+    /// hotspot index instead of an infinite one.
+    pub fn with_zero_mi(mut self, zero_mi: ZeroMi) -> Self {
+        self.zero_mi = zero_mi;
+        self
+    }
 
-        for line in lines {
-            let filename_path = PathBuf::from(line);
-            let absolute_path = self.git_base_path.join(&filename_path);
+    /// Skip the files larger than the given number of bytes, such as
+    /// generated or vendored files, instead of parsing them.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
 
-            if !absolute_path.exists() {
-                continue;
-            }
+    /// Replace the metrics which are NaN or infinite with the ones of an
+    /// empty file, listing the files in [`non_finite`](Self::non_finite),
+    /// instead of keeping values which cannot be sorted nor written to CSV.
+    pub fn with_nan_safe(mut self, enabled: bool) -> Self {
+        self.nan_safe = enabled;
+        self
+    }
 
-            // update filename stats
-            if let Some(existing) = self.stats.get_mut(&absolute_path) {
-                existing.changes_count += 1;
-            };
-        }
+    /// Follow the symbolic links inside the directory instead of skipping
+    /// them.
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
 
+    /// Analyse the `.pyi` stub files along the modules, or alone, instead
+    /// of skipping them.
+    pub fn with_stubs(mut self, stubs: StubFiles) -> Self {
+        self.stubs = stubs;
         self
     }
 
-    fn get_stats_from_filenames(&mut self) -> &mut Self {
-        for (_, file_stats) in self.stats.iter_mut() {
-            Self::get_stats_from_filename(file_stats);
-        }
+    /// Skip the virtual environments, dependencies and build outputs of
+    /// `DEFAULT_EXCLUDES`, enabled by default.
+    pub fn with_default_excludes(mut self, enabled: bool) -> Self {
+        self.default_excludes = enabled;
+        self
+    }
 
+    /// Analyse the files tracked by git instead of walking the directory.
+    pub fn with_tracked_only(mut self, enabled: bool) -> Self {
+        self.tracked_only = enabled;
         self
     }
 
-    fn get_stats_from_filename(file_stats: &mut FileStats) {
-        let path = Path::new(&file_stats.path).to_path_buf();
-        let source_code = fs::read(path.clone()).unwrap();
-        let parser = PythonParser::new(source_code, &path, None);
+    /// Add the untracked files which are not ignored to the tracked ones,
+    /// with no changes.
+    pub fn with_include_untracked(mut self, enabled: bool) -> Self {
+        self.include_untracked = enabled;
+        self
+    }
 
-        if let Some(s) = metrics(&parser, &path) {
-            let sloc = s.metrics.loc.sloc();
+    /// Bucket the hotspot index with the given cutoffs instead of the
+    /// defaults, the directories of the overrides being relative to the
+    /// analysed directory.
+    pub fn with_severity_cutoffs(mut self, cutoffs: impl Into<Overrides<SeverityCutoffs>>) -> Self {
+        self.severity_cutoffs = cutoffs.into();
+        self
+    }
 
-            match sloc {
-                0.0 => {
-                    file_stats.maintainability_index = 100.0;
-                    file_stats.comments_percentage = 0.0;
-                    file_stats.halstead_volume = 0.0;
-                }
-                _ => {
-                    file_stats.maintainability_index = s.metrics.mi.mi_visual_studio();
-                    file_stats.comments_percentage = s.metrics.loc.cloc() / sloc * 100.0;
-                    file_stats.halstead_volume = s.metrics.halstead.volume();
-                }
-            }
+    /// Skip another file or directory.
+    pub fn with_exclude(mut self, exclude: impl Into<PathBuf>) -> Self {
+        self.excludes.push(exclude.into());
+        self
+    }
 
-            file_stats.path = path;
-            file_stats.cyclomatic_complexity = s.metrics.cyclomatic.cyclomatic_max();
-            file_stats.loc = sloc as u32;
-        };
+    /// Classify the commits with the given patterns instead of the
+    /// conventional commit prefixes.
+    pub fn with_commit_types(mut self, commit_types: CommitTypes) -> Self {
+        self.commit_types = commit_types;
+        self
+    }
+
+    /// Only count the changes committed after the tag.
+    pub fn with_since_tag(mut self, tag: impl Into<String>) -> Self {
+        self.since_tag = Some(tag.into());
+        self
     }
 
-    fn normalise_to_git_root(&mut self) -> &mut Self {
-        for (_, file_stats) in self.stats.iter_mut() {
-            let path = Path::new(&file_stats.path).to_path_buf();
-            let relative_path = path.strip_prefix(&self.git_base_path);
+    /// Count the merges or pull requests changing the files instead of the
+    /// commits.
+    pub fn with_churn_unit(mut self, churn_unit: ChurnUnit) -> Self {
+        self.churn_unit = churn_unit;
+        self
+    }
 
-            match relative_path {
-                Ok(relative_path) => file_stats.path = relative_path.to_path_buf(),
-                Err(_) => panic!("Path is not in the Git repository"),
-            }
-        }
+    /// Compute the default hotspot index with the lines changed instead of
+    /// the changes count.
+    pub fn with_churn_weight(mut self, churn_weight: ChurnWeight) -> Self {
+        self.churn_weight = churn_weight;
+        self
+    }
 
+    /// Leave commits out of the history, e.g. the reformatting of the code.
+    pub fn with_ignore_commits(mut self, ignore_commits: IgnoreCommits) -> Self {
+        self.ignore_commits = ignore_commits;
         self
     }
 
-    fn get_git_base_path(directory: &Path) -> PathBuf {
-        let output = Command::new("git")
-            .current_dir(directory)
-            .arg("rev-parse")
-            .arg("--show-toplevel")
-            .output()
-            .unwrap();
+    /// Measure the knowledge lost with the changes of the authors who are
+    /// not active.
+    pub fn with_active_authors(mut self, active_authors: ActiveAuthors) -> Self {
+        self.active_authors = Some(active_authors);
+        self
+    }
 
-        if !output.status.success() {
-            panic!(
-                "Failed to get git base path: {:?}",
-                String::from_utf8_lossy(&output.stderr)
-            );
+    /// Multiply the default hotspot index by the centrality of the module of
+    /// each file in the import graph.
+    pub fn with_centrality_weight(mut self, enabled: bool) -> Self {
+        self.centrality_weight = enabled;
+        self
+    }
+
+    /// Count the changes of each of the last 12 months.
+    pub fn with_monthly_changes(mut self, enabled: bool) -> Self {
+        self.monthly_changes = enabled;
+        self
+    }
+
+    pub(crate) fn with_worktree(mut self, worktree: Worktree) -> Self {
+        self.worktree = Some(worktree);
+        self
+    }
+
+    fn index_weights(&self) -> IndexWeights {
+        IndexWeights {
+            churn: self.churn_weight,
+            centrality: self.centrality_weight,
         }
+    }
 
-        let stdout = String::from_utf8(output.stdout).unwrap();
-        let path = PathBuf::from(stdout.trim());
+    pub fn stats(&self) -> Vec<HotspotStats> {
+        self.stats_by(Granularity::File)
+    }
 
-        path
+    pub fn stats_by(&self, granularity: Granularity) -> Vec<HotspotStats> {
+        self.stats
+            .values()
+            .flat_map(|file_stats| {
+                HotspotStats::from_file_stats(
+                    file_stats,
+                    granularity,
+                    self.formula.as_ref(),
+                    self.index_weights(),
+                    self.mi_range,
+                    self.zero_mi,
+                    severity_cutoffs_of(
+                        &self.severity_cutoffs,
+                        &self.git_base_path,
+                        &self.path,
+                        &file_stats.path,
+                    ),
+                )
+            })
+            .collect()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rstest::*;
-    use tempfile::{tempdir, TempDir};
+    /// The functions of each file by path, in the order of their lines.
+    pub fn function_details(&self) -> HashMap<String, Vec<FunctionDetail>> {
+        self.stats
+            .values()
+            .map(|file_stats| {
+                let mut functions = file_stats
+                    .functions
+                    .iter()
+                    .map(FunctionDetail::new)
+                    .collect::<Vec<_>>();
+                functions.sort_by_key(|function| (function.start_line, function.end_line));
 
-    #[test]
+                (file_stats.path.display().to_string(), functions)
+            })
+            .collect()
+    }
+
+    /// The stats of the files rolled up into each directory containing them,
+    /// up to the analysed directory.
+    pub fn directory_stats(&self) -> Vec<HotspotStats> {
+        let root = self
+            .path
+            .strip_prefix(&self.git_base_path)
+            .unwrap_or(Path::new(""));
+
+        let mut stats = aggregation::directories(
+            &self.stats(),
+            root,
+            self.formula.as_ref(),
+            self.index_weights(),
+            self.mi_range,
+            self.zero_mi,
+        );
+
+        for stat in &mut stats {
+            stat.severity = severity_cutoffs_of(
+                &self.severity_cutoffs,
+                &self.git_base_path,
+                &self.path,
+                Path::new(&stat.path),
+            )
+            .severity(stat.hotspot_index);
+        }
+
+        stats
+    }
+
+    /// The contributors of the given files and what each author owns among
+    /// them, from the Git history.
+    pub fn ownership(&self, stats: &[HotspotStats]) -> Result<Ownership> {
+        Ok(Ownership::new(stats, &self.read_history(false)?))
+    }
+
+    /// Errors of the files which were skipped, the stats of the remaining
+    /// files are still collected.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// The files which are not valid UTF-8, e.g. with latin-1 comments,
+    /// analysed after replacing the invalid bytes so their metrics are
+    /// approximate.
+    pub fn lossy(&self) -> &[PathBuf] {
+        &self.lossy
+    }
+
+    /// The files with metrics which were NaN or infinite and were replaced,
+    /// only with [`with_nan_safe`](Self::with_nan_safe).
+    pub fn non_finite(&self) -> &[PathBuf] {
+        &self.non_finite
+    }
+
+    /// The files and functions left out by a `# hotspot: ignore` or
+    /// `# hotspot: ignore-next-function` comment, by path and line.
+    pub fn suppressions(&self) -> Vec<Suppression> {
+        let mut suppressions = self
+            .suppressions
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        suppressions.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+
+        suppressions
+    }
+
+    /// Time spent in each stage of the last analysis.
+    pub fn timings(&self) -> &Timings {
+        &self.timings
+    }
+
+    /// The number of files found by the last analysis, the skipped ones
+    /// included.
+    pub fn files_found(&self) -> usize {
+        self.files_found
+    }
+
+    /// The version of the tool, repository, commit and `--since` window of
+    /// the analysis with the number of analysed and skipped files.
+    pub fn metadata(&self) -> Metadata {
+        let commit = git::run(&self.path, ["rev-parse", "HEAD"])
+            .ok()
+            .map(|head| head.trim().to_string());
+
+        Metadata::new(
+            self.git_base_path.display().to_string(),
+            commit,
+            self.since
+                .map(|since| since.to_string())
+                .or_else(|| self.since_tag.clone()),
+            self.stats.len(),
+            self.errors.len(),
+        )
+    }
+
+    /// The lines of code, changes, maintainability index and severities of
+    /// every file, whatever the rows of a report.
+    pub fn totals(&self) -> Totals {
+        Totals::new(&self.stats())
+    }
+
+    /// The distribution of the metrics, the files above the thresholds and
+    /// the top hotspots of the project.
+    pub fn summary(&self, thresholds: &Markers) -> Summary {
+        Summary::new(&self.stats(), thresholds)
+    }
+
+    /// The skipped files relative to the root of the repository, with why
+    /// they were skipped.
+    pub fn skipped(&self) -> Vec<Skipped> {
+        self.errors
+            .iter()
+            .map(|error| Skipped {
+                path: error
+                    .path()
+                    .map(|path| {
+                        path.strip_prefix(&self.git_base_path)
+                            .unwrap_or(path)
+                            .display()
+                            .to_string()
+                    })
+                    .unwrap_or_default(),
+                error: error.to_string(),
+            })
+            .collect()
+    }
+
+    pub fn collect(&mut self) -> Result<()> {
+        let mut stats = HashMap::new();
+
+        self.run_pipeline(|path, file_stats| {
+            stats.insert(path, file_stats);
+            Ok(())
+        })?;
+        self.stats = stats;
+        self.detect_duplication();
+        self.associate_tests();
+        self.link_imports();
+
+        Ok(())
+    }
+
+    /// Find the code duplicated across all the analysed files.
+    fn detect_duplication(&mut self) {
+        // Moved out of the stats while the duplicates borrow them
+        let mut fingerprints = self
+            .stats
+            .values_mut()
+            .map(|file_stats| {
+                let fingerprints = mem::take(&mut file_stats.fingerprints);
+                (file_stats.path.clone(), fingerprints)
+            })
+            .collect::<HashMap<_, _>>();
+        let duplicates = duplication::detect(
+            fingerprints
+                .iter()
+                .map(|(path, fingerprints)| (path.as_path(), fingerprints.as_slice())),
+        );
+
+        for file_stats in self.stats.values_mut() {
+            file_stats.set_duplicates(duplicates.get(file_stats.path.as_path()));
+        }
+
+        for file_stats in self.stats.values_mut() {
+            file_stats.fingerprints = fingerprints.remove(&file_stats.path).unwrap_or_default();
+        }
+    }
+
+    /// Sum the lines of code of the test files named after each module and
+    /// near it, see [`discovery::module_tests`].
+    fn associate_tests(&mut self) {
+        let loc = self
+            .stats
+            .values()
+            .map(|file_stats| (file_stats.path.as_path(), file_stats.loc))
+            .collect::<HashMap<_, _>>();
+        let test_loc = discovery::module_tests(loc.keys().copied())
+            .into_iter()
+            .map(|(module, tests)| {
+                let test_loc = tests.iter().map(|test| loc[test]).sum::<u32>();
+                (module.to_path_buf(), test_loc)
+            })
+            .collect::<HashMap<_, _>>();
+
+        for file_stats in self.stats.values_mut() {
+            file_stats.test_loc = match discovery::is_test_file(&file_stats.path) {
+                true => None,
+                false => Some(test_loc.get(&file_stats.path).copied().unwrap_or_default()),
+            };
+        }
+    }
+
+    /// Count the analysed modules importing each module and imported by it,
+    /// and rank them by centrality.
+    fn link_imports(&mut self) {
+        let graph = ImportGraph::new(
+            self.stats
+                .values()
+                .map(|file_stats| (file_stats.path.as_path(), file_stats.imports.as_slice())),
+        );
+        let fan_in_out = graph
+            .fan_in_out()
+            .into_iter()
+            .map(|(path, fan_in_out)| (path.to_path_buf(), fan_in_out))
+            .collect::<HashMap<_, _>>();
+        let centrality = graph
+            .centrality()
+            .into_iter()
+            .map(|(path, centrality)| (path.to_path_buf(), centrality))
+            .collect::<HashMap<_, _>>();
+
+        for file_stats in self.stats.values_mut() {
+            let (fan_in, fan_out) = fan_in_out
+                .get(&file_stats.path)
+                .copied()
+                .unwrap_or_default();
+            file_stats.fan_in = Some(fan_in);
+            file_stats.fan_out = Some(fan_out);
+            file_stats.centrality = centrality.get(&file_stats.path).copied();
+        }
+    }
+
+    /// Pass the stats of each file to `on_stats` as soon as they are
+    /// available, in no particular order and without keeping them in memory.
+    ///
+    /// Only the code duplicated within each file is detected.
+    pub fn stream<F>(&mut self, granularity: Granularity, mut on_stats: F) -> Result<()>
+    where
+        F: FnMut(HotspotStats) -> Result<()>,
+    {
+        let formula = self.formula.clone();
+        let weights = self.index_weights();
+        let mi_range = self.mi_range;
+        let zero_mi = self.zero_mi;
+        let severity_cutoffs = self.severity_cutoffs.clone();
+        let git_base_path = self.git_base_path.clone();
+        let directory = self.path.clone();
+
+        self.run_pipeline(|_, mut file_stats| {
+            let path = file_stats.path.clone();
+            let fingerprints = mem::take(&mut file_stats.fingerprints);
+            let duplicates = duplication::detect([(path.as_path(), fingerprints.as_slice())]);
+            file_stats.set_duplicates(duplicates.get(path.as_path()));
+
+            HotspotStats::from_file_stats(
+                &file_stats,
+                granularity,
+                formula.as_ref(),
+                weights,
+                mi_range,
+                zero_mi,
+                severity_cutoffs_of(&severity_cutoffs, &git_base_path, &directory, &path),
+            )
+            .into_iter()
+            .try_for_each(&mut on_stats)
+        })
+    }
+
+    fn skip(&mut self, error: Error) {
+        info!(
+            path = error.path().map(|path| tracing::field::display(path.display())),
+            reason = %error,
+            "Skipping the file"
+        );
+        self.errors.push(error);
+    }
+
+    fn discovery(&self) -> Discovery {
+        Discovery::new(&self.path, &self.excludes, self.files.clone())
+            .with_follow_symlinks(self.follow_symlinks)
+            .with_stubs(self.stubs)
+            .with_default_excludes(self.default_excludes)
+            .with_tracked_only(self.tracked_only)
+            .with_include_untracked(self.include_untracked)
+    }
+
+    /// Discover, parse and merge the files concurrently: a thread walks the
+    /// directory, a pool of workers parses the files and the calling thread
+    /// completes the stats with the changes count, all connected by bounded
+    /// channels.
+    fn run_pipeline<F>(&mut self, mut on_file: F) -> Result<()>
+    where
+        F: FnMut(PathBuf, FileStats) -> Result<()>,
+    {
+        let start = Instant::now();
+        let churn = self.collect_churn()?;
+        let unchanged = self.unchanged_churn();
+        let mut timings = Timings {
+            history: start.elapsed(),
+            ..Default::default()
+        };
+        let mut files = self.discovery().files();
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        let progress_bar = self.progress.spinner("Analysing files");
+
+        let (path_sender, path_receiver) = mpsc::sync_channel::<PathBuf>(CHANNEL_BOUND);
+        let (parsed_sender, parsed_receiver) =
+            mpsc::sync_channel::<Result<ParsedFile>>(CHANNEL_BOUND);
+        let path_receiver = Arc::new(Mutex::new(path_receiver));
+
+        let mut errors = vec![];
+        let mut lossy = vec![];
+        let mut non_finite = vec![];
+        let mut suppressions = HashMap::new();
+        let mut files_found = 0;
+        let mut new_cache_entries = vec![];
+        let mut analysed_paths = vec![];
+        let cache = self.cache.as_ref();
+        let git_base_path = &self.git_base_path;
+        let coverage = self.coverage.as_ref();
+        let debt_markers = self.debt_markers.as_slice();
+        let plugins = self.plugins.as_slice();
+        let mi_variant = self.mi_variant;
+        let max_file_size = self.max_file_size;
+        let nan_safe = self.nan_safe;
+
+        let result = thread::scope(|scope| {
+            let discovery_sender = parsed_sender.clone();
+            let discovery = scope.spawn(move || {
+                // Only the walk is timed, not the waits for the workers
+                let mut duration = Duration::ZERO;
+
+                loop {
+                    let start = Instant::now();
+                    let Some(file) = files.next() else {
+                        break;
+                    };
+                    duration += start.elapsed();
+
+                    let sent = match file {
+                        Ok(path) => path_sender.send(path).is_ok(),
+                        Err(error) => discovery_sender.send(Err(error)).is_ok(),
+                    };
+
+                    if !sent {
+                        break;
+                    }
+                }
+
+                duration
+            });
+
+            for _ in 0..workers {
+                let path_receiver = Arc::clone(&path_receiver);
+                let parsed_sender = parsed_sender.clone();
+
+                scope.spawn(move || loop {
+                    let Ok(path) = path_receiver.lock().unwrap().recv() else {
+                        break;
+                    };
+
+                    if parsed_sender
+                        .send(Self::parse_file(
+                            path,
+                            cache,
+                            debt_markers,
+                            plugins,
+                            mi_variant,
+                            max_file_size,
+                            nan_safe,
+                        ))
+                        .is_err()
+                    {
+                        break;
+                    }
+                });
+            }
+            drop(path_receiver);
+            drop(parsed_sender);
+
+            for parsed in parsed_receiver {
+                progress_bar.inc(1);
+                files_found += 1;
+
+                let mut parsed = match parsed {
+                    Ok(parsed) => parsed,
+                    Err(error) => {
+                        errors.push(error);
+                        continue;
+                    }
+                };
+
+                if let Err(error) = Self::normalise_to_git_root(git_base_path, &mut parsed.stats) {
+                    errors.push(error);
+                    continue;
+                }
+
+                parsed.set_suppressions_path();
+                if !parsed.suppressions.is_empty() {
+                    suppressions.insert(parsed.path.clone(), parsed.suppressions.clone());
+                }
+                if parsed.ignored {
+                    continue;
+                }
+
+                parsed.stats.churn = churn
+                    .get(&parsed.stats.path)
+                    .cloned()
+                    .unwrap_or_else(|| unchanged.clone());
+                if parsed.lossy {
+                    lossy.push(parsed.path.clone());
+                }
+                if parsed.stats.non_finite {
+                    non_finite.push(parsed.path.clone());
+                }
+                timings.parsing += parsed.duration;
+                timings
+                    .files
+                    .push((parsed.stats.path.clone(), parsed.duration));
+                Self::join_coverage(coverage, git_base_path, &mut parsed.stats);
+
+                if let Some(hash) = parsed.new_hash {
+                    new_cache_entries.push((parsed.path.clone(), hash, parsed.stats.clone()));
+                }
+                if cache.is_some() {
+                    analysed_paths.push(parsed.path.clone());
+                }
+
+                parsed.remove_suppressed_functions();
+                on_file(parsed.path, parsed.stats)?;
+            }
+            timings.discovery = discovery.join().unwrap_or_default();
+
+            Ok(())
+        });
+
+        progress_bar.finish_and_clear();
+        info!("Analysed {} Python files", progress_bar.position());
+        self.files_found = files_found;
+
+        for error in errors {
+            self.skip(error);
+        }
+        for path in &lossy {
+            info!(
+                path = %path.display(),
+                reason = "not valid UTF-8",
+                "Decoded the file lossily, its metrics are approximate"
+            );
+        }
+        self.lossy = lossy;
+        for path in &non_finite {
+            info!(path = %path.display(), "Replaced the NaN or infinite metrics");
+        }
+        self.non_finite = non_finite;
+        for suppression in suppressions.values().flatten() {
+            info!(
+                "Suppressed {}{} by the comment of line {}",
+                suppression.path,
+                suppression
+                    .function
+                    .as_ref()
+                    .map(|function| format!(" {function}"))
+                    .unwrap_or_default(),
+                suppression.line
+            );
+        }
+        self.suppressions = suppressions;
+        self.timings = timings;
+
+        if let Some(cache) = self.cache.as_mut() {
+            for (path, hash, stats) in new_cache_entries {
+                cache.store_metrics(&path, hash, stats);
+            }
+
+            cache.retain_paths(&analysed_paths);
+
+            if let Err(error) = cache.save() {
+                warn!("Failed to save the cache: {error}");
+            }
+        }
+
+        result
+    }
+
+    /// Recompute the metrics of the given files, keeping their changes count.
+    ///
+    /// Files which no longer exist are dropped from the stats.
+    pub fn refresh(&mut self, paths: &[PathBuf]) {
+        let discovery = self.discovery();
+
+        for path in paths {
+            if !discovery.is_analysed(path) {
+                continue;
+            }
+
+            if !path.is_file() {
+                debug!("Dropping removed file {}", path.display());
+                self.stats.remove(path);
+                continue;
+            }
+
+            let churn = self
+                .stats
+                .get(path)
+                .map(|existing| existing.churn.clone())
+                .unwrap_or_else(|| self.unchanged_churn());
+
+            let parsed = Self::parse_file(
+                path.clone(),
+                self.cache.as_ref(),
+                &self.debt_markers,
+                &self.plugins,
+                self.mi_variant,
+                self.max_file_size,
+                self.nan_safe,
+            )
+            .and_then(|mut parsed| {
+                Self::normalise_to_git_root(&self.git_base_path, &mut parsed.stats)?;
+                Ok(parsed)
+            });
+
+            self.lossy.retain(|lossy| lossy != path);
+            self.non_finite.retain(|non_finite| non_finite != path);
+            self.suppressions.remove(path);
+
+            match parsed {
+                Ok(mut parsed) => {
+                    parsed.set_suppressions_path();
+                    if !parsed.suppressions.is_empty() {
+                        self.suppressions
+                            .insert(path.clone(), parsed.suppressions.clone());
+                    }
+                    if parsed.ignored {
+                        self.stats.remove(path);
+                        continue;
+                    }
+
+                    parsed.stats.churn = churn;
+                    if parsed.lossy {
+                        self.lossy.push(path.clone());
+                    }
+                    if parsed.stats.non_finite {
+                        self.non_finite.push(path.clone());
+                    }
+                    Self::join_coverage(
+                        self.coverage.as_ref(),
+                        &self.git_base_path,
+                        &mut parsed.stats,
+                    );
+
+                    if let (Some(cache), Some(hash)) = (self.cache.as_mut(), parsed.new_hash) {
+                        cache.store_metrics(&parsed.path, hash, parsed.stats.clone());
+                    }
+
+                    parsed.remove_suppressed_functions();
+                    self.stats.insert(parsed.path, parsed.stats);
+                }
+                Err(error) => {
+                    self.stats.remove(path);
+                    self.skip(error);
+                }
+            }
+        }
+
+        self.detect_duplication();
+        self.associate_tests();
+        self.link_imports();
+    }
+
+    fn join_coverage(coverage: Option<&Coverage>, git_base_path: &Path, stats: &mut FileStats) {
+        let path = git_base_path.join(&stats.path);
+        let file = coverage.and_then(|coverage| coverage.file(&path));
+
+        stats.coverage = file.and_then(|file| file.total());
+
+        for function in &mut stats.functions {
+            function.coverage =
+                file.and_then(|file| file.percentage(function.start_line, function.end_line));
+        }
+    }
+
+    /// Changes count of each file by path relative to the root of the
+    /// repository, read from the cache when the history did not change.
+    fn collect_churn(&mut self) -> Result<HashMap<PathBuf, Churn>> {
+        let cache_key = match self.cache {
+            Some(_) => Some(self.changes_count_cache_key()?),
+            None => None,
+        };
+        let cached = cache_key
+            .as_deref()
+            .and_then(|key| self.cache.as_ref()?.churn(key))
+            .cloned();
+
+        if let Some(churn) = cached {
+            debug!("Using cached changes count");
+            return Ok(churn);
+        }
+
+        let churn = self.read_churn()?;
+
+        if let (Some(cache), Some(key)) = (self.cache.as_mut(), cache_key) {
+            cache.store_churn(key, churn.clone());
+        }
+
+        Ok(churn)
+    }
+
+    /// Count the changes of each file in the Git history by the type of the
+    /// commits, by path relative to the root of the repository.
+    fn read_churn(&self) -> Result<HashMap<PathBuf, Churn>> {
+        let classifier = self.commit_types.classifier()?;
+        let active_authors = match &self.active_authors {
+            Some(ActiveAuthors::Since(since)) => {
+                Some(history::authors_since(&self.git_base_path, since)?)
+            }
+            Some(ActiveAuthors::Names(names)) => Some(names.iter().cloned().collect()),
+            None => None,
+        };
+        let today = Local::now().date_naive();
+        let months = self.monthly_changes.then(|| last_months(today));
+        let mut churn: HashMap<PathBuf, Churn> = HashMap::new();
+        let mut changes_by_author: HashMap<PathBuf, HashMap<String, u32>> = HashMap::new();
+        let mut pull_requests: HashSet<(PathBuf, u32)> = HashSet::new();
+
+        for commit in self.read_history(self.churn_unit.is_first_parent())? {
+            let commit_type = classifier.classify(&commit.subject);
+            let pull_request = self.churn_unit.pull_request(&commit);
+
+            for FileChange { path, lines } in commit.files {
+                // A pull request merged again, e.g. after a revert, is one change
+                if let Some(number) = pull_request {
+                    if !pull_requests.insert((path.clone(), number)) {
+                        continue;
+                    }
+                }
+
+                *changes_by_author
+                    .entry(path.clone())
+                    .or_default()
+                    .entry(commit.author.clone())
+                    .or_default() += 1;
+                let churn = churn.entry(path).or_default();
+                // The commits are read from the most recent one
+                churn
+                    .last_author
+                    .get_or_insert_with(|| commit.author.clone());
+                churn.add(commit_type, lines);
+                churn.add_recent(commit.date, today);
+                if let Some(months) = &months {
+                    churn.add_monthly(months, commit.date);
+                }
+            }
+        }
+
+        for (path, authors) in changes_by_author {
+            if let Some(churn) = churn.get_mut(&path) {
+                churn.knowledge_loss = active_authors
+                    .as_ref()
+                    .map(|active_authors| knowledge_loss(&authors, active_authors));
+                churn.primary_owner = primary_owner(&authors);
+                churn.author_entropy = author_entropy(authors.into_values());
+            }
+        }
+
+        // The files unchanged since `--since` still have a first commit
+        for (path, first_commit) in history::first_commits(&self.path)? {
+            if !self.git_base_path.join(&path).exists() {
+                continue;
+            }
+
+            churn
+                .entry(path)
+                .or_insert_with(|| self.unchanged_churn())
+                .first_commit = Some(first_commit);
+        }
+
+        Ok(churn)
+    }
+
+    /// The churn of the files without changes.
+    fn unchanged_churn(&self) -> Churn {
+        Churn {
+            monthly_changes: self
+                .monthly_changes
+                .then(|| last_months(Local::now().date_naive())),
+            ..Default::default()
+        }
+    }
+
+    /// The commits of the analysed directory, or of its first-parent history,
+    /// without the ignored commits and the changes to files which do not
+    /// exist anymore.
+    fn read_history(&self, first_parent: bool) -> Result<Vec<Commit>> {
+        let filter = self.ignore_commits.filter(&self.git_base_path)?;
+        let progress_bar = self.progress.spinner("Reading git history");
+        let mut commits = history::read(
+            &self.path,
+            self.since.as_ref(),
+            self.since_tag.as_deref(),
+            first_parent,
+            self.ignore_commits.whitespace,
+        )?;
+
+        commits.retain(|commit| {
+            let ignored = filter.is_ignored(commit);
+            if ignored {
+                trace!("Ignoring commit {} {}", commit.hash, commit.subject);
+            }
+
+            !ignored
+        });
+
+        for commit in &mut commits {
+            progress_bar.inc(1);
+
+            commit.files.retain(|change| {
+                let exists = self.git_base_path.join(&change.path).exists();
+                if !exists {
+                    trace!("Ignoring change to missing file {}", change.path.display());
+                }
+
+                exists
+            });
+        }
+
+        progress_bar.finish_and_clear();
+
+        Ok(commits)
+    }
+
+    /// The changes count only depends on the commit checked out, the `--since`
+    /// window or tag, the analysed directory, the unit of the changes, the
+    /// patterns of the commit types, the active authors, the ignored commits,
+    /// whether the monthly changes are counted and the day, the recent
+    /// changes counts going back from it. The format version is part of the
+    /// key as a cache without a new optional field would still be read.
+    fn changes_count_cache_key(&self) -> Result<String> {
+        let head = git::run(&self.path, ["rev-parse", "HEAD"])?;
+        let since = self
+            .since
+            .map(|since| since.to_string())
+            .unwrap_or_default();
+        let today = Local::now().date_naive();
+
+        Ok(format!(
+            "{FORMAT_VERSION}:{}:{since}:{}:{}:{:?}:{:?}:{:?}:{:?}:{}:{today}",
+            head.trim(),
+            self.since_tag.as_deref().unwrap_or_default(),
+            self.path.display(),
+            self.churn_unit,
+            self.commit_types,
+            self.active_authors,
+            self.ignore_commits,
+            self.monthly_changes
+        ))
+    }
+
+    fn parse_file(
+        path: PathBuf,
+        cache: Option<&Cache>,
+        debt_markers: &[String],
+        plugins: &[Plugin],
+        mi_variant: MiVariant,
+        max_file_size: Option<u64>,
+        nan_safe: bool,
+    ) -> Result<ParsedFile> {
+        let start = Instant::now();
+
+        if let Some(limit) = max_file_size {
+            let size = fs::metadata(&path)
+                .map_err(|source| Error::discovered_io(path.clone(), source))?
+                .len();
+
+            if size > limit {
+                return Err(Error::TooLarge { path, size, limit });
+            }
+        }
+
+        let source_code =
+            fs::read(&path).map_err(|source| Error::discovered_io(path.clone(), source))?;
+        // A file in another encoding is still analysed, the invalid bytes
+        // only change the counts of the strings and comments holding them
+        let (source_code, lossy) = match String::from_utf8(source_code) {
+            Ok(source_code) => (source_code, false),
+            Err(error) => (String::from_utf8_lossy(error.as_bytes()).into_owned(), true),
+        };
+        // The metrics and the cached entry are the same whatever the line
+        // endings of the checkout, e.g. with `core.autocrlf` on Windows
+        let source_code = source_code.replace("\r\n", "\n");
+        let directives = suppressions::directives(&source_code);
+
+        if let Some(suppression) = suppressions::file(&directives) {
+            return Ok(ParsedFile {
+                stats: FileStats {
+                    path: path.clone(),
+                    ..Default::default()
+                },
+                path,
+                new_hash: None,
+                lossy,
+                duration: start.elapsed(),
+                suppressions: vec![suppression],
+                ignored: true,
+            });
+        }
+
+        let hash = cache.map(|_| Cache::hash(source_code.as_bytes(), mi_variant, nan_safe));
+        // Counted on every run, the markers are not part of the cache key
+        let debt_comments = debt_comments::lines(&source_code, debt_markers);
+
+        let cached = cache
+            .zip(hash)
+            .and_then(|(cache, hash)| cache.metrics(&path, hash));
+
+        let (mut file_stats, new_hash) = match cached {
+            Some(cached) => {
+                trace!("Using cached metrics for {}", path.display());
+
+                // The cached path is relative to the root of the repository
+                let file_stats = FileStats {
+                    path: path.clone(),
+                    ..cached.clone()
+                };
+
+                (file_stats, None)
+            }
+            None => {
+                let mut file_stats = FileStats {
+                    path: path.clone(),
+                    ..Default::default()
+                };
+
+                // A bug of a parser on an unusual file must not abort the
+                // whole analysis
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    Self::compute_stats(
+                        &mut file_stats,
+                        source_code.as_bytes().to_vec(),
+                        mi_variant,
+                    )
+                }))
+                .map_err(|payload| Error::Parse {
+                    path: path.clone(),
+                    message: panic_message(payload.as_ref()),
+                })?;
+                file_stats.non_finite = nan_safe && file_stats.replace_non_finite();
+
+                (file_stats, hash)
+            }
+        };
+
+        file_stats.set_debt_comments(&debt_comments);
+        // Run on every run too, the plugins may change between runs
+        file_stats.plugins = BTreeMap::new();
+        for plugin in plugins {
+            let metrics = plugin.metrics(&source_code, &file_stats)?;
+            file_stats.plugins.extend(metrics);
+        }
+
+        let suppressions = suppressions::functions(&directives, &file_stats.functions);
+
+        Ok(ParsedFile {
+            path,
+            stats: file_stats,
+            new_hash,
+            lossy,
+            duration: start.elapsed(),
+            suppressions,
+            ignored: false,
+        })
+    }
+
+    fn compute_stats(file_stats: &mut FileStats, source_code: Vec<u8>, mi_variant: MiVariant) {
+        let path = Path::new(&file_stats.path).to_path_buf();
+        debug!("Parsing {}", path.display());
+
+        let text = String::from_utf8_lossy(&source_code).into_owned();
+        let suite = ast::Suite::parse(&text, &path.to_string_lossy()).ok();
+        if suite.is_none() {
+            debug!(
+                "Cannot compute the nesting depth and type annotations of {}",
+                path.display()
+            );
+        }
+        let nesting = suite
+            .as_deref()
+            .map(|suite| Nesting::new(suite, &text))
+            .unwrap_or_default();
+        let annotations = suite
+            .as_deref()
+            .map(|suite| Annotations::new(suite, &text))
+            .unwrap_or_default();
+        let parser = PythonParser::new(source_code, &path, None);
+
+        if let Some(s) = metrics(&parser, &path) {
+            let sloc = s.metrics.loc.sloc();
+
+            match sloc {
+                0.0 => {
+                    file_stats.maintainability_index = 100.0;
+                    file_stats.comments_percentage = 0.0;
+                    file_stats.halstead_volume = 0.0;
+                }
+                _ => {
+                    file_stats.maintainability_index =
+                        mi_variant.maintainability_index(&s.metrics.mi);
+                    file_stats.comments_percentage = s.metrics.loc.cloc() / sloc * 100.0;
+                    file_stats.halstead_volume = s.metrics.halstead.volume();
+                }
+            }
+
+            file_stats.path = path;
+            file_stats.cyclomatic_complexity = s.metrics.cyclomatic.cyclomatic_max();
+            file_stats.cyclomatic_average = s.metrics.cyclomatic.cyclomatic_average();
+            file_stats.cyclomatic_total = s.metrics.cyclomatic.cyclomatic_sum();
+            file_stats.max_nesting = nesting.max;
+            file_stats.nom = s.metrics.nom.functions_sum() as u32;
+            file_stats.loc = sloc as u32;
+            file_stats.functions = vec![];
+            Self::collect_functions(&s, None, mi_variant, &mut file_stats.functions);
+
+            for function_stats in &mut file_stats.functions {
+                function_stats.max_nesting = nesting
+                    .functions
+                    .get(&function_stats.start_line)
+                    .copied()
+                    .unwrap_or_default();
+                function_stats.type_annotations = annotations
+                    .functions
+                    .get(&function_stats.start_line)
+                    .map(|annotated| if *annotated { 100.0 } else { 0.0 });
+            }
+
+            file_stats.type_annotations = annotations.percentage();
+            file_stats.imports = suite.as_deref().map(imports::imports).unwrap_or_default();
+
+            file_stats.longest_function = file_stats
+                .functions
+                .iter()
+                .map(FunctionStats::length)
+                .max()
+                .unwrap_or_default();
+            file_stats.fingerprints = duplication::fingerprints(&text);
+        };
+    }
+
+    /// Walk the spaces depth first, collecting every function with its name
+    /// qualified by the enclosing classes and functions.
+    fn collect_functions(
+        space: &FuncSpace,
+        prefix: Option<&str>,
+        mi_variant: MiVariant,
+        functions: &mut Vec<FunctionStats>,
+    ) {
+        for child in &space.spaces {
+            let name = child.name.as_deref().unwrap_or("<anonymous>");
+            let qualified_name = match prefix {
+                Some(prefix) => format!("{prefix}.{name}"),
+                None => name.to_string(),
+            };
+
+            if child.kind == SpaceKind::Function {
+                functions.push(Self::function_stats(child, &qualified_name, mi_variant));
+            }
+
+            Self::collect_functions(child, Some(&qualified_name), mi_variant, functions);
+        }
+    }
+
+    fn function_stats(space: &FuncSpace, name: &str, mi_variant: MiVariant) -> FunctionStats {
+        let metrics: &CodeMetrics = &space.metrics;
+        let sloc = metrics.loc.sloc();
+
+        FunctionStats {
+            name: name.to_string(),
+            start_line: space.start_line,
+            end_line: space.end_line,
+            halstead_volume: metrics.halstead.volume(),
+            cyclomatic_complexity: metrics.cyclomatic.cyclomatic(),
+            cognitive_complexity: metrics.cognitive.cognitive(),
+            max_nesting: 0,
+            // Functions nested in this one, the count including the function itself
+            nom: (metrics.nom.functions_sum() as u32).saturating_sub(1),
+            loc: sloc as u32,
+            comments_percentage: match sloc {
+                0.0 => 0.0,
+                _ => metrics.loc.cloc() / sloc * 100.0,
+            },
+            maintainability_index: mi_variant.maintainability_index(&metrics.mi),
+            coverage: None,
+            duplication: 0.0,
+            debt_comments: 0,
+            type_annotations: None,
+        }
+    }
+
+    fn normalise_to_git_root(git_base_path: &Path, file_stats: &mut FileStats) -> Result<()> {
+        match file_stats.path.strip_prefix(git_base_path) {
+            Ok(relative_path) => {
+                file_stats.path = relative_path.to_path_buf();
+                Ok(())
+            }
+            Err(_) => Err(Error::OutsideGitRepository {
+                path: file_stats.path.clone(),
+                git_base_path: git_base_path.to_path_buf(),
+            }),
+        }
+    }
+
+    fn get_git_base_path(directory: &Path) -> Result<PathBuf> {
+        if !directory.is_dir() {
+            return Err(Error::NotADirectory(directory.to_path_buf()));
+        }
+
+        let stdout =
+            git::run(directory, ["rev-parse", "--show-toplevel"]).map_err(|error| match error {
+                Error::GitCommand { stderr, .. } => Error::NotAGitRepository {
+                    path: directory.to_path_buf(),
+                    stderr,
+                },
+                error => error,
+            })?;
+        let git_base_path = PathBuf::from(stdout.trim());
+
+        // Canonical like the analysed directory, git printing a path with
+        // forward slashes and without the verbatim prefix on Windows
+        git_base_path.canonicalize().map_err(|source| Error::Io {
+            path: git_base_path,
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::untested::untested_hotspots;
+    use rstest::*;
+    use std::collections::HashSet;
+    use std::process::Command;
+    use tempfile::{tempdir, TempDir};
+
+    #[test]
     fn test_hotspot_stats_new() {
         // ARRANGE
-        let file_stats = FileStats {
-            path: PathBuf::from("src/main.rs"),
-            halstead_volume: 10.0,
-            cyclomatic_complexity: 5.0,
-            loc: 100,
-            comments_percentage: 20.0,
-            maintainability_index: 80.0,
-            changes_count: 10,
-        };
+        let file_stats = FileStats {
+            path: PathBuf::from("src/main.rs"),
+            halstead_volume: 10.0,
+            cyclomatic_complexity: 5.0,
+            cyclomatic_average: 2.5,
+            cyclomatic_total: 10.0,
+            max_nesting: 2,
+            nom: 3,
+            longest_function: 40,
+            loc: 100,
+            comments_percentage: 20.0,
+            maintainability_index: 80.0,
+            churn: Churn {
+                changes_count: 10,
+                changes_30d: 0,
+                changes_90d: 0,
+                changes_365d: 0,
+                lines_changed: 120,
+                feature: 3,
+                fix: 6,
+                refactor: 0,
+                chore: 1,
+                author_entropy: 1.0,
+                knowledge_loss: Some(40.0),
+                monthly_changes: None,
+                first_commit: None,
+                last_author: None,
+                primary_owner: None,
+            },
+            coverage: Some(75.0),
+            duplication: 0.0,
+            duplication_partners: vec![],
+            fingerprints: vec![],
+            debt_comments: 4,
+            type_annotations: Some(50.0),
+            functions: vec![],
+            plugins: [("sql_strings".to_string(), 3.0)].into(),
+            non_finite: false,
+            test_loc: Some(0),
+            imports: vec![],
+            fan_in: Some(2),
+            fan_out: None,
+            centrality: Some(1.5),
+        };
+
+        // ACT
+        let actual = HotspotStats::new(&file_stats);
+
+        // ASSERT
+        let expected = HotspotStats {
+            path: "src/main.rs".to_string(),
+            path_type: PathType::File,
+            function: None,
+            start_line: None,
+            end_line: None,
+            halstead_volume: 10.0,
+            cyclomatic_complexity: 5.0,
+            cyclomatic_average: 2.5,
+            cyclomatic_total: 10.0,
+            max_nesting: 2,
+            nom: 3,
+            longest_function: 40,
+            loc: 100,
+            comments_percentage: 20.0,
+            maintainability_index: 80.0,
+            changes_count: 10,
+            changes_30d: 0,
+            changes_90d: 0,
+            changes_365d: 0,
+            lines_changed: 120,
+            feature_changes: 3,
+            fix_changes: 6,
+            refactor_changes: 0,
+            chore_changes: 1,
+            author_entropy: 1.0,
+            knowledge_loss: Some(40.0),
+            monthly_changes: None,
+            first_commit: None,
+            last_author: None,
+            primary_owner: None,
+            hotspot_index: 10.0 / (80.0 / 100.0),
+            coverage: Some(75.0),
+            risk: Some(10.0 * 5.0 * 0.25),
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: 0.0,
+            duplication_partners: vec![],
+            debt_comments: 4,
+            type_annotations: Some(50.0),
+            test_ratio: None,
+            test_loc: Some(0),
+            fan_in: Some(2),
+            fan_out: None,
+            centrality: Some(1.5),
+            plugins: [("sql_strings".to_string(), 3.0)].into(),
+            severity: Default::default(),
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(ChurnWeight::Commits, 4.0)]
+    #[case(ChurnWeight::Lines, 60.0)]
+    fn test_from_file_stats_churn_weight(#[case] churn_weight: ChurnWeight, #[case] expected: f64) {
+        // ARRANGE
+        let file_stats = FileStats {
+            path: PathBuf::from("pkg/a.py"),
+            maintainability_index: 50.0,
+            churn: Churn {
+                changes_count: 2,
+                lines_changed: 30,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = HotspotStats::from_file_stats(
+            &file_stats,
+            Granularity::File,
+            None,
+            IndexWeights {
+                churn: churn_weight,
+                ..Default::default()
+            },
+            MiRange::default(),
+            ZeroMi::default(),
+            &SeverityCutoffs::default(),
+        );
+
+        // ASSERT
+        assert_eq!(actual[0].hotspot_index, expected);
+    }
+
+    #[rstest]
+    #[case(false, Some(1.5), 4.0)]
+    #[case(true, Some(1.5), 6.0)]
+    #[case(true, None, 4.0)]
+    fn test_from_file_stats_centrality_weight(
+        #[case] centrality_weight: bool,
+        #[case] centrality: Option<f64>,
+        #[case] expected: f64,
+    ) {
+        // ARRANGE
+        let file_stats = FileStats {
+            path: PathBuf::from("pkg/a.py"),
+            maintainability_index: 50.0,
+            churn: Churn {
+                changes_count: 2,
+                ..Default::default()
+            },
+            centrality,
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = HotspotStats::from_file_stats(
+            &file_stats,
+            Granularity::File,
+            None,
+            IndexWeights {
+                centrality: centrality_weight,
+                ..Default::default()
+            },
+            MiRange::default(),
+            ZeroMi::default(),
+            &SeverityCutoffs::default(),
+        );
+
+        // ASSERT
+        assert_eq!(actual[0].hotspot_index, expected);
+    }
+
+    #[rstest]
+    #[case(MiRange::Raw, 120.0, 2.0 / 1.2)]
+    #[case(MiRange::Clamp, 100.0, 2.0)]
+    #[case(MiRange::Unit, 1.0, 2.0)]
+    fn test_from_file_stats_mi_range(
+        #[case] mi_range: MiRange,
+        #[case] expected_maintainability_index: f64,
+        #[case] expected_hotspot_index: f64,
+    ) {
+        // ARRANGE
+        let file_stats = FileStats {
+            path: PathBuf::from("pkg/a.py"),
+            maintainability_index: 120.0,
+            churn: Churn {
+                changes_count: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = HotspotStats::from_file_stats(
+            &file_stats,
+            Granularity::File,
+            None,
+            IndexWeights::default(),
+            mi_range,
+            ZeroMi::default(),
+            &SeverityCutoffs::default(),
+        );
+
+        // ASSERT
+        assert_eq!(
+            actual[0].maintainability_index,
+            expected_maintainability_index
+        );
+        assert_eq!(actual[0].hotspot_index, expected_hotspot_index);
+    }
+
+    #[rstest]
+    #[case(ZeroMi::Infinity, f64::INFINITY)]
+    #[case(ZeroMi::Cap, 1e9)]
+    #[case(ZeroMi::Epsilon, 20000.0)]
+    fn test_hotspot_index_zero_mi(#[case] zero_mi: ZeroMi, #[case] expected: f64) {
+        // ACT
+        let actual = hotspot_index(2, 0.0, zero_mi);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_hotspot_index_zero_mi_null() {
+        // ACT
+        let actual = hotspot_index(2, 0.0, ZeroMi::Null);
+
+        // ASSERT
+        assert!(actual.is_nan());
+    }
+
+    #[test]
+    fn test_hotspot_stats_serde_roundtrip() {
+        // ARRANGE
+        let stats = vec![HotspotStats {
+            path: "pkg/a.py".to_string(),
+            function: Some("C.m".to_string()),
+            start_line: Some(3),
+            end_line: Some(9),
+            maintainability_index: 42.5,
+            changes_count: 7,
+            knowledge_loss: Some(25.0),
+            severity: Severity::High,
+            duplication_partners: vec!["pkg/b.py".to_string()],
+            ..Default::default()
+        }];
+
+        // ACT
+        let json = serde_json::to_string(&stats).unwrap();
+        let actual: Vec<HotspotStats> = serde_json::from_str(&json).unwrap();
+
+        // ASSERT
+        assert_eq!(actual, stats);
+    }
+
+    #[test]
+    fn test_compute_stats_functions() {
+        // ARRANGE
+        let source_code = b"def foo(a):\n    if a:\n        return 1\n    return 2\n\n\nclass Bar:\n    def baz(self):\n        return 3\n";
+        let mut file_stats = FileStats {
+            path: PathBuf::from("module.py"),
+            ..Default::default()
+        };
+
+        // ACT
+        TechDebtHotspots::compute_stats(
+            &mut file_stats,
+            source_code.to_vec(),
+            MiVariant::default(),
+        );
+
+        // ASSERT
+        let actual = file_stats
+            .functions
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.start_line,
+                    f.end_line,
+                    f.cyclomatic_complexity,
+                    f.max_nesting,
+                    f.nom,
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = vec![("foo", 1, 4, 2.0, 1, 0), ("Bar.baz", 8, 9, 1.0, 0, 0)];
+
+        assert_eq!(actual, expected);
+        assert_eq!(file_stats.nom, 2);
+        assert_eq!(file_stats.longest_function, 4);
+    }
+
+    #[test]
+    fn test_compute_stats_mi_variant() {
+        // ARRANGE
+        let source_code = b"def foo(a):\n    if a:\n        return 1\n    return 2\n";
+        let maintainability_index = |mi_variant| {
+            let mut file_stats = FileStats {
+                path: PathBuf::from("module.py"),
+                ..Default::default()
+            };
+            TechDebtHotspots::compute_stats(&mut file_stats, source_code.to_vec(), mi_variant);
+            file_stats.maintainability_index
+        };
+
+        // ACT
+        let original = maintainability_index(MiVariant::Original);
+        let visual_studio = maintainability_index(MiVariant::VisualStudio);
+
+        // ASSERT
+        assert!(original > 100.0);
+        assert_eq!(visual_studio, original * 100.0 / 171.0);
+    }
+
+    #[test]
+    fn test_parse_file_crlf() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let lf = temp_dir.path().join("lf.py");
+        let crlf = temp_dir.path().join("crlf.py");
+        let source_code = "# A comment\n\ndef foo(a):\n    \"\"\"Docstring\n\n    on lines.\n    \"\"\"\n    # TODO: bar\n    b = a + \\\n        1\n    return b\n";
+        fs::write(&lf, source_code).unwrap();
+        fs::write(&crlf, source_code.replace('\n', "\r\n")).unwrap();
+        let markers = vec!["TODO".to_string()];
+
+        // ACT
+        let expected = TechDebtHotspots::parse_file(
+            lf,
+            None,
+            &markers,
+            &[],
+            MiVariant::default(),
+            None,
+            false,
+        )
+        .unwrap();
+        let actual = TechDebtHotspots::parse_file(
+            crlf,
+            None,
+            &markers,
+            &[],
+            MiVariant::default(),
+            None,
+            false,
+        )
+        .unwrap();
+
+        // ASSERT
+        assert_eq!(
+            FileStats {
+                path: expected.stats.path.clone(),
+                ..actual.stats
+            },
+            expected.stats
+        );
+    }
+
+    #[rstest]
+    #[case(false, 0.0)]
+    #[case(true, 100.0)]
+    fn test_parse_file_nan_safe(
+        #[case] nan_safe: bool,
+        #[case] expected_maintainability_index: f64,
+    ) {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("comments.py");
+        fs::write(&path, "# Only a comment\n").unwrap();
 
         // ACT
-        let actual = HotspotStats::new(&file_stats);
+        let actual = TechDebtHotspots::parse_file(
+            path,
+            None,
+            &[],
+            &[],
+            MiVariant::default(),
+            None,
+            nan_safe,
+        )
+        .unwrap();
 
         // ASSERT
-        let expected = HotspotStats {
-            path: "src/main.rs".to_string(),
-            halstead_volume: 10.0,
-            cyclomatic_complexity: 5.0,
-            loc: 100,
-            comments_percentage: 20.0,
-            maintainability_index: 80.0,
-            changes_count: 10,
-            hotspot_index: 10.0 / (80.0 / 100.0),
-        };
+        assert_eq!(actual.stats.halstead_volume.is_nan(), !nan_safe);
+        assert_eq!(
+            actual.stats.maintainability_index,
+            expected_maintainability_index
+        );
+        assert_eq!(actual.stats.non_finite, nan_safe);
+    }
 
-        assert_eq!(actual, expected);
+    #[rstest]
+    #[case(5, true)]
+    #[case(6, false)]
+    fn test_parse_file_max_file_size(#[case] limit: u64, #[case] too_large: bool) {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("a.py");
+        fs::write(&path, "x = 1\n").unwrap();
+
+        // ACT
+        let actual = TechDebtHotspots::parse_file(
+            path.clone(),
+            None,
+            &[],
+            &[],
+            MiVariant::default(),
+            Some(limit),
+            false,
+        );
+
+        // ASSERT
+        match actual {
+            Err(Error::TooLarge {
+                path: actual_path,
+                size,
+                limit: actual_limit,
+            }) => {
+                assert!(too_large);
+                assert_eq!(actual_path, path);
+                assert_eq!(size, 6);
+                assert_eq!(actual_limit, limit);
+            }
+            Ok(parsed) => {
+                assert!(!too_large);
+                assert_eq!(parsed.stats.loc, 1);
+            }
+            Err(error) => panic!("unexpected error: {error}"),
+        }
     }
 
     #[fixture]
@@ -293,35 +2630,358 @@ mod tests {
         (temp_dir, file1.to_path_buf(), file2.to_path_buf())
     }
 
+    fn commit_all(directory: &Path) {
+        Command::new("git")
+            .current_dir(directory)
+            .args(["add", "--all"])
+            .output()
+            .unwrap();
+        Command::new("git")
+            .current_dir(directory)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(["commit", "-m", "Commit"])
+            .output()
+            .unwrap();
+    }
+
     #[rstest]
     fn test_collect_filenames(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
         // ARRANGE
         let (temp_dir, file1, file2) = git_repo_with_files;
 
         // ACT
-        let mut tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None);
-        tech_debt_hotspots.collect_filenames();
+        let tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None).unwrap();
 
-        let actual = tech_debt_hotspots.stats;
+        let actual: HashSet<PathBuf> = tech_debt_hotspots
+            .discovery()
+            .files()
+            .collect::<Result<_>>()
+            .unwrap();
 
         // ASSERT
-        let mut expected = HashMap::new();
-        expected.insert(
-            file1.clone(),
-            FileStats {
-                path: file1.clone(),
-                ..Default::default()
-            },
+        let expected = HashSet::from([file1, file2]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn test_collect_filenames_from_explicit_files(
+        git_repo_with_files: (TempDir, PathBuf, PathBuf),
+    ) {
+        // ARRANGE
+        let (temp_dir, _, file2) = git_repo_with_files;
+        let outside = PathBuf::from("/outside/file3.py");
+
+        // ACT
+        let tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None)
+            .unwrap()
+            .with_files(vec![
+                file2.clone(),
+                outside,
+                file2.parent().unwrap().to_path_buf(),
+            ]);
+
+        let actual: Vec<PathBuf> = tech_debt_hotspots
+            .discovery()
+            .files()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        // ASSERT
+        assert_eq!(actual, vec![file2]);
+    }
+
+    #[rstest]
+    fn test_collect_filenames_tracked_only(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (temp_dir, file1, _) = git_repo_with_files;
+        Command::new("git")
+            .args(["add", "file1.py"])
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+
+        // ACT
+        let actual: Vec<PathBuf> = TechDebtHotspots::new(temp_dir.path(), None, None)
+            .unwrap()
+            .with_tracked_only(true)
+            .discovery()
+            .files()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        // ASSERT
+        assert_eq!(actual, vec![file1]);
+    }
+
+    #[rstest]
+    fn test_collect_filenames_include_untracked(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (temp_dir, file1, _) = git_repo_with_files;
+        Command::new("git")
+            .args(["add", "file1.py"])
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+        // The untracked file of the ignored directory is left out
+        fs::write(temp_dir.path().join(".gitignore"), "subdir/\n").unwrap();
+        let file3 = temp_dir.path().join("file3.py");
+        fs::write(&file3, "print('Hello, untracked!')").unwrap();
+
+        // ACT
+        let mut actual: Vec<PathBuf> = TechDebtHotspots::new(temp_dir.path(), None, None)
+            .unwrap()
+            .with_tracked_only(true)
+            .with_include_untracked(true)
+            .discovery()
+            .files()
+            .collect::<Result<_>>()
+            .unwrap();
+        actual.sort();
+
+        // ASSERT
+        assert_eq!(actual, vec![file1, file3]);
+    }
+
+    #[rstest]
+    fn test_collect_filenames_with_symlinks(
+        git_repo_with_files: (TempDir, PathBuf, PathBuf),
+        #[values(false, true)] follow_symlinks: bool,
+    ) {
+        // ARRANGE
+        let (temp_dir, file1, file2) = git_repo_with_files;
+        let temp_path = temp_dir.path();
+
+        // A duplicate of a file, a cycle and a duplicate of a directory
+        std::os::unix::fs::symlink(&file1, temp_path.join("alias.py")).unwrap();
+        std::os::unix::fs::symlink(temp_path, temp_path.join("subdir/loop")).unwrap();
+        std::os::unix::fs::symlink(temp_path.join("subdir"), temp_path.join("link")).unwrap();
+
+        // ACT
+        let mut actual: Vec<PathBuf> = TechDebtHotspots::new(temp_path, None, None)
+            .unwrap()
+            .with_follow_symlinks(follow_symlinks)
+            .discovery()
+            .files()
+            .collect::<Result<_>>()
+            .unwrap();
+        actual.sort();
+
+        // ASSERT
+        assert_eq!(actual, vec![file1, file2]);
+    }
+
+    #[rstest]
+    fn test_collect(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (temp_dir, _, _) = git_repo_with_files;
+
+        Command::new("git")
+            .current_dir(temp_dir.path())
+            .args(["add", "."])
+            .output()
+            .unwrap();
+        Command::new("git")
+            .current_dir(temp_dir.path())
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(["commit", "-m", "Initial commit"])
+            .output()
+            .unwrap();
+
+        // ACT
+        let mut tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None).unwrap();
+        tech_debt_hotspots.collect().unwrap();
+
+        let mut actual = tech_debt_hotspots.stats();
+        actual.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // ASSERT
+        let actual: Vec<(&str, u32)> = actual
+            .iter()
+            .map(|stats| (stats.path.as_str(), stats.changes_count))
+            .collect();
+
+        assert_eq!(actual, vec![("file1.py", 1), ("subdir/file2.py", 1)]);
+    }
+
+    #[rstest]
+    fn test_collect_last_author(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (temp_dir, file1, _) = git_repo_with_files;
+        let commit = |author: &str| {
+            Command::new("git")
+                .current_dir(temp_dir.path())
+                .args(["add", "--all"])
+                .output()
+                .unwrap();
+            Command::new("git")
+                .current_dir(temp_dir.path())
+                .args(["-c", &format!("user.name={author}")])
+                .args(["-c", "user.email=test@example.com"])
+                .args(["commit", "-m", "Commit"])
+                .output()
+                .unwrap();
+        };
+        commit("Alice");
+        fs::write(&file1, "x = 2\n").unwrap();
+        commit("Bob");
+
+        // ACT
+        let mut tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None).unwrap();
+        tech_debt_hotspots.collect().unwrap();
+
+        // ASSERT
+        let mut actual = tech_debt_hotspots
+            .stats()
+            .into_iter()
+            .map(|stats| (stats.path, stats.last_author))
+            .collect::<Vec<_>>();
+        actual.sort();
+
+        assert_eq!(
+            actual,
+            [
+                ("file1.py".to_string(), Some("Bob".to_string())),
+                ("subdir/file2.py".to_string(), Some("Alice".to_string())),
+            ]
         );
-        expected.insert(
-            file2.clone(),
-            FileStats {
-                path: file2.clone(),
+    }
+
+    #[test]
+    fn test_associate_tests() {
+        // ARRANGE
+        let file_stats = |path: &str, loc| FileStats {
+            path: PathBuf::from(path),
+            loc,
+            ..Default::default()
+        };
+        let mut tech_debt_hotspots = TechDebtHotspots {
+            stats: [
+                file_stats("pkg_a/models.py", 100),
+                file_stats("pkg_a/tests/test_models.py", 40),
+                file_stats("pkg_b/models.py", 80),
+            ]
+            .into_iter()
+            .map(|file_stats| (file_stats.path.clone(), file_stats))
+            .collect(),
+            ..Default::default()
+        };
+
+        // ACT
+        tech_debt_hotspots.associate_tests();
+
+        // ASSERT
+        let test_loc = |path: &str| tech_debt_hotspots.stats[Path::new(path)].test_loc;
+
+        assert_eq!(test_loc("pkg_a/models.py"), Some(40));
+        assert_eq!(test_loc("pkg_a/tests/test_models.py"), None);
+        assert_eq!(test_loc("pkg_b/models.py"), Some(0));
+    }
+
+    #[test]
+    fn test_untested_hotspots_with_namesake() {
+        // ARRANGE
+        let file_stats = |path: &str| FileStats {
+            path: PathBuf::from(path),
+            cyclomatic_complexity: 5.0,
+            loc: 50,
+            maintainability_index: 50.0,
+            churn: Churn {
+                changes_count: 4,
                 ..Default::default()
             },
+            ..Default::default()
+        };
+        let mut tech_debt_hotspots = TechDebtHotspots {
+            stats: [
+                file_stats("pkg_a/models.py"),
+                file_stats("pkg_a/tests/test_models.py"),
+                file_stats("pkg_b/models.py"),
+            ]
+            .into_iter()
+            .map(|file_stats| (file_stats.path.clone(), file_stats))
+            .collect(),
+            ..Default::default()
+        };
+        tech_debt_hotspots.associate_tests();
+
+        // ACT
+        let actual = untested_hotspots(tech_debt_hotspots.stats());
+
+        // ASSERT
+        let actual = actual
+            .iter()
+            .map(|stat| (stat.path.as_str(), stat.risk))
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, [("pkg_b/models.py", Some(20.0))]);
+    }
+
+    #[rstest]
+    fn test_collect_decodes_invalid_files_lossily(
+        git_repo_with_files: (TempDir, PathBuf, PathBuf),
+    ) {
+        // ARRANGE
+        let (temp_dir, _, _) = git_repo_with_files;
+        fs::write(temp_dir.path().join("latin1.py"), b"name = '\xe9'\n").unwrap();
+
+        Command::new("git")
+            .current_dir(temp_dir.path())
+            .args(["add", "."])
+            .output()
+            .unwrap();
+        Command::new("git")
+            .current_dir(temp_dir.path())
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(["commit", "-m", "Initial commit"])
+            .output()
+            .unwrap();
+
+        // ACT
+        let mut tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None).unwrap();
+        tech_debt_hotspots.collect().unwrap();
+
+        // ASSERT
+        assert!(tech_debt_hotspots.skipped().is_empty());
+        assert_eq!(
+            tech_debt_hotspots.lossy(),
+            [temp_dir.path().join("latin1.py")]
         );
+        assert_eq!(tech_debt_hotspots.stats().len(), 3);
+        assert_eq!(tech_debt_hotspots.files_found(), 3);
+    }
 
-        assert_eq!(actual, expected);
+    #[rstest]
+    fn test_parse_file_deleted(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (_temp_dir, file1, _) = git_repo_with_files;
+        fs::remove_file(&file1).unwrap();
+
+        // ACT
+        let actual = TechDebtHotspots::parse_file(
+            file1.clone(),
+            None,
+            &[],
+            &[],
+            MiVariant::default(),
+            None,
+            false,
+        );
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::Deleted(path)) if path == file1));
+    }
+
+    #[test]
+    fn test_new_outside_git_repository() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+
+        // ACT
+        let actual = TechDebtHotspots::new(temp_dir.path(), None, None);
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::NotAGitRepository { .. })));
     }
 
     #[rstest]
@@ -331,39 +2991,128 @@ mod tests {
         let git_base_path = temp_dir.path().to_path_buf();
         let file_path = git_base_path.join("src/main.py");
 
-        // Create a TechDebtHotspots instance
-        let mut tech_debt_hotspots = TechDebtHotspots {
-            git_base_path: git_base_path.clone(),
-            stats: HashMap::new(),
-            path: git_base_path.clone(),
-            exclude: None,
-            since: None,
+        let mut file_stats = FileStats {
+            path: file_path.clone(),
+            ..Default::default()
         };
 
-        // Insert a FileStats entry with an absolute path
-        tech_debt_hotspots.stats.insert(
-            file_path.clone(),
-            FileStats {
-                path: file_path.clone(),
-                ..Default::default()
-            },
-        );
-
         // ACT
-        tech_debt_hotspots.normalise_to_git_root();
+        TechDebtHotspots::normalise_to_git_root(&git_base_path, &mut file_stats).unwrap();
 
         // ASSERT
-        let normalized_path = tech_debt_hotspots
-            .stats
-            .get(&file_path)
-            .unwrap()
-            .path
-            .clone();
         let expected_relative_path = file_path
             .strip_prefix(&git_base_path)
             .unwrap()
             .to_path_buf();
 
-        assert_eq!(normalized_path, expected_relative_path);
+        assert_eq!(file_stats.path, expected_relative_path);
+    }
+
+    #[test]
+    fn test_normalise_to_git_root_outside_repository() {
+        // ARRANGE
+        let mut file_stats = FileStats {
+            path: PathBuf::from("/elsewhere/main.py"),
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = TechDebtHotspots::normalise_to_git_root(Path::new("/repo"), &mut file_stats);
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::OutsideGitRepository { .. })));
+    }
+
+    #[rstest]
+    fn test_collect_with_cache(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (temp_dir, _, _) = git_repo_with_files;
+        let cache_dir = tempdir().unwrap();
+        commit_all(temp_dir.path());
+
+        // The second run reads the rows of the first one from the cache and
+        // must report the same paths, relative to the repository
+        let collect = || {
+            let mut tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None)
+                .unwrap()
+                .with_cache(cache_dir.path());
+            tech_debt_hotspots.collect().unwrap();
+
+            let mut paths = tech_debt_hotspots
+                .stats
+                .values()
+                .map(|file_stats| file_stats.path.clone())
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        };
+
+        // ACT
+        let expected = collect();
+        let actual = collect();
+
+        // ASSERT
+        assert_eq!(
+            expected,
+            [PathBuf::from("file1.py"), PathBuf::from("subdir/file2.py")]
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn test_refresh(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (temp_dir, file1, file2) = git_repo_with_files;
+        let excluded = temp_dir.path().join("excluded");
+        commit_all(temp_dir.path());
+        let mut tech_debt_hotspots =
+            TechDebtHotspots::new(temp_dir.path(), Some(&excluded), None).unwrap();
+        tech_debt_hotspots.collect().unwrap();
+
+        fs::write(
+            &file1,
+            "def foo(a):\n    return a\n\n\ndef bar(b):\n    return b\n",
+        )
+        .unwrap();
+        fs::remove_file(&file2).unwrap();
+        // Changes to the files outside of the analysis are ignored
+        let notes = temp_dir.path().join("notes.txt");
+        let site = excluded.join("site.py");
+        fs::write(&notes, "notes").unwrap();
+        fs::create_dir(&excluded).unwrap();
+        fs::write(&site, "import os\n").unwrap();
+
+        // ACT
+        tech_debt_hotspots.refresh(&[file1, file2, notes, site]);
+
+        // ASSERT
+        let actual = tech_debt_hotspots
+            .stats()
+            .into_iter()
+            .map(|stats| (stats.path, stats.loc, stats.changes_count))
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, [("file1.py".to_string(), 6, 1)]);
+    }
+
+    #[rstest]
+    fn test_refresh_lossy(git_repo_with_files: (TempDir, PathBuf, PathBuf)) {
+        // ARRANGE
+        let (temp_dir, file1, _) = git_repo_with_files;
+        commit_all(temp_dir.path());
+        let mut tech_debt_hotspots = TechDebtHotspots::new(temp_dir.path(), None, None).unwrap();
+        tech_debt_hotspots.collect().unwrap();
+
+        // ACT
+        fs::write(&file1, b"name = '\xe9'\n").unwrap();
+        tech_debt_hotspots.refresh(std::slice::from_ref(&file1));
+        let lossy = tech_debt_hotspots.lossy().to_vec();
+
+        fs::write(&file1, "name = 'e'\n").unwrap();
+        tech_debt_hotspots.refresh(std::slice::from_ref(&file1));
+
+        // ASSERT
+        assert_eq!(lossy, [file1]);
+        assert!(tech_debt_hotspots.lossy().is_empty());
     }
 }
@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use rustpython_parser::ast::{self, Stmt};
+
+/// The modules imported anywhere in a module, functions and conditional
+/// blocks included, as dotted names prefixed by a dot per level of a
+/// relative import, e.g. `pkg.a`, `pkg.a.b` for `from pkg.a import b` and
+/// `..c` for `from .. import c`.
+pub(crate) fn imports(suite: &[Stmt]) -> Vec<String> {
+    let mut imports = BTreeSet::new();
+    walk(suite, &mut imports);
+
+    imports.into_iter().collect()
+}
+
+fn walk(stmts: &[Stmt], imports: &mut BTreeSet<String>) {
+    for stmt in stmts {
+        stmt_imports(stmt, imports);
+    }
+}
+
+fn stmt_imports(stmt: &Stmt, imports: &mut BTreeSet<String>) {
+    match stmt {
+        Stmt::Import(ast::StmtImport { names, .. }) => {
+            imports.extend(names.iter().map(|alias| alias.name.to_string()));
+        }
+        Stmt::ImportFrom(ast::StmtImportFrom {
+            module,
+            names,
+            level,
+            ..
+        }) => {
+            let dots = ".".repeat(level.map_or(0, |level| level.to_usize()));
+            let module = module.as_ref().map_or("", |module| module.as_str());
+
+            for alias in names {
+                let name = match (module, alias.name.as_str()) {
+                    (module, "*") => module.to_string(),
+                    ("", name) => name.to_string(),
+                    (module, name) => format!("{module}.{name}"),
+                };
+                imports.insert(format!("{dots}{name}"));
+            }
+        }
+        Stmt::FunctionDef(ast::StmtFunctionDef { body, .. })
+        | Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { body, .. })
+        | Stmt::ClassDef(ast::StmtClassDef { body, .. })
+        | Stmt::With(ast::StmtWith { body, .. })
+        | Stmt::AsyncWith(ast::StmtAsyncWith { body, .. }) => walk(body, imports),
+        Stmt::If(ast::StmtIf { body, orelse, .. })
+        | Stmt::For(ast::StmtFor { body, orelse, .. })
+        | Stmt::AsyncFor(ast::StmtAsyncFor { body, orelse, .. })
+        | Stmt::While(ast::StmtWhile { body, orelse, .. }) => {
+            walk(body, imports);
+            walk(orelse, imports);
+        }
+        Stmt::Try(ast::StmtTry {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        })
+        | Stmt::TryStar(ast::StmtTryStar {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        }) => {
+            walk(body, imports);
+            for ast::ExceptHandler::ExceptHandler(handler) in handlers {
+                walk(&handler.body, imports);
+            }
+            walk(orelse, imports);
+            walk(finalbody, imports);
+        }
+        Stmt::Match(ast::StmtMatch { cases, .. }) => {
+            for case in cases {
+                walk(&case.body, imports);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The dependencies between the analysed modules, from the modules they
+/// import among them.
+///
+/// An import is resolved to the module whose path relative to the root of
+/// the repository ends with its longest dotted prefix, so that the modules
+/// of a `src` directory are found. An import matching several modules, such
+/// as `utils` in many packages, and the third-party modules are left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ImportGraph {
+    /// The modules imported by each module.
+    pub edges: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl ImportGraph {
+    pub fn new<'a>(modules: impl IntoIterator<Item = (&'a Path, &'a [String])>) -> ImportGraph {
+        let modules = modules.into_iter().collect::<Vec<_>>();
+        let mut by_suffix: HashMap<String, Vec<&Path>> = HashMap::new();
+
+        for (path, _) in &modules {
+            let parts = module_parts(path);
+            for start in 0..parts.len() {
+                by_suffix
+                    .entry(parts[start..].join("."))
+                    .or_default()
+                    .push(path);
+            }
+        }
+
+        let edges = modules
+            .iter()
+            .map(|(path, imports)| {
+                let imported = imports
+                    .iter()
+                    .filter_map(|import| {
+                        let name = absolute_name(path, import)?;
+                        resolve(&by_suffix, &name)
+                    })
+                    .filter(|imported| imported != path)
+                    .map(Path::to_path_buf)
+                    .collect();
+
+                (path.to_path_buf(), imported)
+            })
+            .collect();
+
+        ImportGraph { edges }
+    }
+
+    /// Number of modules importing each module, and of modules it imports.
+    pub fn fan_in_out(&self) -> HashMap<&Path, (u32, u32)> {
+        let mut fan_in_out: HashMap<&Path, (u32, u32)> = self
+            .edges
+            .iter()
+            .map(|(path, imported)| (path.as_path(), (0, imported.len() as u32)))
+            .collect();
+
+        for imported in self.edges.values().flatten() {
+            fan_in_out.entry(imported).or_default().0 += 1;
+        }
+
+        fan_in_out
+    }
+
+    /// The PageRank of each module, the share of the time a walk following
+    /// the imports spends on it, scaled so that the modules are 1 on
+    /// average: a module imported by many modules, or by central ones, is
+    /// above 1 even when few modules import it directly.
+    pub fn centrality(&self) -> HashMap<&Path, f64> {
+        let count = self.edges.len() as f64;
+        let mut ranks: BTreeMap<&Path, f64> = self
+            .edges
+            .keys()
+            .map(|path| (path.as_path(), 1.0 / count))
+            .collect();
+
+        for _ in 0..PAGERANK_ITERATIONS {
+            // The modules importing none of the others spread their rank
+            // over all the modules
+            let dangling = self
+                .edges
+                .iter()
+                .filter(|(_, imported)| imported.is_empty())
+                .map(|(path, _)| ranks[path.as_path()])
+                .sum::<f64>();
+            let mut next: BTreeMap<&Path, f64> = ranks
+                .keys()
+                .map(|path| {
+                    (
+                        *path,
+                        (1.0 - PAGERANK_DAMPING) / count + PAGERANK_DAMPING * dangling / count,
+                    )
+                })
+                .collect();
+
+            for (path, imported) in &self.edges {
+                let share = ranks[path.as_path()] / imported.len() as f64;
+                for imported in imported {
+                    *next.get_mut(imported.as_path()).unwrap() += PAGERANK_DAMPING * share;
+                }
+            }
+
+            let change = ranks
+                .values()
+                .zip(next.values())
+                .map(|(rank, next)| (rank - next).abs())
+                .sum::<f64>();
+            ranks = next;
+            if change < PAGERANK_TOLERANCE {
+                break;
+            }
+        }
+
+        ranks
+            .into_iter()
+            .map(|(path, rank)| (path, rank * count))
+            .collect()
+    }
+}
+
+const PAGERANK_DAMPING: f64 = 0.85;
+const PAGERANK_ITERATIONS: usize = 100;
+const PAGERANK_TOLERANCE: f64 = 1e-9;
+
+/// The dotted name of a module from its path, the package of an
+/// `__init__.py`.
+fn module_parts(path: &Path) -> Vec<String> {
+    let mut parts = path
+        .with_extension("")
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    if parts.last().is_some_and(|part| part == "__init__") {
+        parts.pop();
+    }
+
+    parts
+}
+
+/// The import as a dotted name from the root of the repository for a
+/// relative import, unchanged otherwise, none going above the root.
+fn absolute_name(path: &Path, import: &str) -> Option<String> {
+    let name = import.trim_start_matches('.');
+    let level = import.len() - name.len();
+
+    if level == 0 {
+        return Some(name.to_string());
+    }
+
+    let mut package = module_parts(path);
+    // The package of a module is its parent, an `__init__.py` is its own
+    if path.file_stem().is_some_and(|stem| stem != "__init__") {
+        package.pop()?;
+    }
+    for _ in 1..level {
+        package.pop()?;
+    }
+
+    Some(
+        package
+            .into_iter()
+            .chain((!name.is_empty()).then(|| name.to_string()))
+            .collect::<Vec<_>>()
+            .join("."),
+    )
+}
+
+/// The module of the longest prefix of the dotted name matching a single
+/// module.
+fn resolve<'a>(by_suffix: &HashMap<String, Vec<&'a Path>>, name: &str) -> Option<&'a Path> {
+    let parts = name.split('.').collect::<Vec<_>>();
+
+    (1..=parts.len()).rev().find_map(|end| {
+        match by_suffix.get(&parts[..end].join(".")).map(Vec::as_slice) {
+            Some([path]) => Some(*path),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use rustpython_parser::Parse;
+
+    #[test]
+    fn test_imports() {
+        // ARRANGE
+        let source_code = "import os, pkg.a\nfrom pkg import b as c, d\nfrom . import e\nfrom ..f import *\n\ndef g():\n    if x:\n        from .h import i\n";
+        let suite = ast::Suite::parse(source_code, "module.py").unwrap();
+
+        // ACT
+        let actual = imports(&suite);
+
+        // ASSERT
+        assert_eq!(
+            actual,
+            ["..f", ".e", ".h.i", "os", "pkg.a", "pkg.b", "pkg.d"]
+        );
+    }
+
+    #[rstest]
+    #[case("src/pkg/a.py", "os.path", Some("os.path"))]
+    #[case("src/pkg/a.py", ".b", Some("src.pkg.b"))]
+    #[case("src/pkg/__init__.py", ".b", Some("src.pkg.b"))]
+    #[case("src/pkg/a.py", "..", Some("src"))]
+    #[case("a.py", "..b", None)]
+    fn test_absolute_name(
+        #[case] path: &str,
+        #[case] import: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        // ACT
+        let actual = absolute_name(Path::new(path), import);
+
+        // ASSERT
+        assert_eq!(actual.as_deref(), expected);
+    }
+
+    #[test]
+    fn test_import_graph() {
+        // ARRANGE
+        let imports = [
+            ("src/pkg/__init__.py", vec![".core".to_string()]),
+            (
+                "src/pkg/core.py",
+                vec!["os".to_string(), "pkg.utils.helper".to_string()],
+            ),
+            ("src/pkg/utils.py", vec!["utils".to_string()]),
+            ("src/other/utils.py", vec![]),
+            (
+                "tests/test_core.py",
+                vec!["pkg".to_string(), "pkg.core".to_string()],
+            ),
+        ];
+
+        // ACT
+        let graph = ImportGraph::new(
+            imports
+                .iter()
+                .map(|(path, imports)| (Path::new(*path), imports.as_slice())),
+        );
+        let actual = graph.fan_in_out();
+
+        // ASSERT
+        let expected = HashMap::from([
+            (Path::new("src/pkg/__init__.py"), (1, 1)),
+            (Path::new("src/pkg/core.py"), (2, 1)),
+            (Path::new("src/pkg/utils.py"), (1, 0)),
+            (Path::new("src/other/utils.py"), (0, 0)),
+            (Path::new("tests/test_core.py"), (0, 2)),
+        ]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_centrality() {
+        // ARRANGE
+        let imports = [
+            ("app.py", vec!["core".to_string(), "utils".to_string()]),
+            ("cli.py", vec!["core".to_string()]),
+            ("core.py", vec!["utils".to_string()]),
+            ("utils.py", vec![]),
+            ("script.py", vec![]),
+        ];
+        let graph = ImportGraph::new(
+            imports
+                .iter()
+                .map(|(path, imports)| (Path::new(*path), imports.as_slice())),
+        );
+
+        // ACT
+        let actual = graph.centrality();
+
+        // ASSERT
+        let centrality = |path: &str| actual[Path::new(path)];
+
+        assert!((actual.values().sum::<f64>() - 5.0).abs() < 1e-6);
+        assert!(centrality("utils.py") > centrality("core.py"));
+        assert!(centrality("core.py") > 1.0);
+        assert!(centrality("script.py") < 1.0);
+        assert!((centrality("app.py") - centrality("cli.py")).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_centrality_without_imports() {
+        // ARRANGE
+        let graph = ImportGraph::new([
+            (Path::new("a.py"), [].as_slice()),
+            (Path::new("b.py"), [].as_slice()),
+        ]);
+
+        // ACT
+        let actual = graph.centrality();
+
+        // ASSERT
+        assert!(actual.values().all(|rank| (rank - 1.0).abs() < 1e-9));
+    }
+}
@@ -0,0 +1,105 @@
+//! Identify hotspots of tech debt in a Python codebase by combining the
+//! maintainability index of each file with the number of changes recorded in
+//! its Git history.
+//!
+//! ```no_run
+//! use tech_debt_hotspot::{sort_stats_by, Analysis, SortBy, SortKey};
+//!
+//! let hotspots = Analysis::builder().directory("path/to/repo").run()?;
+//! let stats = sort_stats_by(hotspots.stats(), &[SortKey::new(SortBy::HotspotIndex)]);
+//! # Ok::<(), tech_debt_hotspot::Error>(())
+//! ```
+
+mod aggregation;
+mod analysis;
+mod annotations;
+mod cache;
+mod columnar;
+mod columns;
+mod commit_type;
+mod config;
+mod coverage;
+mod dashboard;
+mod debt_comments;
+mod diff;
+mod discovery;
+mod duckdb;
+mod duplication;
+mod email;
+mod error;
+mod external;
+mod formatting;
+mod formula;
+mod git;
+mod history;
+mod hotspot;
+mod imports;
+mod metadata;
+mod nesting;
+mod overrides;
+mod ownership;
+mod paths;
+mod plugins;
+mod postgres;
+mod preset;
+mod progress;
+mod runs;
+mod schema;
+mod severity;
+mod slack;
+mod sorting;
+mod split;
+mod sql;
+mod sqlite;
+mod summary;
+mod suppressions;
+mod timings;
+mod totals;
+mod untested;
+mod upload;
+
+pub use aggregation::Aggregation;
+pub use analysis::{Analysis, AnalysisBuilder};
+pub use cache::default_cache_dir;
+pub use columns::Column;
+pub use commit_type::{CommitType, CommitTypes};
+pub use config::{
+    nested_overrides, ComplexityMetric, Config, DebtComments, Markers, Weights, CONFIG_FILENAME,
+};
+pub use coverage::Coverage;
+pub use debt_comments::DEFAULT_DEBT_MARKERS;
+pub use diff::{load_snapshot, set_baseline_deltas, Diff, DiffRow, DiffStatus};
+pub use discovery::{StubFiles, DEFAULT_EXCLUDES};
+pub use duckdb::append_to_duckdb;
+pub use email::Email;
+pub use error::{Error, Result};
+pub use formatting::{
+    Output, OutputFormat, ReportKind, StreamingOutput, CRITICAL_MAINTAINABILITY_INDEX,
+};
+pub use formula::Formula;
+pub use git::{is_remote_url, ClonedRepository};
+pub use history::{ChurnUnit, IgnoreCommits, SinceTag};
+pub use hotspot::{
+    ChurnWeight, FunctionDetail, Granularity, HotspotStats, MiRange, MiVariant, PathType, Skipped,
+    TechDebtHotspots, ZeroMi,
+};
+pub use metadata::{Metadata, FORMAT_VERSION};
+pub use overrides::Overrides;
+pub use ownership::{ActiveAuthors, AuthorOwnership, Contribution, FileOwnership, Ownership};
+pub use paths::{PathDisplay, PathStyle};
+pub use plugins::Plugin;
+pub use postgres::upsert_to_postgres;
+pub use preset::Preset;
+pub use runs::{find_run, load_history, record_run, RecordedRun};
+pub use schema::json_schema;
+pub use severity::{Severity, SeverityCutoffs};
+pub use slack::post_to_slack;
+pub use sorting::{sort_stats_by, SortBy, SortKey, SortOrder};
+pub use split::{split, Partition, SplitBy};
+pub use sqlite::{append_to_sqlite, metric_history, worst_since, HistoryPoint, Worsening};
+pub use summary::{Bin, Histogram, MetricSummary, Summary};
+pub use suppressions::Suppression;
+pub use timings::Timings;
+pub use totals::Totals;
+pub use untested::{untested_hotspots, LOW_COVERAGE};
+pub use upload::UploadUrl;
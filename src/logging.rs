@@ -0,0 +1,140 @@
+use std::io::{self, IsTerminal};
+
+use clap::{builder::PossibleValue, ValueEnum};
+use tracing::Subscriber;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// How the log records are written on stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line, for the log pipelines of CI.
+    Json,
+}
+
+impl ValueEnum for LogFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[LogFormat::Text, LogFormat::Json]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            LogFormat::Text => PossibleValue::new("text").help("Human readable lines"),
+            LogFormat::Json => PossibleValue::new("json").help("One JSON object per line"),
+        })
+    }
+}
+
+/// Initialise the logger on stderr from the `-v`/`-q` command line flags,
+/// the records of the `log` crate included.
+///
+/// `RUST_LOG` still takes precedence when set, so individual modules can be
+/// debugged without touching the flags.
+pub fn init(verbosity: u8, quiet: bool, format: LogFormat) {
+    let filter = EnvFilter::builder()
+        .with_default_directive(level(verbosity, quiet).into())
+        .from_env_lossy();
+
+    subscriber(filter, format, io::stderr().is_terminal(), io::stderr).init();
+}
+
+fn level(verbosity: u8, quiet: bool) -> LevelFilter {
+    match (quiet, verbosity) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    }
+}
+
+/// The JSON records have the fields of the event, e.g. `path` and `reason`,
+/// next to the `timestamp`, `level`, `target` and `message` fields.
+fn subscriber<W>(
+    filter: EnvFilter,
+    format: LogFormat,
+    ansi: bool,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer);
+
+    match format {
+        LogFormat::Text => Box::new(builder.without_time().with_ansi(ansi).finish()),
+        LogFormat::Json => Box::new(builder.json().flatten_event(true).finish()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::{info, warn};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[case(0, false, LevelFilter::WARN)]
+    #[case(1, false, LevelFilter::INFO)]
+    #[case(2, false, LevelFilter::DEBUG)]
+    #[case(3, false, LevelFilter::TRACE)]
+    #[case(0, true, LevelFilter::ERROR)]
+    #[case(2, true, LevelFilter::ERROR)]
+    fn test_level(#[case] verbosity: u8, #[case] quiet: bool, #[case] expected: LevelFilter) {
+        // ACT
+        let actual = level(verbosity, quiet);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_subscriber_json() {
+        // ARRANGE
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = subscriber(EnvFilter::new("warn"), LogFormat::Json, false, move || {
+            writer.clone()
+        });
+
+        // ACT
+        tracing::subscriber::with_default(subscriber, || {
+            info!("Analysed 1 Python file");
+            warn!(path = "a.py", reason = "too large", "Skipping the file");
+        });
+
+        // ASSERT
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["level"], "WARN");
+        assert_eq!(records[0]["target"], "tech_debt_hotspot::logging::tests");
+        assert_eq!(records[0]["message"], "Skipping the file");
+        assert_eq!(records[0]["path"], "a.py");
+        assert_eq!(records[0]["reason"], "too large");
+        assert!(records[0]["timestamp"].is_string());
+    }
+}
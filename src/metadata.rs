@@ -0,0 +1,198 @@
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Version of the fields of the rows and metadata of the reports, bumped
+/// whenever one is added, removed or renamed, or changes type or unit, or
+/// the default columns change, so consumers can detect a change instead of
+/// misreading the reports.
+pub const FORMAT_VERSION: u32 = 13;
+
+/// What a report was run against, so archived reports can be told apart.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// Version of the fields of the report, see [`FORMAT_VERSION`].
+    pub format_version: u32,
+    /// Version of the tool which wrote the report.
+    pub version: String,
+    /// Root of the analysed repository.
+    pub root: String,
+    /// Commit checked out, unknown in a repository without commits.
+    pub commit: Option<String>,
+    /// Start of the `--since` window of the changes count.
+    pub since: Option<String>,
+    /// When the report was written, in RFC 3339 UTC.
+    pub timestamp: String,
+    /// Number of analysed files.
+    pub files: usize,
+    /// Number of files skipped because they could not be analysed.
+    pub skipped: usize,
+}
+
+impl Metadata {
+    pub(crate) fn new(
+        root: String,
+        commit: Option<String>,
+        since: Option<String>,
+        files: usize,
+        skipped: usize,
+    ) -> Metadata {
+        Metadata {
+            format_version: FORMAT_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            root,
+            commit,
+            since,
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            files,
+            skipped,
+        }
+    }
+
+    /// The set fields as names and values, in the order of the struct.
+    pub(crate) fn fields(&self) -> Vec<(&'static str, String)> {
+        [
+            ("format_version", Some(self.format_version.to_string())),
+            ("version", Some(self.version.clone())),
+            ("root", Some(self.root.clone())),
+            ("commit", self.commit.clone()),
+            ("since", self.since.clone()),
+            ("timestamp", Some(self.timestamp.clone())),
+            ("files", Some(self.files.to_string())),
+            ("skipped", Some(self.skipped.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotspot::HotspotStats;
+    use crate::ownership::Contribution;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_fields() {
+        // ARRANGE
+        let metadata = Metadata::new(
+            "/repo".to_string(),
+            None,
+            Some("2024-01-01".to_string()),
+            3,
+            1,
+        );
+
+        // ACT
+        let actual = metadata
+            .fields()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        // ASSERT
+        assert_eq!(
+            actual,
+            vec![
+                "format_version",
+                "version",
+                "root",
+                "since",
+                "timestamp",
+                "files",
+                "skipped"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_version() {
+        // ARRANGE
+        let stats = serde_json::to_value(HotspotStats {
+            function: Some("foo".to_string()),
+            start_line: Some(1),
+            end_line: Some(2),
+            knowledge_loss: Some(0.0),
+            monthly_changes: Some([("2024-01".to_string(), 0)].into()),
+            first_commit: NaiveDate::from_ymd_opt(2024, 1, 1),
+            last_author: Some("Alice Smith".to_string()),
+            primary_owner: Some(Contribution {
+                author: "Alice Smith".to_string(),
+                changes_count: 1,
+                share: 100.0,
+            }),
+            coverage: Some(0.0),
+            risk: Some(0.0),
+            rank_delta: Some(0),
+            hotspot_index_delta: Some(0.0),
+            type_annotations: Some(0.0),
+            test_ratio: Some(0.0),
+            test_loc: Some(0),
+            fan_in: Some(0),
+            fan_out: Some(0),
+            centrality: Some(1.0),
+            plugins: [("sql_strings".to_string(), 0.0)].into(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // ACT
+        let mut actual = stats.as_object().unwrap().keys().collect::<Vec<_>>();
+        actual.sort();
+
+        // ASSERT
+        // A change of the fields must bump the format version with them
+        assert_eq!(FORMAT_VERSION, 13);
+        let mut expected = vec![
+            "path",
+            "path_type",
+            "function",
+            "start_line",
+            "end_line",
+            "halstead_volume",
+            "cyclomatic_complexity",
+            "cyclomatic_average",
+            "cyclomatic_total",
+            "max_nesting",
+            "nom",
+            "longest_function",
+            "loc",
+            "comments_percentage",
+            "maintainability_index",
+            "changes_count",
+            "changes_30d",
+            "changes_90d",
+            "changes_365d",
+            "lines_changed",
+            "feature_changes",
+            "fix_changes",
+            "refactor_changes",
+            "chore_changes",
+            "author_entropy",
+            "knowledge_loss",
+            "monthly_changes",
+            "first_commit",
+            "last_author",
+            "primary_owner",
+            "hotspot_index",
+            "severity",
+            "coverage",
+            "risk",
+            "rank_delta",
+            "hotspot_index_delta",
+            "duplication",
+            "duplication_partners",
+            "debt_comments",
+            "type_annotations",
+            "test_ratio",
+            "test_loc",
+            "fan_in",
+            "fan_out",
+            "centrality",
+            "plugins",
+        ];
+        expected.sort();
+        assert_eq!(actual, expected);
+    }
+}
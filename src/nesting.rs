@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use rustpython_parser::ast::{self, Stmt};
+use rustpython_parser::source_code::LineIndex;
+
+/// Maximum depth of nested control flow blocks in a module.
+///
+/// Nested functions start again from zero, the blocks inside them only count
+/// towards their own depth, and `elif` branches are not nested in their `if`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Nesting {
+    /// The deepest nesting of the module, functions included.
+    pub max: u32,
+    /// The deepest nesting of each function, by the line of its `def`.
+    pub functions: HashMap<usize, u32>,
+}
+
+impl Nesting {
+    pub fn new(suite: &[Stmt], source_code: &str) -> Nesting {
+        let mut visitor = Visitor {
+            source_code,
+            line_index: LineIndex::from_source_text(source_code),
+            nesting: Nesting::default(),
+        };
+
+        let module_max = visitor.body(suite, 0);
+        visitor.nesting.max = visitor.nesting.max.max(module_max);
+
+        visitor.nesting
+    }
+}
+
+struct Visitor<'a> {
+    source_code: &'a str,
+    line_index: LineIndex,
+    nesting: Nesting,
+}
+
+impl Visitor<'_> {
+    /// Returns the deepest nesting reached by the statements.
+    fn body(&mut self, body: &[Stmt], depth: u32) -> u32 {
+        body.iter()
+            .map(|stmt| self.stmt(stmt, depth))
+            .max()
+            .unwrap_or(depth)
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, depth: u32) -> u32 {
+        match stmt {
+            Stmt::FunctionDef(ast::StmtFunctionDef { range, body, .. })
+            | Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { range, body, .. }) => {
+                let max = self.body(body, 0);
+                let line = self
+                    .line_index
+                    .source_location(range.start(), self.source_code)
+                    .row
+                    .to_usize();
+
+                self.nesting.functions.insert(line, max);
+                self.nesting.max = self.nesting.max.max(max);
+
+                depth
+            }
+            Stmt::ClassDef(ast::StmtClassDef { body, .. }) => self.body(body, depth),
+            Stmt::If(ast::StmtIf { body, orelse, .. }) => {
+                let orelse_max = match orelse.as_slice() {
+                    // `elif` is parsed as an `if` alone in the `else` branch
+                    [elif @ Stmt::If(_)] => self.stmt(elif, depth),
+                    _ => self.body(orelse, depth + 1),
+                };
+
+                self.body(body, depth + 1).max(orelse_max)
+            }
+            Stmt::For(ast::StmtFor { body, orelse, .. })
+            | Stmt::AsyncFor(ast::StmtAsyncFor { body, orelse, .. })
+            | Stmt::While(ast::StmtWhile { body, orelse, .. }) => {
+                self.body(body, depth + 1).max(self.body(orelse, depth + 1))
+            }
+            Stmt::With(ast::StmtWith { body, .. })
+            | Stmt::AsyncWith(ast::StmtAsyncWith { body, .. }) => self.body(body, depth + 1),
+            Stmt::Try(ast::StmtTry {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            })
+            | Stmt::TryStar(ast::StmtTryStar {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            }) => {
+                let handlers_max = handlers
+                    .iter()
+                    .map(|ast::ExceptHandler::ExceptHandler(handler)| {
+                        self.body(&handler.body, depth + 1)
+                    })
+                    .max()
+                    .unwrap_or(depth);
+
+                self.body(body, depth + 1)
+                    .max(handlers_max)
+                    .max(self.body(orelse, depth + 1))
+                    .max(self.body(finalbody, depth + 1))
+            }
+            Stmt::Match(ast::StmtMatch { cases, .. }) => cases
+                .iter()
+                .map(|case| self.body(&case.body, depth + 1))
+                .max()
+                .unwrap_or(depth),
+            _ => depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use rustpython_parser::Parse;
+
+    fn parse(source_code: &str) -> Option<Nesting> {
+        let suite = ast::Suite::parse(source_code, "module.py").ok()?;
+
+        Some(Nesting::new(&suite, source_code))
+    }
+
+    #[rstest]
+    #[case::empty("", 0)]
+    #[case::flat("x = 1\n", 0)]
+    #[case::nested_loops("for a in b:\n    while a:\n        if a:\n            pass\n", 3)]
+    #[case::elif(
+        "if a:\n    pass\nelif b:\n    pass\nelif c:\n    pass\nelse:\n    pass\n",
+        1
+    )]
+    #[case::else_if("if a:\n    pass\nelse:\n    x = 1\n    if b:\n        pass\n", 2)]
+    #[case::try_except("try:\n    pass\nexcept E:\n    with a:\n        pass\n", 2)]
+    fn test_nesting_max(#[case] source_code: &str, #[case] expected: u32) {
+        // ACT
+        let actual = parse(source_code).unwrap();
+
+        // ASSERT
+        assert_eq!(actual.max, expected);
+    }
+
+    #[test]
+    fn test_nesting_functions() {
+        // ARRANGE
+        let source_code = "if a:\n    def f():\n        for x in y:\n            def g():\n                return 1\n\n\nclass C:\n    @property\n    def m(self):\n        if self:\n            return 1\n";
+
+        // ACT
+        let actual = parse(source_code).unwrap();
+
+        // ASSERT
+        let expected = Nesting {
+            max: 1,
+            functions: HashMap::from([(2, 1), (4, 0), (10, 1)]),
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_nesting_invalid_source_code() {
+        // ACT
+        let actual = parse("def f(:\n");
+
+        // ASSERT
+        assert_eq!(actual, None);
+    }
+}
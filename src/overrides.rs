@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+/// A setting with the values overriding it for the files below some
+/// directories, such as the ones with their own configuration file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides<T> {
+    value: T,
+    directories: Vec<(PathBuf, T)>,
+}
+
+impl<T> Overrides<T> {
+    pub fn new(value: T) -> Self {
+        Overrides {
+            value,
+            directories: vec![],
+        }
+    }
+
+    /// Override the value for the paths below the directory, the deepest
+    /// directory containing a path taking precedence.
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>, value: T) -> Self {
+        self.directories.push((directory.into(), value));
+        self
+    }
+
+    /// The value of the deepest directory containing the path, the default
+    /// value outside of them.
+    pub fn get(&self, path: &Path) -> &T {
+        self.directories
+            .iter()
+            .filter(|(directory, _)| path.starts_with(directory))
+            .max_by_key(|(directory, _)| directory.components().count())
+            .map_or(&self.value, |(_, value)| value)
+    }
+
+    /// Rewrite the directories, e.g. in the style of the displayed paths.
+    pub fn map_directories(mut self, f: impl Fn(&Path) -> PathBuf) -> Self {
+        for (directory, _) in &mut self.directories {
+            *directory = f(directory);
+        }
+        self
+    }
+}
+
+impl<T> From<T> for Overrides<T> {
+    fn from(value: T) -> Self {
+        Overrides::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("app/views.py", 0)]
+    #[case("legacy/views.py", 1)]
+    #[case("legacy/billing/invoice.py", 2)]
+    #[case("legacy-tools/cli.py", 0)]
+    fn test_overrides_get(#[case] path: &str, #[case] expected: u32) {
+        // ARRANGE
+        let overrides = Overrides::new(0)
+            .with_directory("legacy/billing", 2)
+            .with_directory("legacy", 1);
+
+        // ACT
+        let actual = overrides.get(Path::new(path));
+
+        // ASSERT
+        assert_eq!(*actual, expected);
+    }
+}
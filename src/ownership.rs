@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::history::Commit;
+use crate::hotspot::HotspotStats;
+
+/// Who changed the hotspots, and how much of the risk each author knows.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ownership {
+    pub files: Vec<FileOwnership>,
+    pub authors: Vec<AuthorOwnership>,
+}
+
+/// The contributors of a file, by decreasing number of changes.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct FileOwnership {
+    pub path: String,
+    pub hotspot_index: f64,
+    pub contributors: Vec<Contribution>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub author: String,
+    pub changes_count: u32,
+    /// Percentage of the changes of the file made by the author.
+    pub share: f64,
+}
+
+/// What an author owns among the reported files, an author owning the files
+/// they made the most changes to.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuthorOwnership {
+    pub author: String,
+    pub changes_count: u32,
+    pub files_owned: u32,
+    pub loc_owned: u32,
+    /// Sum of the hotspot indexes of the files weighted by the share of the
+    /// changes of the author.
+    pub hotspot_share: f64,
+}
+
+/// The authors still contributing, the changes of the others being
+/// knowledge lost to the team.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActiveAuthors {
+    /// The authors of a commit since the date.
+    Since(NaiveDate),
+    /// The authors with the given names.
+    Names(Vec<String>),
+}
+
+/// Percentage of the changes made by the authors who are not active.
+pub(crate) fn knowledge_loss(
+    changes_by_author: &HashMap<String, u32>,
+    active_authors: &HashSet<String>,
+) -> f64 {
+    let total: u32 = changes_by_author.values().sum();
+    let lost: u32 = changes_by_author
+        .iter()
+        .filter(|(author, _)| !active_authors.contains(*author))
+        .map(|(_, changes_count)| changes_count)
+        .sum();
+
+    match total {
+        0 => 0.0,
+        total => lost as f64 / total as f64 * 100.0,
+    }
+}
+
+/// The author of the most changes, the first by name on a tie, with their
+/// share of the changes.
+pub(crate) fn primary_owner(changes_by_author: &HashMap<String, u32>) -> Option<Contribution> {
+    let total: u32 = changes_by_author.values().sum();
+    let (author, changes_count) = changes_by_author
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))?;
+
+    Some(Contribution {
+        author: author.clone(),
+        changes_count: *changes_count,
+        share: *changes_count as f64 / total.max(1) as f64 * 100.0,
+    })
+}
+
+/// Shannon entropy in bits of the changes count of each author, 0 for a
+/// single author and `log2(n)` for `n` authors with as many changes.
+pub(crate) fn author_entropy(changes: impl IntoIterator<Item = u32>) -> f64 {
+    let changes: Vec<f64> = changes.into_iter().map(f64::from).collect();
+    let total: f64 = changes.iter().sum();
+
+    changes
+        .iter()
+        .filter(|count| **count > 0.0)
+        .map(|count| {
+            let share = count / total;
+            share * (1.0 / share).log2()
+        })
+        .sum()
+}
+
+impl Ownership {
+    /// The files keep the order of `stats`, the authors are sorted by
+    /// decreasing share of the hotspots.
+    pub(crate) fn new(stats: &[HotspotStats], commits: &[Commit]) -> Ownership {
+        let mut changes_by_file: HashMap<&Path, HashMap<&str, u32>> = HashMap::new();
+
+        for commit in commits {
+            for change in &commit.files {
+                *changes_by_file
+                    .entry(change.path.as_path())
+                    .or_default()
+                    .entry(commit.author.as_str())
+                    .or_default() += 1;
+            }
+        }
+
+        let mut authors: HashMap<String, AuthorOwnership> = HashMap::new();
+        let mut files = vec![];
+
+        for stat in stats {
+            let changes = changes_by_file
+                .remove(Path::new(&stat.path))
+                .unwrap_or_default();
+            let total = changes.values().sum::<u32>().max(1) as f64;
+
+            let mut contributors = changes
+                .into_iter()
+                .map(|(author, changes_count)| Contribution {
+                    author: author.to_string(),
+                    changes_count,
+                    share: changes_count as f64 / total * 100.0,
+                })
+                .collect::<Vec<_>>();
+            contributors.sort_unstable_by(|a, b| {
+                b.changes_count
+                    .cmp(&a.changes_count)
+                    .then_with(|| a.author.cmp(&b.author))
+            });
+
+            for (index, contribution) in contributors.iter().enumerate() {
+                let author = authors
+                    .entry(contribution.author.clone())
+                    .or_insert_with(|| AuthorOwnership {
+                        author: contribution.author.clone(),
+                        changes_count: 0,
+                        files_owned: 0,
+                        loc_owned: 0,
+                        hotspot_share: 0.0,
+                    });
+
+                author.changes_count += contribution.changes_count;
+                author.hotspot_share += contribution.share / 100.0 * stat.hotspot_index;
+
+                if index == 0 {
+                    author.files_owned += 1;
+                    author.loc_owned += stat.loc;
+                }
+            }
+
+            files.push(FileOwnership {
+                path: stat.path.clone(),
+                hotspot_index: stat.hotspot_index,
+                contributors,
+            });
+        }
+
+        let mut authors = authors.into_values().collect::<Vec<_>>();
+        authors.sort_unstable_by(|a, b| {
+            b.hotspot_share
+                .total_cmp(&a.hotspot_share)
+                .then_with(|| a.author.cmp(&b.author))
+        });
+
+        Ownership { files, authors }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::FileChange;
+    use crate::hotspot::PathType;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    fn stat(path: &str, loc: u32, hotspot_index: f64) -> HotspotStats {
+        HotspotStats {
+            path: path.to_string(),
+            path_type: PathType::File,
+            function: None,
+            start_line: None,
+            end_line: None,
+            halstead_volume: 0.0,
+            cyclomatic_complexity: 0.0,
+            cyclomatic_average: 0.0,
+            cyclomatic_total: 0.0,
+            max_nesting: 0,
+            nom: 0,
+            longest_function: 0,
+            loc,
+            comments_percentage: 0.0,
+            maintainability_index: 100.0,
+            changes_count: 0,
+            changes_30d: 0,
+            changes_90d: 0,
+            changes_365d: 0,
+            lines_changed: 0,
+            feature_changes: 0,
+            fix_changes: 0,
+            refactor_changes: 0,
+            chore_changes: 0,
+            author_entropy: 0.0,
+            knowledge_loss: None,
+            monthly_changes: None,
+            first_commit: None,
+            last_author: None,
+            primary_owner: None,
+            hotspot_index,
+            coverage: None,
+            risk: None,
+            rank_delta: None,
+            hotspot_index_delta: None,
+            duplication: 0.0,
+            duplication_partners: vec![],
+            debt_comments: 0,
+            type_annotations: None,
+            test_ratio: None,
+            test_loc: None,
+            fan_in: None,
+            fan_out: None,
+            centrality: None,
+            plugins: Default::default(),
+            severity: Default::default(),
+        }
+    }
+
+    fn commit(author: &str, files: &[&str]) -> Commit {
+        Commit {
+            hash: String::new(),
+            author: author.to_string(),
+            date: None,
+            subject: String::new(),
+            files: files
+                .iter()
+                .map(|path| FileChange {
+                    path: PathBuf::from(path),
+                    lines: 1,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_ownership_new() {
+        // ARRANGE
+        let stats = [stat("a.py", 10, 4.0), stat("b.py", 20, 1.0)];
+        let commits = [
+            commit("alice", &["a.py", "b.py"]),
+            commit("bob", &["a.py"]),
+            commit("bob", &["a.py"]),
+            commit("bob", &["a.py", "removed.py"]),
+        ];
+
+        // ACT
+        let actual = Ownership::new(&stats, &commits);
+
+        // ASSERT
+        let expected = Ownership {
+            files: vec![
+                FileOwnership {
+                    path: "a.py".to_string(),
+                    hotspot_index: 4.0,
+                    contributors: vec![
+                        Contribution {
+                            author: "bob".to_string(),
+                            changes_count: 3,
+                            share: 75.0,
+                        },
+                        Contribution {
+                            author: "alice".to_string(),
+                            changes_count: 1,
+                            share: 25.0,
+                        },
+                    ],
+                },
+                FileOwnership {
+                    path: "b.py".to_string(),
+                    hotspot_index: 1.0,
+                    contributors: vec![Contribution {
+                        author: "alice".to_string(),
+                        changes_count: 1,
+                        share: 100.0,
+                    }],
+                },
+            ],
+            authors: vec![
+                AuthorOwnership {
+                    author: "bob".to_string(),
+                    changes_count: 3,
+                    files_owned: 1,
+                    loc_owned: 10,
+                    hotspot_share: 3.0,
+                },
+                AuthorOwnership {
+                    author: "alice".to_string(),
+                    changes_count: 2,
+                    files_owned: 1,
+                    loc_owned: 20,
+                    hotspot_share: 2.0,
+                },
+            ],
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(vec![5], 0.0)]
+    #[case(vec![3, 3], 1.0)]
+    #[case(vec![1, 1, 1, 1], 2.0)]
+    #[case(vec![3, 1], 0.8112781244591328)]
+    fn test_author_entropy(#[case] changes: Vec<u32>, #[case] expected: f64) {
+        // ACT
+        let actual = author_entropy(changes);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_primary_owner() {
+        // ARRANGE
+        let changes_by_author = HashMap::from([
+            ("Carol".to_string(), 3),
+            ("Alice".to_string(), 3),
+            ("Bob".to_string(), 2),
+        ]);
+
+        // ACT
+        let actual = primary_owner(&changes_by_author);
+
+        // ASSERT
+        let expected = Contribution {
+            author: "Alice".to_string(),
+            changes_count: 3,
+            share: 37.5,
+        };
+
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn test_knowledge_loss() {
+        // ARRANGE
+        let changes_by_author = HashMap::from([
+            ("Alice".to_string(), 3),
+            ("Bob".to_string(), 1),
+            ("Carol".to_string(), 4),
+        ]);
+        let active_authors = HashSet::from(["Alice".to_string()]);
+
+        // ACT
+        let actual = knowledge_loss(&changes_by_author, &active_authors);
+
+        // ASSERT
+        assert_eq!(actual, 62.5);
+    }
+}
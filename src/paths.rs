@@ -0,0 +1,165 @@
+use std::path::{Component, Path, PathBuf};
+
+use clap::{builder::PossibleValue, ValueEnum};
+
+/// How the paths of the rows are displayed, the analysis keeping them
+/// relative to the root of the repository.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PathStyle {
+    #[default]
+    Repository,
+    Absolute,
+    CurrentDirectory,
+}
+
+impl ValueEnum for PathStyle {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            PathStyle::Repository,
+            PathStyle::Absolute,
+            PathStyle::CurrentDirectory,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            PathStyle::Repository => {
+                PossibleValue::new("repo-relative").help("Relative to the root of the repository")
+            }
+            PathStyle::Absolute => PossibleValue::new("absolute").help("Absolute paths"),
+            PathStyle::CurrentDirectory => {
+                PossibleValue::new("cwd-relative").help("Relative to the current directory")
+            }
+        })
+    }
+}
+
+/// Rewrites the paths relative to the root of the repository in a style,
+/// then removes a leading prefix from them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathDisplay {
+    style: PathStyle,
+    root: PathBuf,
+    current_dir: PathBuf,
+    strip_prefix: Option<PathBuf>,
+}
+
+impl PathDisplay {
+    /// Both the root and the current directory are absolute.
+    pub fn new(style: PathStyle, root: &Path, current_dir: &Path) -> Self {
+        PathDisplay {
+            style,
+            root: root.to_path_buf(),
+            current_dir: current_dir.to_path_buf(),
+            strip_prefix: None,
+        }
+    }
+
+    /// Remove the prefix from the paths starting with it, e.g. the package
+    /// of a monorepo.
+    pub fn with_strip_prefix(mut self, prefix: impl Into<PathBuf>) -> Self {
+        self.strip_prefix = Some(normalise(&prefix.into()));
+        self
+    }
+
+    pub fn display(&self, path: &str) -> String {
+        let absolute = normalise(&self.root.join(path));
+        let path = match self.style {
+            PathStyle::Repository => normalise(Path::new(path)),
+            PathStyle::Absolute => absolute,
+            PathStyle::CurrentDirectory => relative(&absolute, &self.current_dir),
+        };
+        let path = match &self.strip_prefix {
+            Some(prefix) => path.strip_prefix(prefix).unwrap_or(&path).to_path_buf(),
+            None => path,
+        };
+
+        match path.as_os_str().is_empty() {
+            true => ".".to_string(),
+            false => path.display().to_string(),
+        }
+    }
+}
+
+/// The path without its `.` components.
+fn normalise(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// The path from the base to an absolute path, going up with `..` out of
+/// the base.
+fn relative(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    base.components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .chain(path.components().skip(common))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(
+        PathStyle::Repository,
+        None,
+        "packages/api/views.py",
+        "packages/api/views.py"
+    )]
+    #[case(
+        PathStyle::Repository,
+        Some("packages/api"),
+        "packages/api/views.py",
+        "views.py"
+    )]
+    #[case(
+        PathStyle::Repository,
+        Some("packages/api"),
+        "tools/lint.py",
+        "tools/lint.py"
+    )]
+    #[case(PathStyle::Repository, Some("packages/api"), "packages/api", ".")]
+    #[case(
+        PathStyle::Absolute,
+        None,
+        "packages/api/views.py",
+        "/repo/packages/api/views.py"
+    )]
+    #[case(PathStyle::CurrentDirectory, None, "packages/api/views.py", "views.py")]
+    #[case(
+        PathStyle::CurrentDirectory,
+        None,
+        "tools/lint.py",
+        "../../tools/lint.py"
+    )]
+    #[case(PathStyle::CurrentDirectory, None, "", "../..")]
+    fn test_path_display(
+        #[case] style: PathStyle,
+        #[case] strip_prefix: Option<&str>,
+        #[case] path: &str,
+        #[case] expected: &str,
+    ) {
+        // ARRANGE
+        let mut paths =
+            PathDisplay::new(style, Path::new("/repo"), Path::new("/repo/packages/api"));
+        if let Some(prefix) = strip_prefix {
+            paths = paths.with_strip_prefix(prefix);
+        }
+
+        // ACT
+        let actual = paths.display(path);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use wasmi::{Engine, Instance, Linker, Module, Store};
+
+use crate::error::{Error, Result};
+use crate::hotspot::FileStats;
+
+/// Instructions a plugin may execute on each file before it is stopped, so a
+/// plugin stuck in a loop does not hang the analysis.
+const FUEL_PER_FILE: u64 = 1_000_000_000;
+
+/// A WebAssembly module computing custom metrics of each file.
+///
+/// The module imports nothing and exports:
+///
+/// - `memory`, its linear memory,
+/// - `alloc(len: i32) -> i32`, returning where to write an input of `len`
+///   bytes,
+/// - `metrics(ptr: i32, len: i32) -> i64`, reading the JSON input
+///   `{"path": ..., "contents": ..., "metrics": {...}}` and returning the
+///   location of its JSON output packed as `ptr << 32 | len`, an object of
+///   numbers by metric name, e.g. `{"sql_strings": 3}`.
+///
+/// A new instance runs on each file, the plugin cannot keep state between
+/// files.
+#[derive(Debug)]
+pub struct Plugin {
+    path: PathBuf,
+    engine: Engine,
+    module: Module,
+}
+
+impl Plugin {
+    /// Compile the module, checking it exports the functions of a plugin.
+    pub fn load(path: &Path) -> Result<Plugin> {
+        let wasm = fs::read(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm).map_err(|error| Error::Plugin {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        })?;
+        let plugin = Plugin {
+            path: path.to_path_buf(),
+            engine,
+            module,
+        };
+
+        plugin.instantiate().map_err(|message| Error::Plugin {
+            path: path.to_path_buf(),
+            message,
+        })?;
+
+        Ok(plugin)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The metrics of the plugin for a file from its content and built-in
+    /// metrics.
+    pub(crate) fn metrics(
+        &self,
+        contents: &str,
+        stats: &FileStats,
+    ) -> Result<BTreeMap<String, f64>> {
+        self.run(contents, stats)
+            .map_err(|message| Error::PluginFailed {
+                plugin: self.path.clone(),
+                path: stats.path.clone(),
+                message,
+            })
+    }
+
+    fn run(
+        &self,
+        contents: &str,
+        stats: &FileStats,
+    ) -> std::result::Result<BTreeMap<String, f64>, String> {
+        let input = json!({
+            "path": stats.path,
+            "contents": contents,
+            "metrics": {
+                "halstead_volume": stats.halstead_volume,
+                "cyclomatic_complexity": stats.cyclomatic_complexity,
+                "max_nesting": stats.max_nesting,
+                "nom": stats.nom,
+                "longest_function": stats.longest_function,
+                "loc": stats.loc,
+                "comments_percentage": stats.comments_percentage,
+                "maintainability_index": stats.maintainability_index,
+                "debt_comments": stats.debt_comments,
+                "type_annotations": stats.type_annotations,
+            },
+        })
+        .to_string();
+
+        let (mut store, instance) = self.instantiate()?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("missing `memory` export")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|error| error.to_string())?;
+        let metrics = instance
+            .get_typed_func::<(i32, i32), i64>(&store, "metrics")
+            .map_err(|error| error.to_string())?;
+
+        let len = i32::try_from(input.len()).map_err(|_| "the file is too large")?;
+        let ptr = alloc
+            .call(&mut store, len)
+            .map_err(|error| error.to_string())?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input.as_bytes())
+            .map_err(|error| error.to_string())?;
+
+        let packed = metrics
+            .call(&mut store, (ptr, len))
+            .map_err(|error| error.to_string())? as u64;
+        let mut output = vec![0; (packed & 0xffff_ffff) as usize];
+        memory
+            .read(&store, (packed >> 32) as usize, &mut output)
+            .map_err(|error| error.to_string())?;
+
+        let metrics = serde_json::from_slice::<BTreeMap<String, f64>>(&output)
+            .map_err(|error| format!("invalid output: {error}"))?;
+
+        match metrics.keys().find(|name| !is_metric_name(name)) {
+            Some(name) => Err(format!(
+                "invalid metric name {name:?}, expected lowercase letters, digits and underscores"
+            )),
+            None => Ok(metrics),
+        }
+    }
+
+    fn instantiate(&self) -> std::result::Result<(Store<()>, Instance), String> {
+        let mut store = Store::new(&self.engine, ());
+        store
+            .set_fuel(FUEL_PER_FILE)
+            .map_err(|error| error.to_string())?;
+
+        let instance = Linker::<()>::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|error| error.to_string())?;
+
+        for export in ["memory", "alloc", "metrics"] {
+            if instance.get_export(&store, export).is_none() {
+                return Err(format!("missing `{export}` export"));
+            }
+        }
+
+        Ok((store, instance))
+    }
+}
+
+/// Whether a name can be a metric of a plugin, e.g. `sql_strings`.
+pub(crate) fn is_metric_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn plugin(output: &str) -> Plugin {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "metrics") (param i32 i32) (result i64) (i64.const {})))"#,
+            output.replace('"', "\\\""),
+            output.len()
+        );
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("plugin.wasm");
+        fs::write(&path, wat::parse_str(wat).unwrap()).unwrap();
+
+        Plugin::load(&path).unwrap()
+    }
+
+    #[test]
+    fn test_plugin_metrics() {
+        // ARRANGE
+        let plugin = plugin(r#"{"sql_strings": 3}"#);
+
+        // ACT
+        let actual = plugin.metrics("x = 1\n", &FileStats::default()).unwrap();
+
+        // ASSERT
+        let expected = BTreeMap::from([("sql_strings".to_string(), 3.0)]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_plugin_metrics_invalid_name() {
+        // ARRANGE
+        let plugin = plugin(r#"{"SQL strings": 3}"#);
+
+        // ACT
+        let actual = plugin.metrics("x = 1\n", &FileStats::default());
+
+        // ASSERT
+        assert!(matches!(actual, Err(Error::PluginFailed { .. })));
+    }
+}
@@ -0,0 +1,115 @@
+use clap::{builder::PossibleValue, ValueEnum};
+use serde::Deserialize;
+
+use crate::config::Markers;
+use crate::severity::SeverityCutoffs;
+
+/// Named set of the marker thresholds and severity cutoffs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    /// Flags the code earlier, for new or well kept codebases.
+    Strict,
+    #[default]
+    Default,
+    /// Flags only the worst code, for legacy codebases.
+    Lenient,
+}
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Strict => "strict",
+            Preset::Default => "default",
+            Preset::Lenient => "lenient",
+        }
+    }
+
+    pub fn markers(&self) -> Markers {
+        match self {
+            Preset::Strict => Markers {
+                maintainability_index: [20.0, 40.0],
+                hotspot_index: [5.0, 10.0],
+                cyclomatic_complexity: [5.0, 10.0],
+            },
+            Preset::Default => Markers::default(),
+            Preset::Lenient => Markers {
+                maintainability_index: [5.0, 10.0],
+                hotspot_index: [20.0, 40.0],
+                cyclomatic_complexity: [20.0, 40.0],
+            },
+        }
+    }
+
+    pub fn severity_cutoffs(&self) -> SeverityCutoffs {
+        match self {
+            Preset::Strict => SeverityCutoffs {
+                medium: 2.0,
+                high: 5.0,
+                critical: 10.0,
+            },
+            Preset::Default => SeverityCutoffs::default(),
+            Preset::Lenient => SeverityCutoffs {
+                medium: 10.0,
+                high: 20.0,
+                critical: 40.0,
+            },
+        }
+    }
+}
+
+impl ValueEnum for Preset {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Preset::Strict, Preset::Default, Preset::Lenient]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let help = match self {
+            Preset::Strict => "Severities from a hotspot index of 2, 5 and 10, 🔴 below a maintainability index of 20 and from a cyclomatic complexity of 10",
+            Preset::Default => "Severities from a hotspot index of 5, 10 and 20, 🔴 below a maintainability index of 10 and from a cyclomatic complexity of 20",
+            Preset::Lenient => "Severities from a hotspot index of 10, 20 and 40, 🔴 below a maintainability index of 5 and from a cyclomatic complexity of 40",
+        };
+
+        Some(PossibleValue::new(self.name()).help(help))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotspot::HotspotStats;
+    use rstest::*;
+
+    #[rstest]
+    #[case(Preset::Strict)]
+    #[case(Preset::Default)]
+    #[case(Preset::Lenient)]
+    fn test_preset_is_consistent(#[case] preset: Preset) {
+        // ACT
+        let markers = preset.markers();
+        let cutoffs = preset.severity_cutoffs();
+
+        // ASSERT
+        assert!(cutoffs.is_valid());
+        assert_eq!(markers.hotspot_index, [cutoffs.high, cutoffs.critical]);
+    }
+
+    #[rstest]
+    #[case(Preset::Strict, 2)]
+    #[case(Preset::Default, 0)]
+    #[case(Preset::Lenient, 0)]
+    fn test_preset_violations(#[case] preset: Preset, #[case] expected: usize) {
+        // ARRANGE
+        let stat = HotspotStats {
+            maintainability_index: 15.0,
+            cyclomatic_complexity: 12.0,
+            ..Default::default()
+        };
+
+        // ACT
+        let actual = preset.markers().violations(&stat);
+
+        // ASSERT
+        assert_eq!(actual.len(), expected);
+    }
+}
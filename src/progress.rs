@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress reporting on stderr, so piped output on stdout is never corrupted.
+///
+/// Indicatif hides the bars automatically when stderr is not a terminal.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct Progress {
+    enabled: bool,
+}
+
+impl Progress {
+    pub fn new(enabled: bool) -> Self {
+        Progress { enabled }
+    }
+
+    pub fn spinner(&self, message: &'static str) -> ProgressBar {
+        if !self.enabled {
+            return ProgressBar::hidden();
+        }
+
+        let spinner = ProgressBar::new_spinner()
+            .with_style(ProgressStyle::with_template("{spinner} {msg}: {pos} [{elapsed}]").unwrap())
+            .with_message(message);
+        spinner.enable_steady_tick(TICK_INTERVAL);
+
+        spinner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spinner_disabled() {
+        // ACT
+        let actual = Progress::new(false).spinner("Parsing");
+
+        // ASSERT
+        assert!(actual.is_hidden());
+    }
+}
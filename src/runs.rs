@@ -0,0 +1,154 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::hotspot::{Granularity, HotspotStats, TechDebtHotspots};
+use crate::metadata::Metadata;
+
+/// A run appended to the history by `--record`, its metadata telling the
+/// commit and the time of the run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRun {
+    pub metadata: Metadata,
+    pub granularity: Granularity,
+    /// The rows of the run, with their paths relative to the root of the
+    /// repository.
+    pub stats: Vec<HotspotStats>,
+}
+
+impl RecordedRun {
+    pub fn new(hotspots: &TechDebtHotspots, granularity: Granularity) -> RecordedRun {
+        RecordedRun {
+            metadata: hotspots.metadata(),
+            granularity,
+            stats: hotspots.stats_by(granularity),
+        }
+    }
+}
+
+/// Append the run to the history, one run per line, creating the history
+/// and its directory if needed.
+pub fn record_run(path: &Path, run: &RecordedRun) -> Result<()> {
+    let io_error = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+
+    let mut line = serde_json::to_string(run).map_err(|error| Error::History {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })?;
+    line.push('\n');
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(io_error)
+}
+
+/// Read the runs of the history, from the oldest to the latest recorded.
+pub fn load_history(path: &Path) -> Result<Vec<RecordedRun>> {
+    let content = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|error| Error::History {
+                path: path.to_path_buf(),
+                message: format!("line {}: {error}", index + 1),
+            })
+        })
+        .collect()
+}
+
+/// The latest run of the granularity, of the commit starting with the given
+/// hash if any.
+pub fn find_run<'a>(
+    runs: &'a [RecordedRun],
+    granularity: Granularity,
+    commit: Option<&str>,
+) -> Option<&'a RecordedRun> {
+    runs.iter().rev().find(|run| {
+        run.granularity == granularity
+            && commit.is_none_or(|commit| {
+                run.metadata
+                    .commit
+                    .as_deref()
+                    .is_some_and(|hash| hash.starts_with(commit))
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(commit: &str, granularity: Granularity, hotspot_index: f64) -> RecordedRun {
+        RecordedRun {
+            metadata: Metadata {
+                commit: Some(commit.to_string()),
+                ..Metadata::new("/repo".to_string(), None, None, 1, 0)
+            },
+            granularity,
+            stats: vec![HotspotStats {
+                path: "a.py".to_string(),
+                hotspot_index,
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_record_run() {
+        // ARRANGE
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("history").join("runs.ndjson");
+        let runs = vec![
+            run("abc123", Granularity::File, 1.0),
+            run("def456", Granularity::Function, 2.0),
+        ];
+
+        // ACT
+        for run in &runs {
+            record_run(&path, run).unwrap();
+        }
+        let actual = load_history(&path).unwrap();
+
+        // ASSERT
+        assert_eq!(actual, runs);
+    }
+
+    #[test]
+    fn test_find_run() {
+        // ARRANGE
+        let runs = vec![
+            run("abc123", Granularity::File, 1.0),
+            run("def456", Granularity::File, 2.0),
+            run("abc123", Granularity::File, 3.0),
+            run("ghi789", Granularity::Function, 4.0),
+        ];
+
+        // ACT
+        let latest = find_run(&runs, Granularity::File, None);
+        let of_commit = find_run(&runs, Granularity::File, Some("def"));
+        let missing = find_run(&runs, Granularity::Function, Some("abc"));
+
+        // ASSERT
+        assert_eq!(latest, Some(&runs[2]));
+        assert_eq!(of_commit, Some(&runs[1]));
+        assert_eq!(missing, None);
+    }
+}
@@ -0,0 +1,282 @@
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+use crate::columns::Column;
+use crate::metadata::FORMAT_VERSION;
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema of the records of the NDJSON hotspots report: the metadata
+/// and the totals before the rows, the rows with the functions of their file in the
+/// detailed report, and the suppressed and skipped files after the rows.
+///
+/// The units of the metrics are given by the `x-unit` keyword.
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "Tech debt hotspots NDJSON record",
+        "oneOf": [
+            { "$ref": "#/$defs/row" },
+            { "$ref": "#/$defs/metadata" },
+            { "$ref": "#/$defs/totals" },
+            { "$ref": "#/$defs/suppressed" },
+            { "$ref": "#/$defs/skipped" },
+        ],
+        "$defs": {
+            "row": row(),
+            "function": function(),
+            "metadata": metadata(),
+            "totals": totals(),
+            "suppressed": suppressed(),
+            "skipped": skipped(),
+        },
+    })
+}
+
+/// A row of the report, every metric being written whatever the columns.
+fn row() -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+
+    for column in Column::value_variants() {
+        let (mut property, is_required) = column_schema(column);
+        property["description"] = json!(column.description());
+        properties.insert(column.name().into_owned(), property);
+
+        if is_required {
+            required.push(column.name().into_owned());
+        }
+    }
+
+    properties.insert(
+        "functions".to_string(),
+        json!({
+            "description": "Functions of the file, in the detailed report only",
+            "type": "array",
+            "items": { "$ref": "#/$defs/function" },
+        }),
+    );
+
+    properties.insert(
+        "plugins".to_string(),
+        json!({
+            "description": "Metrics of the plugins by name",
+            "type": "object",
+            "additionalProperties": { "type": "number" },
+        }),
+    );
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// The JSON schema of the value of a column, and whether the field is always
+/// written.
+fn column_schema(column: &Column) -> (Value, bool) {
+    match column {
+        Column::Path => (json!({ "type": "string" }), true),
+        Column::PathType => (json!({ "enum": ["file", "directory"] }), true),
+        Column::Function => (json!({ "type": "string" }), false),
+        Column::StartLine | Column::EndLine => (
+            json!({ "type": "integer", "minimum": 1, "x-unit": "line" }),
+            false,
+        ),
+        Column::HalsteadVolume | Column::AuthorEntropy => (
+            json!({ "type": "number", "minimum": 0, "x-unit": "bits" }),
+            true,
+        ),
+        Column::CyclomaticComplexity
+        | Column::CyclomaticAverage
+        | Column::CyclomaticTotal
+        | Column::HotspotIndex => (json!({ "type": "number", "minimum": 0 }), true),
+        Column::MaxNesting | Column::Nom | Column::DebtComments => {
+            (json!({ "type": "integer", "minimum": 0 }), true)
+        }
+        Column::LongestFunction | Column::LinesOfCode | Column::LinesChanged => (
+            json!({ "type": "integer", "minimum": 0, "x-unit": "lines" }),
+            true,
+        ),
+        Column::CommentsPercentage | Column::Duplication => (percentage(), true),
+        Column::TypeAnnotations | Column::KnowledgeLoss | Column::Coverage => (percentage(), false),
+        Column::DuplicationPartners => (
+            json!({ "type": "array", "items": { "type": "string" } }),
+            true,
+        ),
+        Column::MonthlyChanges => (
+            json!({
+                "type": "object",
+                "propertyNames": { "pattern": "^[0-9]{4}-[0-9]{2}$" },
+                "additionalProperties": { "type": "integer", "minimum": 0 }
+            }),
+            false,
+        ),
+        Column::FirstCommit => (json!({ "type": "string", "format": "date" }), false),
+        Column::LastAuthor => (json!({ "type": "string" }), false),
+        Column::PrimaryOwner => (
+            json!({
+                "type": "object",
+                "properties": {
+                    "author": { "type": "string" },
+                    "changes_count": { "type": "integer", "minimum": 0, "x-unit": "changes" },
+                    "share": percentage()
+                },
+                "required": ["author", "changes_count", "share"]
+            }),
+            false,
+        ),
+        Column::TestRatio => (json!({ "type": "number", "minimum": 0 }), false),
+        Column::TestLoc => (
+            json!({ "type": "integer", "minimum": 0, "x-unit": "lines" }),
+            false,
+        ),
+        Column::FanIn | Column::FanOut => (
+            json!({ "type": "integer", "minimum": 0, "x-unit": "modules" }),
+            false,
+        ),
+        Column::Centrality => (json!({ "type": "number", "minimum": 0 }), false),
+        Column::MaintainabilityIndex => (
+            json!({ "type": "number", "minimum": 0, "maximum": 100 }),
+            true,
+        ),
+        Column::ChangesCount
+        | Column::Changes30d
+        | Column::Changes90d
+        | Column::Changes365d
+        | Column::FeatureChanges
+        | Column::FixChanges
+        | Column::RefactorChanges
+        | Column::ChoreChanges => (
+            json!({ "type": "integer", "minimum": 0, "x-unit": "changes" }),
+            true,
+        ),
+        Column::Severity => (
+            json!({ "enum": ["low", "medium", "high", "critical"] }),
+            true,
+        ),
+        Column::Risk => (json!({ "type": "number", "minimum": 0 }), false),
+        Column::RankDelta => (json!({ "type": "integer" }), false),
+        Column::HotspotIndexDelta => (json!({ "type": "number" }), false),
+        // Only written by the tabular outputs
+        Column::Percentile(_) | Column::ZScore(_) => (json!({ "type": "number" }), false),
+        // Written under `plugins` instead
+        Column::Plugin(_) => (json!({ "type": "number" }), false),
+    }
+}
+
+fn percentage() -> Value {
+    json!({ "type": "number", "minimum": 0, "maximum": 100, "x-unit": "percent" })
+}
+
+fn function() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "description": "Name of the function, prefixed by its classes" },
+            "start_line": { "type": "integer", "minimum": 1, "x-unit": "line", "description": "First line of the function" },
+            "end_line": { "type": "integer", "minimum": 1, "x-unit": "line", "description": "Last line of the function" },
+            "cyclomatic_complexity": { "type": "number", "minimum": 0, "description": "Cyclomatic complexity" },
+            "cognitive_complexity": { "type": "number", "minimum": 0, "description": "Cognitive complexity" },
+            "loc": { "type": "integer", "minimum": 0, "x-unit": "lines", "description": "Lines of code" },
+        },
+        "required": ["name", "start_line", "end_line", "cyclomatic_complexity", "cognitive_complexity", "loc"],
+    })
+}
+
+fn metadata() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "record": { "const": "metadata" },
+            "format_version": { "const": FORMAT_VERSION, "description": "Version of the fields of the report, bumped when they change" },
+            "version": { "type": "string", "description": "Version of the tool which wrote the report" },
+            "root": { "type": "string", "description": "Root of the analysed repository" },
+            "commit": { "type": ["string", "null"], "description": "Commit checked out, null without commits" },
+            "since": { "type": ["string", "null"], "description": "Start of the window of the changes count, null for the whole history" },
+            "timestamp": { "type": "string", "format": "date-time", "description": "When the report was written" },
+            "files": { "type": "integer", "minimum": 0, "description": "Number of analysed files" },
+            "skipped": { "type": "integer", "minimum": 0, "description": "Number of skipped files" },
+        },
+        "required": ["record", "format_version", "version", "root", "timestamp", "files", "skipped"],
+    })
+}
+
+fn totals() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "record": { "const": "totals" },
+            "files": { "type": "integer", "minimum": 0, "description": "Number of files" },
+            "loc": { "type": "integer", "minimum": 0, "x-unit": "lines", "description": "Lines of code of the files" },
+            "changes_count": { "type": "integer", "minimum": 0, "x-unit": "changes", "description": "Changes of the files" },
+            "maintainability_index": { "type": ["number", "null"], "description": "Maintainability index of the files weighted by their lines of code, null without files" },
+            "critical": { "type": "integer", "minimum": 0, "description": "Number of critical files" },
+            "high": { "type": "integer", "minimum": 0, "description": "Number of files of high severity" },
+            "medium": { "type": "integer", "minimum": 0, "description": "Number of files of medium severity" },
+            "low": { "type": "integer", "minimum": 0, "description": "Number of files of low severity" },
+        },
+        "required": ["record", "files", "loc", "changes_count", "maintainability_index", "critical", "high", "medium", "low"],
+    })
+}
+
+fn suppressed() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "record": { "const": "suppressed" },
+            "path": { "type": "string", "description": "Path of the file" },
+            "function": { "type": ["string", "null"], "description": "The suppressed function, null when the whole file is" },
+            "line": { "type": "integer", "minimum": 1, "description": "Line of the suppression comment" },
+            "reason": { "type": ["string", "null"], "description": "The text following the directive of the comment" },
+        },
+        "required": ["record", "path", "function", "line", "reason"],
+    })
+}
+
+fn skipped() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "record": { "const": "skipped" },
+            "path": { "type": "string", "description": "Path of the skipped file" },
+            "error": { "type": "string", "description": "Why the file was skipped" },
+        },
+        "required": ["record", "path", "error"],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotspot::HotspotStats;
+
+    #[test]
+    fn test_schema_row_matches_stats() {
+        // ARRANGE
+        let stats = serde_json::to_value(HotspotStats {
+            function: Some("foo".to_string()),
+            coverage: Some(50.0),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // ACT
+        let actual = json_schema();
+
+        // ASSERT
+        let properties = actual["$defs"]["row"]["properties"].as_object().unwrap();
+        let required = actual["$defs"]["row"]["required"].as_array().unwrap();
+
+        for field in stats.as_object().unwrap().keys() {
+            assert!(properties.contains_key(field), "{field} is not described");
+        }
+        for field in required {
+            assert!(
+                stats.get(field.as_str().unwrap()).is_some(),
+                "{field} is not written"
+            );
+        }
+    }
+}
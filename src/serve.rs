@@ -0,0 +1,330 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+use tech_debt_hotspot::{
+    sort_stats_by, AnalysisBuilder, Granularity, HotspotStats, SortBy, SortKey,
+};
+
+/// Rows of `/top` without `n`.
+const DEFAULT_TOP: usize = 10;
+
+/// The rows of the latest analysis, by decreasing hotspot index.
+struct State {
+    analysis: AnalysisBuilder,
+    granularity: Granularity,
+    rows: Vec<HotspotStats>,
+    analysed_at: Instant,
+}
+
+impl State {
+    fn new(analysis: AnalysisBuilder, granularity: Granularity) -> Result<State, Box<dyn Error>> {
+        let mut state = State {
+            analysis,
+            granularity,
+            rows: vec![],
+            analysed_at: Instant::now(),
+        };
+        state.refresh()?;
+
+        Ok(state)
+    }
+
+    /// Analyse the repository again, the previous rows being kept when the
+    /// analysis fails.
+    fn refresh(&mut self) -> Result<(), Box<dyn Error>> {
+        self.analysed_at = Instant::now();
+
+        let hotspots = self.analysis.clone().run()?;
+        self.rows = sort_stats_by(
+            hotspots.stats_by(self.granularity),
+            &[SortKey::new(SortBy::HotspotIndex)],
+        );
+        info!("Analysed {} rows", self.rows.len());
+
+        Ok(())
+    }
+}
+
+/// Serve the rows of the analysis as JSON until interrupted, analysing the
+/// repository again on `POST /refresh` and every `refresh_every` if set.
+pub fn run(
+    analysis: AnalysisBuilder,
+    granularity: Granularity,
+    address: &str,
+    refresh_every: Option<Duration>,
+) -> Result<(), Box<dyn Error>> {
+    let mut state = State::new(analysis, granularity)?;
+    let server = Server::http(address).map_err(|error| error as Box<dyn Error>)?;
+    info!("Serving the hotspots on http://{address}");
+
+    loop {
+        let request = match refresh_every {
+            // The time elapsed is read once, as it may reach `every` meanwhile
+            Some(every) => match every.checked_sub(state.analysed_at.elapsed()) {
+                Some(remaining) if !remaining.is_zero() => server.recv_timeout(remaining)?,
+                _ => {
+                    if let Err(error) = state.refresh() {
+                        warn!("Failed to analyse the repository again: {error}");
+                    }
+                    continue;
+                }
+            },
+            None => Some(server.recv()?),
+        };
+
+        if let Some(request) = request {
+            reply(&mut state, request);
+        }
+    }
+}
+
+fn reply(state: &mut State, request: Request) {
+    let (status, body) = respond(state, request.method(), request.url());
+
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("the header is valid");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+
+    if let Err(error) = request.respond(response) {
+        warn!("Failed to respond: {error}");
+    }
+}
+
+/// The status and JSON body of the response to a request of the URL.
+fn respond(state: &mut State, method: &Method, url: &str) -> (u16, String) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    match (method, path) {
+        (Method::Get, "/stats") => to_json(&state.rows),
+        (Method::Get, "/top") => match parse_top(query) {
+            Ok(n) => to_json(&state.rows[..n.min(state.rows.len())]),
+            Err(message) => error(400, message),
+        },
+        (Method::Get, path) if path.starts_with("/file/") => {
+            let file = percent_decode(&path["/file/".len()..]);
+            let rows = state
+                .rows
+                .iter()
+                .filter(|stat| stat.path == file)
+                .collect::<Vec<_>>();
+
+            match rows.is_empty() {
+                true => error(404, format!("no rows for {file:?}")),
+                false => to_json(&rows),
+            }
+        }
+        (Method::Post, "/refresh") => match state.refresh() {
+            Ok(()) => (200, json!({"rows": state.rows.len()}).to_string()),
+            Err(message) => error(500, message.to_string()),
+        },
+        _ => error(404, format!("no endpoint {path}")),
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> (u16, String) {
+    match serde_json::to_string(value) {
+        Ok(body) => (200, body),
+        Err(message) => error(500, message.to_string()),
+    }
+}
+
+fn error(status: u16, message: String) -> (u16, String) {
+    (status, json!({"error": message}).to_string())
+}
+
+/// The `n` parameter of the query, [`DEFAULT_TOP`] if missing.
+fn parse_top(query: &str) -> Result<usize, String> {
+    match query.split('&').find_map(|param| param.strip_prefix("n=")) {
+        Some(n) => n
+            .parse()
+            .map_err(|_| format!("n must be a number of rows, not {n:?}")),
+        None => Ok(DEFAULT_TOP),
+    }
+}
+
+/// Decode the `%XX` escapes of a path of a URL, e.g. a space as `%20`.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn state(paths: &[&str]) -> State {
+        State {
+            analysis: AnalysisBuilder::default(),
+            granularity: Granularity::File,
+            rows: paths
+                .iter()
+                .map(|path| HotspotStats {
+                    path: path.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            analysed_at: Instant::now(),
+        }
+    }
+
+    fn commit_file(directory: &Path, path: &str) {
+        fs::write(directory.join(path), "def foo(a):\n    return a\n").unwrap();
+        Command::new("git")
+            .current_dir(directory)
+            .args(["add", path])
+            .output()
+            .unwrap();
+        Command::new("git")
+            .current_dir(directory)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(["commit", "-m", path])
+            .output()
+            .unwrap();
+    }
+
+    #[rstest]
+    #[case(Method::Get, "/stats", 200, Some(vec!["a.py", "my pkg/b.py", "a.py"]))]
+    #[case(Method::Get, "/top?n=2", 200, Some(vec!["a.py", "my pkg/b.py"]))]
+    #[case(Method::Get, "/top?n=20", 200, Some(vec!["a.py", "my pkg/b.py", "a.py"]))]
+    #[case(Method::Get, "/top?n=0", 200, Some(vec![]))]
+    #[case(Method::Get, "/top?n=all", 400, None)]
+    #[case(Method::Get, "/file/my%20pkg/b.py", 200, Some(vec!["my pkg/b.py"]))]
+    #[case(Method::Get, "/file/a.py", 200, Some(vec!["a.py", "a.py"]))]
+    #[case(Method::Get, "/file/c.py", 404, None)]
+    #[case(Method::Get, "/unknown", 404, None)]
+    #[case(Method::Post, "/stats", 404, None)]
+    fn test_respond(
+        #[case] method: Method,
+        #[case] url: &str,
+        #[case] expected_status: u16,
+        #[case] expected_paths: Option<Vec<&str>>,
+    ) {
+        // ARRANGE
+        let mut state = state(&["a.py", "my pkg/b.py", "a.py"]);
+
+        // ACT
+        let (status, body) = respond(&mut state, &method, url);
+
+        // ASSERT
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(status, expected_status);
+        match expected_paths {
+            Some(expected_paths) => {
+                let paths = body
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|row| row["path"].as_str().unwrap())
+                    .collect::<Vec<_>>();
+                assert_eq!(paths, expected_paths);
+            }
+            None => assert!(body["error"].is_string()),
+        }
+    }
+
+    #[test]
+    fn test_respond_refresh() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        Command::new("git")
+            .arg("init")
+            .arg(temp_dir.path())
+            .output()
+            .unwrap();
+        commit_file(temp_dir.path(), "a.py");
+        let analysis = AnalysisBuilder::default().directory(temp_dir.path());
+        let mut state = State::new(analysis, Granularity::File).unwrap();
+        commit_file(temp_dir.path(), "b.py");
+
+        // ACT
+        let actual = respond(&mut state, &Method::Post, "/refresh");
+
+        // ASSERT
+        assert_eq!(actual, (200, json!({"rows": 2}).to_string()));
+    }
+
+    #[test]
+    fn test_respond_refresh_failed() {
+        // ARRANGE
+        let temp_dir = tempdir().unwrap();
+        Command::new("git")
+            .arg("init")
+            .arg(temp_dir.path())
+            .output()
+            .unwrap();
+        commit_file(temp_dir.path(), "a.py");
+        let analysis = AnalysisBuilder::default().directory(temp_dir.path());
+        let mut state = State::new(analysis, Granularity::File).unwrap();
+        fs::remove_dir_all(temp_dir.path().join(".git")).unwrap();
+
+        // ACT
+        let (status, _) = respond(&mut state, &Method::Post, "/refresh");
+
+        // ASSERT
+        assert_eq!(status, 500);
+        assert_eq!(state.rows.len(), 1);
+        assert_eq!(state.rows[0].path, "a.py");
+    }
+
+    #[rstest]
+    #[case("pkg/a.py", "pkg/a.py")]
+    #[case("my%20pkg/a.py", "my pkg/a.py")]
+    #[case("%C3%A9t%C3%A9.py", "été.py")]
+    #[case("100%.py", "100%.py")]
+    #[case("a%zz.py", "a%zz.py")]
+    fn test_percent_decode(#[case] path: &str, #[case] expected: &str) {
+        // ACT
+        let actual = percent_decode(path);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("", Ok(DEFAULT_TOP))]
+    #[case("n=20", Ok(20))]
+    #[case("verbose=1&n=3", Ok(3))]
+    #[case("n=all", Err("n must be a number of rows, not \"all\"".to_string()))]
+    fn test_parse_top(#[case] query: &str, #[case] expected: Result<usize, String>) {
+        // ACT
+        let actual = parse_top(query);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,98 @@
+use clap::{builder::PossibleValue, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+/// Named bucket of the hotspot index.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl ValueEnum for Severity {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Severity::Low,
+            Severity::Medium,
+            Severity::High,
+            Severity::Critical,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+/// Hotspot index from which each severity starts, below `medium` being low.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeverityCutoffs {
+    pub medium: f64,
+    pub high: f64,
+    pub critical: f64,
+}
+
+impl Default for SeverityCutoffs {
+    fn default() -> Self {
+        SeverityCutoffs {
+            medium: 5.0,
+            high: 10.0,
+            critical: 20.0,
+        }
+    }
+}
+
+impl SeverityCutoffs {
+    pub fn severity(&self, hotspot_index: f64) -> Severity {
+        match hotspot_index {
+            value if value >= self.critical => Severity::Critical,
+            value if value >= self.high => Severity::High,
+            value if value >= self.medium => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+
+    /// Whether the cutoffs are finite numbers in increasing order.
+    pub fn is_valid(&self) -> bool {
+        let cutoffs = [self.medium, self.high, self.critical];
+
+        cutoffs.iter().all(|cutoff| cutoff.is_finite())
+            && cutoffs.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0.0, Severity::Low)]
+    #[case(5.0, Severity::Medium)]
+    #[case(19.9, Severity::High)]
+    #[case(f64::INFINITY, Severity::Critical)]
+    fn test_severity_cutoffs_severity(#[case] hotspot_index: f64, #[case] expected: Severity) {
+        // ACT
+        let actual = SeverityCutoffs::default().severity(hotspot_index);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::diff::Diff;
+use crate::error::Result;
+use crate::external::run_with_stdin;
+use crate::formatting::{format_arrow, format_number};
+use crate::hotspot::HotspotStats;
+
+/// Rows of the digest, below the 50 blocks of a Slack message once the
+/// header and the context are added.
+pub(crate) const SLACK_MAX_ROWS: usize = 45;
+
+/// A [Block Kit](https://api.slack.com/block-kit) message with a section per
+/// row among the first ones, with the change of its metrics since the
+/// baseline, and the number of the other rows.
+pub(crate) fn payload(
+    stats: &[HotspotStats],
+    top: usize,
+    baseline: &HashMap<(String, Option<String>), HotspotStats>,
+) -> Value {
+    let top = top.min(SLACK_MAX_ROWS).min(stats.len());
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": {"type": "plain_text", "text": "Tech debt hotspots"},
+    })];
+
+    for (rank, stat) in stats[..top].iter().enumerate() {
+        let before = baseline.get(&(stat.path.clone(), stat.function.clone()));
+        let mut title = format!("*{}.* `{}`", rank + 1, stat.path);
+
+        if let Some(function) = &stat.function {
+            title.push_str(&format!(" `{function}`"));
+        }
+        if before.is_none() && !baseline.is_empty() {
+            title.push_str(" :new:");
+        }
+
+        let metrics = Diff::COLUMNS
+            .iter()
+            .map(|column| {
+                let value = column.metric(stat);
+                let arrow = before
+                    .map(|before| format_arrow(column.metric(before), value))
+                    .unwrap_or_default();
+
+                format!("{} {}{arrow}", column.name(), format_number(value))
+            })
+            .collect::<Vec<_>>()
+            .join(" · ");
+
+        blocks.push(json!({
+            "type": "section",
+            "text": {"type": "mrkdwn", "text": format!("{title}\n{metrics}")},
+        }));
+    }
+
+    if stats.len() > top {
+        blocks.push(json!({
+            "type": "context",
+            "elements": [{"type": "mrkdwn", "text": format!("{} more rows", stats.len() - top)}],
+        }));
+    }
+
+    json!({
+        "text": format!("Tech debt hotspots: top {top} of {} rows", stats.len()),
+        "blocks": blocks,
+    })
+}
+
+/// Post a message to a Slack incoming webhook with the `curl` CLI, which
+/// must be on the `PATH`.
+pub fn post_to_slack(webhook: &str, payload: &[u8]) -> Result<()> {
+    run_with_stdin(
+        "curl",
+        [
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--header",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+            webhook,
+        ],
+        payload,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        // ARRANGE
+        let stat = |path: &str, hotspot_index| HotspotStats {
+            path: path.to_string(),
+            hotspot_index,
+            maintainability_index: 50.0,
+            loc: 10,
+            ..Default::default()
+        };
+        let baseline = HashMap::from([(("a.py".to_string(), None), stat("a.py", 1.0))]);
+
+        // ACT
+        let actual = payload(
+            &[stat("a.py", 2.5), stat("b.py", 2.0), stat("c.py", 1.0)],
+            2,
+            &baseline,
+        );
+
+        // ASSERT
+        let expected = json!({
+            "text": "Tech debt hotspots: top 2 of 3 rows",
+            "blocks": [
+                {"type": "header", "text": {"type": "plain_text", "text": "Tech debt hotspots"}},
+                {"type": "section", "text": {"type": "mrkdwn", "text": "*1.* `a.py`\nhotspot_index 2.5 ▲ 1.5 · maintainability_index 50 · cyclomatic_complexity 0 · loc 10"}},
+                {"type": "section", "text": {"type": "mrkdwn", "text": "*2.* `b.py` :new:\nhotspot_index 2 · maintainability_index 50 · cyclomatic_complexity 0 · loc 10"}},
+                {"type": "context", "elements": [{"type": "mrkdwn", "text": "1 more rows"}]},
+            ],
+        });
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,17 +1,38 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
 use clap::{builder::PossibleValue, ValueEnum};
 
+use crate::columns::Column;
 use crate::hotspot::HotspotStats;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum SortBy {
     Path,
     MaintainabilityIndex,
     HalsteadVolume,
     CyclomaticComplexity,
+    CyclomaticAverage,
+    CyclomaticTotal,
+    MaxNesting,
+    Nom,
+    LongestFunction,
     LinesOfCode,
     CommentsPercentage,
+    DebtComments,
+    TestLoc,
+    FanIn,
+    Centrality,
     ChangesCount,
+    Changes30d,
+    Changes90d,
+    Changes365d,
     HotspotIndex,
+    Risk,
+    RankDelta,
+    HotspotIndexDelta,
+    /// A metric of a plugin, e.g. `plugin.sql_strings`.
+    Plugin(&'static str),
 }
 
 impl ValueEnum for SortBy {
@@ -21,15 +42,31 @@ impl ValueEnum for SortBy {
             SortBy::MaintainabilityIndex,
             SortBy::HalsteadVolume,
             SortBy::CyclomaticComplexity,
+            SortBy::CyclomaticAverage,
+            SortBy::CyclomaticTotal,
+            SortBy::MaxNesting,
+            SortBy::Nom,
+            SortBy::LongestFunction,
             SortBy::LinesOfCode,
             SortBy::CommentsPercentage,
+            SortBy::DebtComments,
+            SortBy::TestLoc,
+            SortBy::FanIn,
+            SortBy::Centrality,
             SortBy::ChangesCount,
+            SortBy::Changes30d,
+            SortBy::Changes90d,
+            SortBy::Changes365d,
             SortBy::HotspotIndex,
+            SortBy::Risk,
+            SortBy::RankDelta,
+            SortBy::HotspotIndexDelta,
         ]
     }
 
     fn to_possible_value(&self) -> Option<PossibleValue> {
         Some(match self {
+            SortBy::Plugin(_) => return None,
             SortBy::Path => PossibleValue::new("path").help("Sort by path"),
             SortBy::MaintainabilityIndex => {
                 PossibleValue::new("maintainability_index").help("Sort by maintainability index")
@@ -40,69 +77,388 @@ impl ValueEnum for SortBy {
             SortBy::CyclomaticComplexity => {
                 PossibleValue::new("cyclomatic_complexity").help("Sort by cyclomatic complexity")
             }
+            SortBy::CyclomaticAverage => PossibleValue::new("cyclomatic_average")
+                .help("Sort by average cyclomatic complexity"),
+            SortBy::CyclomaticTotal => {
+                PossibleValue::new("cyclomatic_total").help("Sort by total cyclomatic complexity")
+            }
+            SortBy::MaxNesting => {
+                PossibleValue::new("max_nesting").help("Sort by maximum nesting depth")
+            }
+            SortBy::Nom => PossibleValue::new("nom").help("Sort by number of functions"),
+            SortBy::LongestFunction => PossibleValue::new("longest_function")
+                .help("Sort by length of the longest function"),
             SortBy::LinesOfCode => {
                 PossibleValue::new("lines_of_code").help("Sort by lines of code")
             }
             SortBy::CommentsPercentage => {
                 PossibleValue::new("comments_percentage").help("Sort by comments percentage")
             }
+            SortBy::DebtComments => {
+                PossibleValue::new("debt_comments").help("Sort by number of debt comments")
+            }
+            SortBy::TestLoc => PossibleValue::new("test_loc")
+                .help("Sort by lines of code of the tests named after the module"),
+            SortBy::FanIn => {
+                PossibleValue::new("fan_in").help("Sort by number of modules importing the module")
+            }
+            SortBy::Centrality => PossibleValue::new("centrality")
+                .help("Sort by centrality of the module in the import graph"),
             SortBy::ChangesCount => {
                 PossibleValue::new("changes_count").help("Sort by changes count")
             }
+            SortBy::Changes30d => {
+                PossibleValue::new("changes_30d").help("Sort by changes in the last 30 days")
+            }
+            SortBy::Changes90d => {
+                PossibleValue::new("changes_90d").help("Sort by changes in the last 90 days")
+            }
+            SortBy::Changes365d => {
+                PossibleValue::new("changes_365d").help("Sort by changes in the last 365 days")
+            }
             SortBy::HotspotIndex => {
                 PossibleValue::new("hotspot_index").help("Sort by hotspot index")
             }
+            SortBy::Risk => PossibleValue::new("risk").help("Sort by risk, requires --coverage"),
+            SortBy::RankDelta => PossibleValue::new("rank_delta")
+                .help("Sort by places moved up since the --baseline"),
+            SortBy::HotspotIndexDelta => PossibleValue::new("hotspot_index_delta")
+                .help("Sort by change of the hotspot index since the --baseline"),
         })
     }
 }
 
-pub fn sort_stats_by(mut stats: Vec<HotspotStats>, sort_by: SortBy) -> Vec<HotspotStats> {
-    match sort_by {
-        SortBy::Path => {
-            stats.sort_unstable_by(|a, b| a.path.cmp(&b.path));
-        }
-        SortBy::MaintainabilityIndex => {
-            stats.sort_unstable_by(|a, b| {
-                b.maintainability_index
-                    .partial_cmp(&a.maintainability_index)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
-        }
-        SortBy::HalsteadVolume => {
-            stats.sort_unstable_by(|a, b| {
-                b.halstead_volume
-                    .partial_cmp(&a.halstead_volume)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
-        }
-        SortBy::CyclomaticComplexity => {
-            stats.sort_unstable_by(|a, b| {
-                b.cyclomatic_complexity
-                    .partial_cmp(&a.cyclomatic_complexity)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+/// Direction of the sort.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub fn reverse(&self) -> SortOrder {
+        match self {
+            SortOrder::Ascending => SortOrder::Descending,
+            SortOrder::Descending => SortOrder::Ascending,
         }
-        SortBy::LinesOfCode => {
-            stats.sort_unstable_by(|a, b| b.loc.cmp(&a.loc));
+    }
+}
+
+impl SortBy {
+    /// Paths are sorted alphabetically, the metrics from the highest value.
+    pub fn default_order(&self) -> SortOrder {
+        match self {
+            SortBy::Path => SortOrder::Ascending,
+            _ => SortOrder::Descending,
         }
-        SortBy::CommentsPercentage => {
-            stats.sort_unstable_by(|a, b| {
-                b.comments_percentage
-                    .partial_cmp(&a.comments_percentage)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+    }
+
+    /// Compare two rows from the lowest to the highest value.
+    fn compare(&self, a: &HotspotStats, b: &HotspotStats) -> Ordering {
+        match self {
+            SortBy::Path => a.path.cmp(&b.path),
+            SortBy::MaintainabilityIndex => {
+                a.maintainability_index.total_cmp(&b.maintainability_index)
+            }
+            SortBy::HalsteadVolume => a.halstead_volume.total_cmp(&b.halstead_volume),
+            SortBy::CyclomaticComplexity => {
+                a.cyclomatic_complexity.total_cmp(&b.cyclomatic_complexity)
+            }
+            SortBy::CyclomaticAverage => a.cyclomatic_average.total_cmp(&b.cyclomatic_average),
+            SortBy::CyclomaticTotal => a.cyclomatic_total.total_cmp(&b.cyclomatic_total),
+            SortBy::MaxNesting => a.max_nesting.cmp(&b.max_nesting),
+            SortBy::Nom => a.nom.cmp(&b.nom),
+            SortBy::LongestFunction => a.longest_function.cmp(&b.longest_function),
+            SortBy::LinesOfCode => a.loc.cmp(&b.loc),
+            SortBy::CommentsPercentage => a.comments_percentage.total_cmp(&b.comments_percentage),
+            SortBy::DebtComments => a.debt_comments.cmp(&b.debt_comments),
+            SortBy::TestLoc => a
+                .test_loc
+                .unwrap_or_default()
+                .cmp(&b.test_loc.unwrap_or_default()),
+            SortBy::FanIn => a
+                .fan_in
+                .unwrap_or_default()
+                .cmp(&b.fan_in.unwrap_or_default()),
+            SortBy::Centrality => a
+                .centrality
+                .unwrap_or_default()
+                .total_cmp(&b.centrality.unwrap_or_default()),
+            SortBy::ChangesCount => a.changes_count.cmp(&b.changes_count),
+            SortBy::Changes30d => a.changes_30d.cmp(&b.changes_30d),
+            SortBy::Changes90d => a.changes_90d.cmp(&b.changes_90d),
+            SortBy::Changes365d => a.changes_365d.cmp(&b.changes_365d),
+            SortBy::HotspotIndex => a.hotspot_index.total_cmp(&b.hotspot_index),
+            SortBy::Risk => a
+                .risk
+                .unwrap_or_default()
+                .total_cmp(&b.risk.unwrap_or_default()),
+            SortBy::RankDelta => a
+                .rank_delta
+                .unwrap_or_default()
+                .cmp(&b.rank_delta.unwrap_or_default()),
+            SortBy::HotspotIndexDelta => a
+                .hotspot_index_delta
+                .unwrap_or_default()
+                .total_cmp(&b.hotspot_index_delta.unwrap_or_default()),
+            SortBy::Plugin(name) => a
+                .plugins
+                .get(*name)
+                .unwrap_or(&0.0)
+                .total_cmp(b.plugins.get(*name).unwrap_or(&0.0)),
         }
-        SortBy::ChangesCount => {
-            stats.sort_unstable_by(|a, b| b.changes_count.cmp(&a.changes_count));
+    }
+}
+
+/// A column to sort by, in its default order unless set, e.g.
+/// `changes_count` or `path:desc`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortKey {
+    pub sort_by: SortBy,
+    pub order: Option<SortOrder>,
+}
+
+impl SortKey {
+    pub fn new(sort_by: SortBy) -> Self {
+        SortKey {
+            sort_by,
+            order: None,
         }
-        SortBy::HotspotIndex => {
-            stats.sort_unstable_by(|a, b| {
-                b.hotspot_index
-                    .partial_cmp(&a.hotspot_index)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+    }
+
+    /// The order of the key, the default one of the column when not set.
+    pub fn order(&self) -> SortOrder {
+        self.order.unwrap_or_else(|| self.sort_by.default_order())
+    }
+
+    fn compare(&self, a: &HotspotStats, b: &HotspotStats) -> Ordering {
+        let ordering = match self.order() {
+            SortOrder::Ascending => self.sort_by.compare(a, b),
+            SortOrder::Descending => self.sort_by.compare(b, a),
+        };
+
+        // Rows without coverage or hotspot index come last in both orders
+        match self.sort_by {
+            SortBy::Risk => a.risk.is_none().cmp(&b.risk.is_none()).then(ordering),
+            SortBy::HotspotIndex => a
+                .hotspot_index
+                .is_nan()
+                .cmp(&b.hotspot_index.is_nan())
+                .then(ordering),
+            _ => ordering,
         }
-    };
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, order) = match value.rsplit_once(':') {
+            Some((name, "asc")) => (name, Some(SortOrder::Ascending)),
+            Some((name, "desc")) => (name, Some(SortOrder::Descending)),
+            Some((_, order)) => {
+                return Err(format!(
+                    "invalid sort order {order:?}, expected asc or desc"
+                ))
+            }
+            None => (value, None),
+        };
+        let sort_by = match Column::from_plugin_name(name)? {
+            Some(Column::Plugin(name)) => SortBy::Plugin(name),
+            _ => SortBy::from_str(name, false)?,
+        };
+
+        Ok(SortKey { sort_by, order })
+    }
+}
+
+/// Sort the rows by the first key, the ties by the next keys and then by
+/// path, function and line so the order is the same across runs.
+pub fn sort_stats_by(mut stats: Vec<HotspotStats>, keys: &[SortKey]) -> Vec<HotspotStats> {
+    stats.sort_unstable_by(|a, b| {
+        keys.iter()
+            .fold(Ordering::Equal, |ordering, key| {
+                ordering.then_with(|| key.compare(a, b))
+            })
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.function.cmp(&b.function))
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
 
     stats
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("risk", vec!["b.py", "a.py", "c.py"])]
+    #[case("risk:asc", vec!["a.py", "b.py", "c.py"])]
+    #[case("changes_count", vec!["a.py", "b.py", "c.py"])]
+    #[case("changes_count:asc", vec!["c.py", "a.py", "b.py"])]
+    #[case("changes_count,path:desc", vec!["b.py", "a.py", "c.py"])]
+    #[case("changes_count:asc,risk", vec!["c.py", "b.py", "a.py"])]
+    fn test_sort_stats_by(#[case] keys: &str, #[case] expected: Vec<&str>) {
+        // ARRANGE
+        let stat = |path: &str, changes_count, risk| HotspotStats {
+            path: path.to_string(),
+            changes_count,
+            risk,
+            ..Default::default()
+        };
+        let stats = vec![
+            stat("c.py", 1, None),
+            stat("a.py", 2, Some(1.0)),
+            stat("b.py", 2, Some(2.0)),
+        ];
+        let keys = keys
+            .split(',')
+            .map(|key| key.parse::<SortKey>().unwrap())
+            .collect::<Vec<_>>();
+
+        // ACT
+        let actual = sort_stats_by(stats, &keys);
+
+        // ASSERT
+        let actual = actual
+            .iter()
+            .map(|stat| stat.path.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("nom", vec!["b.py", "a.py"])]
+    #[case("nom:asc", vec!["a.py", "b.py"])]
+    #[case("longest_function", vec!["a.py", "b.py"])]
+    #[case("longest_function:asc", vec!["b.py", "a.py"])]
+    fn test_sort_stats_by_metric(#[case] key: &str, #[case] expected: Vec<&str>) {
+        // ARRANGE
+        let stats = vec![
+            HotspotStats {
+                path: "a.py".to_string(),
+                nom: 1,
+                longest_function: 30,
+                ..Default::default()
+            },
+            HotspotStats {
+                path: "b.py".to_string(),
+                nom: 2,
+                longest_function: 10,
+                ..Default::default()
+            },
+        ];
+        let keys = [key.parse::<SortKey>().unwrap()];
+
+        // ACT
+        let actual = sort_stats_by(stats, &keys);
+
+        // ASSERT
+        let actual = actual
+            .iter()
+            .map(|stat| stat.path.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("path", SortBy::Path, None)]
+    #[case("changes_count:asc", SortBy::ChangesCount, Some(SortOrder::Ascending))]
+    #[case("path:desc", SortBy::Path, Some(SortOrder::Descending))]
+    #[case("plugin.sql_strings", SortBy::Plugin("sql_strings"), None)]
+    fn test_sort_key_from_str(
+        #[case] value: &str,
+        #[case] sort_by: SortBy,
+        #[case] order: Option<SortOrder>,
+    ) {
+        // ACT
+        let actual = value.parse::<SortKey>();
+
+        // ASSERT
+        assert_eq!(actual, Ok(SortKey { sort_by, order }));
+    }
+
+    #[rstest]
+    #[case("path:up", "invalid sort order \"up\", expected asc or desc")]
+    #[case("plugin.SQL", "invalid plugin metric `plugin.SQL`")]
+    #[case("unknown", "invalid variant: unknown")]
+    fn test_sort_key_from_str_invalid(#[case] value: &str, #[case] expected: &str) {
+        // ACT
+        let actual = value.parse::<SortKey>();
+
+        // ASSERT
+        assert_eq!(actual, Err(expected.to_string()));
+    }
+
+    #[test]
+    fn test_sort_stats_by_plugin() {
+        // ARRANGE
+        let stat = |path: &str, value| HotspotStats {
+            path: path.to_string(),
+            plugins: [("sql_strings".to_string(), value)].into(),
+            ..Default::default()
+        };
+        let stats = vec![stat("a.py", 1.0), stat("b.py", 3.0), stat("c.py", 2.0)];
+        let keys = ["plugin.sql_strings".parse::<SortKey>().unwrap()];
+
+        // ACT
+        let actual = sort_stats_by(stats, &keys);
+
+        // ASSERT
+        let actual = actual
+            .iter()
+            .map(|stat| stat.path.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec!["b.py", "c.py", "a.py"]);
+    }
+
+    #[test]
+    fn test_sort_stats_by_ties() {
+        // ARRANGE
+        let stat = |path: &str, function: Option<&str>, start_line| HotspotStats {
+            path: path.to_string(),
+            function: function.map(str::to_string),
+            start_line,
+            changes_count: 1,
+            ..Default::default()
+        };
+        let stats = vec![
+            stat("b.py", Some("foo"), Some(10)),
+            stat("b.py", Some("foo"), Some(2)),
+            stat("b.py", Some("bar"), Some(5)),
+            stat("a.py", None, None),
+        ];
+        let keys = ["changes_count".parse::<SortKey>().unwrap()];
+
+        // ACT
+        let actual = sort_stats_by(stats, &keys);
+
+        // ASSERT
+        let actual = actual
+            .iter()
+            .map(|stat| {
+                (
+                    stat.path.as_str(),
+                    stat.function.as_deref(),
+                    stat.start_line,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![
+                ("a.py", None, None),
+                ("b.py", Some("bar"), Some(5)),
+                ("b.py", Some("foo"), Some(2)),
+                ("b.py", Some("foo"), Some(10)),
+            ]
+        );
+    }
+}
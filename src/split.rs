@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hotspot::{HotspotStats, PathType};
+
+/// How the rows of a monorepo are partitioned into one report per package.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SplitBy {
+    /// The directories at this depth below the root, e.g. `1` for the
+    /// top-level directories.
+    Depth(usize),
+    /// The directories matching a pattern of path components where `*`
+    /// matches any characters and `?` a single one, e.g. `packages/*`.
+    Pattern(Vec<String>),
+}
+
+impl FromStr for SplitBy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(depth) = value.parse::<usize>() {
+            return match depth {
+                0 => Err("the depth must be at least 1".to_string()),
+                depth => Ok(SplitBy::Depth(depth)),
+            };
+        }
+
+        let components = value
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        match components.is_empty() {
+            true => Err(format!("{value:?} is neither a depth nor a pattern")),
+            false => Ok(SplitBy::Pattern(components)),
+        }
+    }
+}
+
+impl SplitBy {
+    /// The partition of a row, none for the rows above the partitions such
+    /// as the files at the root of the repository.
+    pub fn partition(&self, stat: &HotspotStats) -> Option<String> {
+        let components = Path::new(&stat.path)
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let depth = match self {
+            SplitBy::Depth(depth) => *depth,
+            SplitBy::Pattern(patterns) => patterns.len(),
+        };
+
+        // A file is inside its directory, a directory is its own partition
+        let inside = match stat.path_type {
+            PathType::File => components.len() > depth,
+            PathType::Directory => components.len() >= depth,
+        };
+
+        if !inside {
+            return None;
+        }
+
+        let prefix = &components[..depth];
+
+        if let SplitBy::Pattern(patterns) = self {
+            let matches = patterns
+                .iter()
+                .zip(prefix)
+                .all(|(pattern, name)| matches_glob(pattern, name));
+
+            if !matches {
+                return None;
+            }
+        }
+
+        Some(prefix.join("/"))
+    }
+}
+
+/// The rows of a partition with the worst metrics of its files for the
+/// index.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Partition {
+    pub name: String,
+    #[serde(skip)]
+    pub stats: Vec<HotspotStats>,
+    pub rows: usize,
+    pub max_hotspot_index: f64,
+    pub min_maintainability_index: f64,
+}
+
+/// Group the rows by partition, keeping their order, the rows outside of
+/// every partition being left out.
+pub fn split(stats: Vec<HotspotStats>, split_by: &SplitBy) -> Vec<Partition> {
+    let mut partitions: BTreeMap<String, Vec<HotspotStats>> = BTreeMap::new();
+
+    for stat in stats {
+        if let Some(name) = split_by.partition(&stat) {
+            partitions.entry(name).or_default().push(stat);
+        }
+    }
+
+    partitions
+        .into_iter()
+        .map(|(name, stats)| {
+            // The directory rows add up their files
+            let files = stats
+                .iter()
+                .filter(|stat| stat.path_type == PathType::File)
+                .collect::<Vec<_>>();
+
+            Partition {
+                name,
+                rows: stats.len(),
+                max_hotspot_index: files
+                    .iter()
+                    .map(|stat| stat.hotspot_index)
+                    .fold(0.0, f64::max),
+                min_maintainability_index: files
+                    .iter()
+                    .map(|stat| stat.maintainability_index)
+                    .fold(f64::INFINITY, f64::min),
+                stats,
+            }
+        })
+        .collect()
+}
+
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.as_bytes(), name.as_bytes());
+
+    // Position after the last `*` and the name position it was matched to
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn file(path: &str) -> HotspotStats {
+        HotspotStats {
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case("1", "api/views.py", Some("api"))]
+    #[case("1", "setup.py", None)]
+    #[case("2", "packages/api/views.py", Some("packages/api"))]
+    #[case("packages/*", "packages/api/src/views.py", Some("packages/api"))]
+    #[case("packages/*", "tools/lint.py", None)]
+    #[case("*-service", "auth-service/app.py", Some("auth-service"))]
+    #[case("lib?", "lib2/x.py", Some("lib2"))]
+    fn test_split_by_partition(
+        #[case] split_by: &str,
+        #[case] path: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        // ARRANGE
+        let split_by = split_by.parse::<SplitBy>().unwrap();
+
+        // ACT
+        let actual = split_by.partition(&file(path));
+
+        // ASSERT
+        assert_eq!(actual.as_deref(), expected);
+    }
+
+    #[test]
+    fn test_split() {
+        // ARRANGE
+        let stats = vec![
+            file("b/x.py"),
+            file("a/x.py"),
+            file("setup.py"),
+            file("b/y.py"),
+        ];
+
+        // ACT
+        let actual = split(stats, &SplitBy::Depth(1))
+            .into_iter()
+            .map(|partition| {
+                let paths = partition
+                    .stats
+                    .into_iter()
+                    .map(|stat| stat.path)
+                    .collect::<Vec<_>>();
+
+                (partition.name, paths)
+            })
+            .collect::<Vec<_>>();
+
+        // ASSERT
+        let expected = vec![
+            ("a".to_string(), vec!["a/x.py".to_string()]),
+            (
+                "b".to_string(),
+                vec!["b/x.py".to_string(), "b/y.py".to_string()],
+            ),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,10 @@
+//! Quoting shared by the database sinks, which hand their statements to the
+//! CLI of the database.
+
+pub(crate) fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+pub(crate) fn quote_literal(literal: &str) -> String {
+    format!("'{}'", literal.replace('\'', "''"))
+}
@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::columns::Column;
+use crate::error::{Error, Result};
+use crate::external::{output_with_stdin, run_with_stdin};
+use crate::hotspot::{Granularity, HotspotStats};
+use crate::runs::RecordedRun;
+use crate::sql::quote_literal;
+
+/// Metrics kept for each row of the recorded runs.
+const METRICS: [Column; 5] = [
+    Column::HotspotIndex,
+    Column::MaintainabilityIndex,
+    Column::CyclomaticComplexity,
+    Column::LinesOfCode,
+    Column::ChangesCount,
+];
+
+/// The metrics of a file or function in a recorded run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPoint {
+    pub run_at: String,
+    pub commit: Option<String>,
+    pub hotspot_index: Option<f64>,
+    pub maintainability_index: Option<f64>,
+    pub cyclomatic_complexity: Option<f64>,
+    pub loc: Option<f64>,
+    pub changes_count: Option<f64>,
+}
+
+/// How much the hotspot index of a file or function grew between its first
+/// and its latest recorded run since a date.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Worsening {
+    pub path: String,
+    pub function: Option<String>,
+    /// Number of runs since the date recording the row.
+    pub runs: u64,
+    pub hotspot_index_before: f64,
+    pub hotspot_index_after: f64,
+}
+
+impl Worsening {
+    pub fn hotspot_index_delta(&self) -> f64 {
+        self.hotspot_index_after - self.hotspot_index_before
+    }
+}
+
+/// Insert the run in a SQLite database, creating its tables if missing, a
+/// row of `runs` per run and its rows in `hotspots`.
+///
+/// The statements are handed to the `sqlite3` CLI in a single transaction.
+pub fn append_to_sqlite(database: &Path, run: &RecordedRun) -> Result<()> {
+    run_with_stdin("sqlite3", [database], insert_sql(run).as_bytes())
+}
+
+/// The metrics of the file, or of the function of the file, in each
+/// recorded run, from the oldest run.
+pub fn metric_history(
+    database: &Path,
+    path: &str,
+    function: Option<&str>,
+) -> Result<Vec<HistoryPoint>> {
+    let metrics = metric_names("h.");
+    let function = match function {
+        Some(function) => format!("= {}", quote_literal(function)),
+        None => "IS NULL".to_string(),
+    };
+
+    query(
+        database,
+        &format!(
+            "SELECT r.run_at, r.\"commit\", {metrics} FROM hotspots h \
+             JOIN runs r ON r.id = h.run_id \
+             WHERE h.path = {path} AND h.function {function} \
+             ORDER BY r.run_at, r.id;",
+            path = quote_literal(path),
+        ),
+    )
+}
+
+/// The rows whose hotspot index grew the most over the runs of the
+/// granularity recorded since the date, at most `limit` of them.
+pub fn worst_since(
+    database: &Path,
+    since: NaiveDate,
+    granularity: Granularity,
+    limit: usize,
+) -> Result<Vec<Worsening>> {
+    let granularity = serde_json::to_value(granularity).expect("a granularity is serialisable");
+
+    query(
+        database,
+        &format!(
+            "WITH ranked AS (\
+             SELECT h.path, h.function, h.hotspot_index, \
+             row_number() OVER (PARTITION BY h.path, h.function ORDER BY r.run_at, r.id) AS first, \
+             row_number() OVER (PARTITION BY h.path, h.function ORDER BY r.run_at DESC, r.id DESC) AS last \
+             FROM hotspots h JOIN runs r ON r.id = h.run_id \
+             WHERE r.run_at >= {since} AND r.granularity = {granularity} AND h.hotspot_index IS NOT NULL) \
+             SELECT path, function, count(*) AS runs, \
+             max(CASE WHEN first = 1 THEN hotspot_index END) AS hotspot_index_before, \
+             max(CASE WHEN last = 1 THEN hotspot_index END) AS hotspot_index_after \
+             FROM ranked GROUP BY path, function \
+             HAVING hotspot_index_after > hotspot_index_before \
+             ORDER BY hotspot_index_after - hotspot_index_before DESC, path, function \
+             LIMIT {limit};",
+            since = quote_literal(&since.to_string()),
+            granularity = quote_literal(granularity.as_str().unwrap_or_default()),
+        ),
+    )
+}
+
+/// Run a query with the `sqlite3` CLI, which prints nothing instead of an
+/// empty array without rows.
+fn query<T: DeserializeOwned>(database: &Path, sql: &str) -> Result<Vec<T>> {
+    let output = output_with_stdin(
+        "sqlite3",
+        [database.as_os_str(), "-json".as_ref(), "-bail".as_ref()],
+        sql.as_bytes(),
+    )?;
+
+    if output.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    serde_json::from_str(&output).map_err(|error| Error::History {
+        path: database.to_path_buf(),
+        message: error.to_string(),
+    })
+}
+
+fn metric_names(prefix: &str) -> String {
+    METRICS
+        .iter()
+        .map(|column| format!("{prefix}{}", column.name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn insert_sql(run: &RecordedRun) -> String {
+    let metric_columns = METRICS
+        .iter()
+        .map(|column| format!(", {} REAL", column.name()))
+        .collect::<String>();
+    let commit = run
+        .metadata
+        .commit
+        .as_deref()
+        .map_or("NULL".to_string(), quote_literal);
+    let granularity = serde_json::to_value(run.granularity).expect("a granularity is serialisable");
+    let rows = run
+        .stats
+        .iter()
+        .map(row_values)
+        .collect::<Vec<_>>()
+        .join(",\n");
+    let insert_rows = match rows.is_empty() {
+        true => String::new(),
+        false => format!(
+            "INSERT INTO hotspots (run_id, path, function, {metrics}) VALUES\n{rows};\n",
+            metrics = metric_names(""),
+        ),
+    };
+
+    format!(
+        "BEGIN;\n\
+         CREATE TABLE IF NOT EXISTS runs (id INTEGER PRIMARY KEY, run_at TEXT NOT NULL, \"commit\" TEXT, granularity TEXT NOT NULL);\n\
+         CREATE TABLE IF NOT EXISTS hotspots (run_id INTEGER NOT NULL REFERENCES runs (id), path TEXT NOT NULL, function TEXT{metric_columns});\n\
+         CREATE INDEX IF NOT EXISTS hotspots_path ON hotspots (path, function);\n\
+         INSERT INTO runs (run_at, \"commit\", granularity) VALUES ({timestamp}, {commit}, {granularity});\n\
+         {insert_rows}\
+         COMMIT;\n",
+        timestamp = quote_literal(&run.metadata.timestamp),
+        granularity = quote_literal(granularity.as_str().unwrap_or_default()),
+    )
+}
+
+/// The values of a row, the NaN and infinite metrics being stored as nulls.
+fn row_values(stat: &HotspotStats) -> String {
+    let metrics = METRICS.iter().map(|column| {
+        column
+            .metric(stat)
+            .filter(|value| value.is_finite())
+            .map_or("NULL".to_string(), |value| format!("{value:?}"))
+    });
+    let values = [
+        "(SELECT max(id) FROM runs)".to_string(),
+        quote_literal(&stat.path),
+        stat.function
+            .as_deref()
+            .map_or("NULL".to_string(), quote_literal),
+    ]
+    .into_iter()
+    .chain(metrics)
+    .collect::<Vec<_>>();
+
+    format!("({})", values.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+
+    #[test]
+    fn test_insert_sql() {
+        // ARRANGE
+        let run = RecordedRun {
+            metadata: Metadata {
+                commit: Some("abc".to_string()),
+                ..Metadata::new(".".to_string(), None, None, 1, 0)
+            },
+            granularity: Granularity::Function,
+            stats: vec![HotspotStats {
+                path: "it's.py".to_string(),
+                function: Some("f".to_string()),
+                hotspot_index: 1.5,
+                maintainability_index: f64::NAN,
+                loc: 3,
+                ..Default::default()
+            }],
+        };
+
+        // ACT
+        let actual = insert_sql(&run);
+
+        // ASSERT
+        assert!(actual.starts_with("BEGIN;\n"));
+        assert!(actual.contains(", 'abc', 'function');\n"));
+        assert!(actual.contains(
+            "\n((SELECT max(id) FROM runs), 'it''s.py', 'f', 1.5, NULL, 0.0, 3.0, 0.0);\n"
+        ));
+        assert!(actual.ends_with("COMMIT;\n"));
+    }
+}
@@ -0,0 +1,483 @@
+use std::fmt;
+
+use serde::Serialize;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+use crate::columns::Column;
+use crate::config::Markers;
+use crate::formatting::format_number;
+use crate::hotspot::{HotspotStats, PathType};
+use crate::sorting::{sort_stats_by, SortBy, SortKey, SortOrder};
+use crate::totals::Totals;
+
+/// Number of hotspots listed in the summary.
+const TOP_HOTSPOTS: usize = 5;
+
+/// Number of bins of the histograms.
+const HISTOGRAM_BINS: usize = 10;
+
+/// Characters of the longest bar of a histogram.
+const HISTOGRAM_WIDTH: usize = 30;
+
+/// Fraction of the files, the most changed ones, whose share of the changes
+/// is given with the Gini coefficient.
+const MOST_CHANGED_FILES: f64 = 0.2;
+
+/// A one-screen health check of the project: the distribution of each
+/// metric, the files above the thresholds and the top hotspots.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub totals: Totals,
+    /// Number of files with a maintainability index below the threshold.
+    pub critical_maintainability: usize,
+    pub maintainability_threshold: f64,
+    /// Gini coefficient of the changes count of the files, from 0 when every
+    /// file changed as much to 1 when a single file absorbed every change,
+    /// unknown without changes.
+    pub churn_gini: Option<f64>,
+    /// Share of the changes, from 0 to 1, of the 20% most changed files,
+    /// unknown without changes.
+    pub most_changed_share: Option<f64>,
+    pub metrics: Vec<MetricSummary>,
+    /// Histograms of the maintainability and hotspot indexes, showing
+    /// whether their distributions are bimodal or have a long tail.
+    pub histograms: Vec<Histogram>,
+    /// The files of highest hotspot index.
+    pub top: Vec<HotspotStats>,
+}
+
+/// Distribution of a metric among the files, unknown when no file has it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MetricSummary {
+    pub name: String,
+    pub min: Option<f64>,
+    pub median: Option<f64>,
+    pub p90: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Counts of the finite values of a metric in bins of equal width between
+/// their minimum and maximum, the infinite values being counted apart.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Histogram {
+    pub name: String,
+    pub bins: Vec<Bin>,
+    pub infinite: usize,
+}
+
+/// A bin of a histogram, the values from `start` included to `end` excluded,
+/// the last bin including its end.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Bin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+impl Summary {
+    /// The summary of the file rows, the function and directory rows being
+    /// left out, the files marked 🟡 or 🔴 being above the thresholds.
+    pub(crate) fn new(stats: &[HotspotStats], thresholds: &Markers) -> Summary {
+        let maintainability_threshold = thresholds.maintainability_index[1];
+        let files = stats
+            .iter()
+            .filter(|stat| stat.path_type == PathType::File && stat.function.is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        let metrics = Column::FILE_DEFAULTS
+            .iter()
+            .filter(|column| column.is_metric())
+            .map(|column| MetricSummary::new(column, &files))
+            .collect();
+        let histograms = [Column::MaintainabilityIndex, Column::HotspotIndex]
+            .iter()
+            .map(|column| Histogram::new(column, &files, HISTOGRAM_BINS))
+            .collect();
+        let critical_maintainability = files
+            .iter()
+            .filter(|stat| stat.maintainability_index < maintainability_threshold)
+            .count();
+
+        let mut changes = files
+            .iter()
+            .map(|stat| stat.changes_count as f64)
+            .collect::<Vec<_>>();
+        changes.sort_unstable_by(f64::total_cmp);
+
+        let mut top = sort_stats_by(
+            files.clone(),
+            &[SortKey {
+                sort_by: SortBy::HotspotIndex,
+                order: Some(SortOrder::Descending),
+            }],
+        );
+        top.truncate(TOP_HOTSPOTS);
+
+        Summary {
+            totals: Totals::new(&files),
+            critical_maintainability,
+            maintainability_threshold,
+            churn_gini: gini(&changes),
+            most_changed_share: top_share(&changes, MOST_CHANGED_FILES),
+            metrics,
+            histograms,
+            top,
+        }
+    }
+}
+
+impl MetricSummary {
+    fn new(column: &Column, stats: &[HotspotStats]) -> MetricSummary {
+        let mut values = stats
+            .iter()
+            .filter_map(|stat| column.metric(stat))
+            .filter(|value| !value.is_nan())
+            .collect::<Vec<_>>();
+        values.sort_unstable_by(f64::total_cmp);
+
+        MetricSummary {
+            name: column.name().into_owned(),
+            min: values.first().copied(),
+            median: quantile(&values, 0.5),
+            p90: quantile(&values, 0.9),
+            max: values.last().copied(),
+        }
+    }
+}
+
+impl Histogram {
+    fn new(column: &Column, stats: &[HotspotStats], bins: usize) -> Histogram {
+        let values = stats
+            .iter()
+            .filter_map(|stat| column.metric(stat))
+            .filter(|value| !value.is_nan())
+            .collect::<Vec<_>>();
+        let finite = values
+            .iter()
+            .copied()
+            .filter(|value| value.is_finite())
+            .collect::<Vec<_>>();
+        let min = finite.iter().copied().reduce(f64::min);
+        let max = finite.iter().copied().reduce(f64::max);
+
+        let bins = match min.zip(max) {
+            Some((min, max)) => {
+                // A single bin when every value is the same
+                let bins = if min == max { 1 } else { bins };
+                let width = (max - min) / bins as f64;
+                let mut counts = vec![0; bins];
+
+                for value in &finite {
+                    let index = ((value - min) / width) as usize;
+                    counts[index.min(bins - 1)] += 1;
+                }
+
+                counts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, count)| Bin {
+                        start: min + index as f64 * width,
+                        end: match index + 1 {
+                            end if end == bins => max,
+                            end => min + end as f64 * width,
+                        },
+                        count,
+                    })
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        Histogram {
+            name: column.name().into_owned(),
+            bins,
+            infinite: values.len() - finite.len(),
+        }
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highest = self
+            .bins
+            .iter()
+            .map(|bin| bin.count)
+            .chain([self.infinite])
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        let bar = |count: usize| {
+            let bar = "█".repeat((count * HISTOGRAM_WIDTH).div_ceil(highest));
+            format!("{bar} {count}").trim_start().to_string()
+        };
+
+        let mut builder = Builder::default();
+        for bin in &self.bins {
+            builder.push_record([
+                format!(
+                    "{} - {}",
+                    format_number(Some(bin.start)),
+                    format_number(Some(bin.end))
+                ),
+                bar(bin.count),
+            ]);
+        }
+        if self.infinite > 0 {
+            builder.push_record(["inf".to_string(), bar(self.infinite)]);
+        }
+        let mut table = builder.build();
+        table.with(Style::blank());
+
+        write!(f, "{}\n{table}", self.name)
+    }
+}
+
+/// The nearest-rank quantile of sorted values.
+fn quantile(sorted: &[f64], quantile: f64) -> Option<f64> {
+    let rank = (quantile * sorted.len() as f64).ceil() as usize;
+
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+/// The Gini coefficient of sorted values, unknown when they are all 0.
+fn gini(sorted: &[f64]) -> Option<f64> {
+    let total = sorted.iter().sum::<f64>();
+    if total == 0.0 {
+        return None;
+    }
+
+    let count = sorted.len() as f64;
+    let weighted = sorted
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (index + 1) as f64 * value)
+        .sum::<f64>();
+
+    Some(2.0 * weighted / (count * total) - (count + 1.0) / count)
+}
+
+/// The share of the total of the highest values, a fraction of the values
+/// rounded up, unknown when they are all 0.
+fn top_share(sorted: &[f64], fraction: f64) -> Option<f64> {
+    let total = sorted.iter().sum::<f64>();
+    if total == 0.0 {
+        return None;
+    }
+
+    let count = (fraction * sorted.len() as f64).ceil() as usize;
+    let top = sorted.iter().rev().take(count).sum::<f64>();
+
+    Some(top / total)
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let totals = &self.totals;
+
+        writeln!(
+            f,
+            "{} files, {} lines of code, {} changes, maintainability index {}",
+            totals.files,
+            totals.loc,
+            totals.changes_count,
+            format_number(totals.maintainability_index)
+        )?;
+        writeln!(
+            f,
+            "Severity: {} critical, {} high, {} medium, {} low",
+            totals.critical, totals.high, totals.medium, totals.low
+        )?;
+        writeln!(
+            f,
+            "Files with a maintainability index below {}: {}",
+            self.maintainability_threshold, self.critical_maintainability
+        )?;
+        if let Some((gini, share)) = self.churn_gini.zip(self.most_changed_share) {
+            writeln!(
+                f,
+                "Churn concentration: Gini {}, the {}% most changed files absorb {}% of the changes",
+                format_number(Some(gini)),
+                MOST_CHANGED_FILES * 100.0,
+                format_number(Some(share * 100.0))
+            )?;
+        }
+
+        let mut builder = Builder::default();
+        builder.push_record(["metric", "min", "median", "p90", "max"]);
+        for metric in &self.metrics {
+            builder.push_record([
+                metric.name.clone(),
+                format_number(metric.min),
+                format_number(metric.median),
+                format_number(metric.p90),
+                format_number(metric.max),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(Style::blank());
+        writeln!(f, "\n{table}")?;
+
+        for histogram in &self.histograms {
+            writeln!(f, "\n{histogram}")?;
+        }
+
+        writeln!(f, "\nTop {} hotspots:", self.top.len())?;
+        for (rank, stat) in self.top.iter().enumerate() {
+            writeln!(
+                f,
+                "{}. {} hotspot index {} ({})",
+                rank + 1,
+                stat.path,
+                format_number(Some(stat.hotspot_index)),
+                stat.severity.name()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preset::Preset;
+    use rstest::*;
+
+    #[rstest]
+    #[case(&[], 0.5, None)]
+    #[case(&[3.0], 0.9, Some(3.0))]
+    #[case(&[1.0, 2.0, 3.0, 4.0], 0.5, Some(2.0))]
+    #[case(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0], 0.9, Some(9.0))]
+    fn test_quantile(#[case] sorted: &[f64], #[case] q: f64, #[case] expected: Option<f64>) {
+        // ACT
+        let actual = quantile(sorted, q);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_histogram() {
+        // ARRANGE
+        let stats = [0.0, 1.0, 1.5, 10.0, f64::INFINITY]
+            .into_iter()
+            .map(|hotspot_index| HotspotStats {
+                hotspot_index,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        // ACT
+        let actual = Histogram::new(&Column::HotspotIndex, &stats, 4);
+
+        // ASSERT
+        let bin = |start, end, count| Bin { start, end, count };
+        let expected = Histogram {
+            name: "hotspot_index".to_string(),
+            bins: vec![
+                bin(0.0, 2.5, 3),
+                bin(2.5, 5.0, 0),
+                bin(5.0, 7.5, 0),
+                bin(7.5, 10.0, 1),
+            ],
+            infinite: 1,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(&[], None)]
+    #[case(&[0.0, 0.0], None)]
+    #[case(&[3.0, 3.0, 3.0, 3.0], Some(0.0))]
+    #[case(&[0.0, 0.0, 0.0, 4.0], Some(0.75))]
+    #[case(&[1.0, 2.0, 3.0, 4.0], Some(0.25))]
+    fn test_gini(#[case] sorted: &[f64], #[case] expected: Option<f64>) {
+        // ACT
+        let actual = gini(sorted);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(&[], None)]
+    #[case(&[1.0, 1.0, 1.0, 1.0, 1.0], Some(0.2))]
+    #[case(&[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 2.0, 4.0, 12.0], Some(0.8))]
+    fn test_top_share(#[case] sorted: &[f64], #[case] expected: Option<f64>) {
+        // ACT
+        let actual = top_share(sorted, 0.2);
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_summary() {
+        // ARRANGE
+        let stat = |path: &str, path_type, maintainability_index, hotspot_index| HotspotStats {
+            path: path.to_string(),
+            path_type,
+            maintainability_index,
+            hotspot_index,
+            ..Default::default()
+        };
+        let stats = (1..=7)
+            .map(|i| {
+                stat(
+                    &format!("{i}.py"),
+                    PathType::File,
+                    i as f64 * 10.0,
+                    i as f64,
+                )
+            })
+            .chain([stat(".", PathType::Directory, 5.0, 100.0)])
+            .collect::<Vec<_>>();
+
+        // ACT
+        let actual = Summary::new(&stats, &Markers::default());
+
+        // ASSERT
+        let maintainability_index = actual
+            .metrics
+            .iter()
+            .find(|metric| metric.name == "maintainability_index")
+            .unwrap();
+        let top = actual
+            .top
+            .iter()
+            .map(|stat| stat.path.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual.totals.files, 7);
+        assert_eq!(actual.critical_maintainability, 1);
+        assert_eq!(maintainability_index.min, Some(10.0));
+        assert_eq!(maintainability_index.median, Some(40.0));
+        assert_eq!(maintainability_index.p90, Some(70.0));
+        assert_eq!(top, ["7.py", "6.py", "5.py", "4.py", "3.py"]);
+    }
+
+    #[rstest]
+    #[case(Preset::Strict, "Files with a maintainability index below 40: 3\n")]
+    #[case(Preset::Default, "Files with a maintainability index below 20: 1\n")]
+    #[case(Preset::Lenient, "Files with a maintainability index below 10: 0\n")]
+    fn test_summary_preset(#[case] preset: Preset, #[case] expected: &str) {
+        // ARRANGE
+        let stats = [10.0, 30.0, 35.0, 60.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, maintainability_index)| HotspotStats {
+                path: format!("{i}.py"),
+                maintainability_index,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        // ACT
+        let actual = Summary::new(&stats, &preset.markers()).to_string();
+
+        // ASSERT
+        assert!(actual.contains(expected), "{actual}");
+    }
+}
@@ -0,0 +1,176 @@
+use rustpython_parser::lexer::lex;
+use rustpython_parser::source_code::LineIndex;
+use rustpython_parser::{Mode, Tok};
+use serde::{Deserialize, Serialize};
+
+use crate::hotspot::FunctionStats;
+
+/// Prefix of the comments suppressing a file or a function.
+const PREFIX: &str = "hotspot:";
+
+/// A file or a function left out of the report by a `# hotspot: ignore` or
+/// `# hotspot: ignore-next-function` comment.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Suppression {
+    pub path: String,
+    /// The suppressed function, none when the whole file is.
+    pub function: Option<String>,
+    /// Line of the comment.
+    pub line: usize,
+    /// The text following the directive, e.g. why the code is accepted.
+    pub reason: Option<String>,
+}
+
+/// What a suppression comment leaves out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Scope {
+    File,
+    NextFunction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Directive {
+    pub scope: Scope,
+    pub line: usize,
+    pub reason: Option<String>,
+}
+
+impl Directive {
+    fn suppression(&self, function: Option<String>) -> Suppression {
+        Suppression {
+            // Set once the path is relative to the root of the repository
+            path: String::new(),
+            function,
+            line: self.line,
+            reason: self.reason.clone(),
+        }
+    }
+}
+
+/// The suppression comments of the source code, such as
+/// `# hotspot: ignore -- generated by protoc`. Source code which cannot be
+/// tokenized has none.
+pub(crate) fn directives(source_code: &str) -> Vec<Directive> {
+    let line_index = LineIndex::from_source_text(source_code);
+    let mut directives = vec![];
+
+    for token in lex(source_code, Mode::Module) {
+        let Ok((token, range)) = token else {
+            return vec![];
+        };
+        let Tok::Comment(comment) = token else {
+            continue;
+        };
+        let Some(text) = comment
+            .trim_start_matches('#')
+            .trim_start()
+            .strip_prefix(PREFIX)
+        else {
+            continue;
+        };
+
+        let text = text.trim();
+        let (name, reason) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let scope = match name {
+            "ignore" => Scope::File,
+            "ignore-next-function" => Scope::NextFunction,
+            _ => continue,
+        };
+        let reason = reason.trim_start_matches(['-', ':', ' ']).trim();
+
+        directives.push(Directive {
+            scope,
+            line: line_index
+                .source_location(range.start(), source_code)
+                .row
+                .to_usize(),
+            reason: (!reason.is_empty()).then(|| reason.to_string()),
+        });
+    }
+
+    directives
+}
+
+/// The suppression of the whole file, if any.
+pub(crate) fn file(directives: &[Directive]) -> Option<Suppression> {
+    directives
+        .iter()
+        .find(|directive| directive.scope == Scope::File)
+        .map(|directive| directive.suppression(None))
+}
+
+/// The suppression of the first function starting after each
+/// `ignore-next-function` comment.
+pub(crate) fn functions(directives: &[Directive], functions: &[FunctionStats]) -> Vec<Suppression> {
+    directives
+        .iter()
+        .filter(|directive| directive.scope == Scope::NextFunction)
+        .filter_map(|directive| {
+            functions
+                .iter()
+                .filter(|function| function.start_line > directive.line)
+                .min_by_key(|function| function.start_line)
+                .map(|function| directive.suppression(Some(function.name.clone())))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives() {
+        // ARRANGE
+        let source_code = "# hotspot: ignore -- generated by protoc\nx = \"# hotspot: ignore\"\n\n#hotspot: ignore-next-function\ndef f():  # hotspot: unknown\n    return 1\n";
+
+        // ACT
+        let actual = directives(source_code);
+
+        // ASSERT
+        let expected = vec![
+            Directive {
+                scope: Scope::File,
+                line: 1,
+                reason: Some("generated by protoc".to_string()),
+            },
+            Directive {
+                scope: Scope::NextFunction,
+                line: 4,
+                reason: None,
+            },
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_functions() {
+        // ARRANGE
+        let function = |name: &str, start_line| FunctionStats {
+            name: name.to_string(),
+            start_line,
+            ..Default::default()
+        };
+        let functions = vec![function("f", 1), function("h", 9), function("g", 5)];
+        let directives = vec![Directive {
+            scope: Scope::NextFunction,
+            line: 3,
+            reason: Some("deprecated".to_string()),
+        }];
+
+        // ACT
+        let actual = super::functions(&directives, &functions);
+
+        // ASSERT
+        assert_eq!(
+            actual,
+            vec![Suppression {
+                path: String::new(),
+                function: Some("g".to_string()),
+                line: 3,
+                reason: Some("deprecated".to_string()),
+            }]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Time spent in each stage of the last analysis, to tell whether a slow
+/// run is bound by git or by the parser.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timings {
+    /// Walking the directory, concurrently with the parsing.
+    pub discovery: Duration,
+    /// Reading the Git history for the changes count.
+    pub history: Duration,
+    /// Parsing the files, summed over the workers.
+    pub parsing: Duration,
+    /// Time to parse each file, by path relative to the root of the
+    /// repository.
+    pub files: Vec<(PathBuf, Duration)>,
+}
+
+impl Timings {
+    /// The files which took the longest to parse, slowest first.
+    pub fn slowest(&self, count: usize) -> Vec<(&Path, Duration)> {
+        let mut files = self
+            .files
+            .iter()
+            .map(|(path, duration)| (path.as_path(), *duration))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        files.truncate(count);
+
+        files
+    }
+
+    /// A plain text summary of the stages, with the time spent writing the
+    /// report, followed by the slowest files to parse.
+    pub fn summary(&self, formatting: Duration, slowest: usize) -> String {
+        let mut summary = String::from("Timings:\n");
+        let stages = [
+            ("discovery", self.discovery),
+            ("git history", self.history),
+            ("parsing", self.parsing),
+            ("formatting", formatting),
+        ];
+
+        for (stage, duration) in stages {
+            let _ = writeln!(summary, "  {stage:<12}{:>10.3}s", duration.as_secs_f64());
+        }
+
+        let files = self.slowest(slowest);
+        if !files.is_empty() {
+            summary.push_str("Slowest files to parse:\n");
+
+            for (path, duration) in files {
+                let _ = writeln!(
+                    summary,
+                    "  {:>10.3}s  {}",
+                    duration.as_secs_f64(),
+                    path.display()
+                );
+            }
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        // ARRANGE
+        let timings = Timings {
+            discovery: Duration::from_millis(12),
+            history: Duration::from_millis(1500),
+            parsing: Duration::from_millis(250),
+            files: vec![
+                (PathBuf::from("a.py"), Duration::from_millis(50)),
+                (PathBuf::from("b.py"), Duration::from_millis(200)),
+                (PathBuf::from("c.py"), Duration::ZERO),
+            ],
+        };
+
+        // ACT
+        let actual = timings.summary(Duration::from_millis(3), 2);
+
+        // ASSERT
+        let expected = "Timings:
+  discovery        0.012s
+  git history      1.500s
+  parsing          0.250s
+  formatting       0.003s
+Slowest files to parse:
+       0.200s  b.py
+       0.050s  a.py
+";
+        assert_eq!(actual, expected);
+    }
+}
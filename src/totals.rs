@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::hotspot::{HotspotStats, PathType};
+use crate::severity::Severity;
+
+/// Aggregates of every file of the project, a top-line summary of a report.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Totals {
+    /// Number of files.
+    pub files: usize,
+    /// Lines of code of the files.
+    pub loc: u64,
+    /// Changes of the files, a commit changing several files counting once
+    /// for each.
+    pub changes_count: u64,
+    /// Maintainability index of the files weighted by their lines of code,
+    /// unknown without files.
+    pub maintainability_index: Option<f64>,
+    /// Number of files of each severity.
+    pub critical: usize,
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+}
+
+impl Totals {
+    /// The totals of the file rows, the function and directory rows being
+    /// left out so nothing is counted twice.
+    pub(crate) fn new(stats: &[HotspotStats]) -> Totals {
+        let files = stats
+            .iter()
+            .filter(|stat| stat.path_type == PathType::File && stat.function.is_none())
+            .collect::<Vec<_>>();
+        let loc = files.iter().map(|stat| stat.loc as u64).sum::<u64>();
+        let count = |severity| {
+            files
+                .iter()
+                .filter(|stat| stat.severity == severity)
+                .count()
+        };
+
+        // Files without code, e.g. empty `__init__.py`, weigh nothing unless
+        // every file is empty
+        let maintainability_index = match (files.len(), loc) {
+            (0, _) => None,
+            (len, 0) => Some(
+                files
+                    .iter()
+                    .map(|stat| stat.maintainability_index)
+                    .sum::<f64>()
+                    / len as f64,
+            ),
+            (_, loc) => Some(
+                files
+                    .iter()
+                    .map(|stat| stat.maintainability_index * stat.loc as f64)
+                    .sum::<f64>()
+                    / loc as f64,
+            ),
+        };
+
+        Totals {
+            files: files.len(),
+            loc,
+            changes_count: files.iter().map(|stat| stat.changes_count as u64).sum(),
+            maintainability_index,
+            critical: count(Severity::Critical),
+            high: count(Severity::High),
+            medium: count(Severity::Medium),
+            low: count(Severity::Low),
+        }
+    }
+
+    /// The set fields as names and values, in the order of the struct.
+    pub(crate) fn fields(&self) -> Vec<(&'static str, String)> {
+        [
+            ("files", Some(self.files.to_string())),
+            ("loc", Some(self.loc.to_string())),
+            ("changes_count", Some(self.changes_count.to_string())),
+            (
+                "maintainability_index",
+                self.maintainability_index.map(|mi| mi.to_string()),
+            ),
+            ("critical", Some(self.critical.to_string())),
+            ("high", Some(self.high.to_string())),
+            ("medium", Some(self.medium.to_string())),
+            ("low", Some(self.low.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals() {
+        // ARRANGE
+        let stat = |path: &str, path_type, loc, mi, severity| HotspotStats {
+            path: path.to_string(),
+            path_type,
+            loc,
+            maintainability_index: mi,
+            changes_count: 2,
+            severity,
+            ..Default::default()
+        };
+        let stats = vec![
+            stat("a.py", PathType::File, 30, 40.0, Severity::Critical),
+            stat("b.py", PathType::File, 10, 80.0, Severity::Low),
+            stat("__init__.py", PathType::File, 0, 100.0, Severity::Low),
+            stat(".", PathType::Directory, 40, 40.0, Severity::Critical),
+        ];
+
+        // ACT
+        let actual = Totals::new(&stats);
+
+        // ASSERT
+        let expected = Totals {
+            files: 3,
+            loc: 40,
+            changes_count: 6,
+            maintainability_index: Some(50.0),
+            critical: 1,
+            high: 0,
+            medium: 0,
+            low: 2,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_totals_without_files() {
+        // ACT
+        let actual = Totals::new(&[]);
+
+        // ASSERT
+        assert_eq!(actual.maintainability_index, None);
+        assert_eq!(actual.fields().len(), 7);
+    }
+}
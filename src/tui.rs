@@ -0,0 +1,405 @@
+use std::error::Error;
+
+use clap::ValueEnum;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use tech_debt_hotspot::{sort_stats_by, HotspotStats, SortBy, SortKey, SortOrder};
+
+const HELP: &str = "↑/↓ move  PgUp/PgDn page  s sort  r reverse  / filter  Esc clear  q quit";
+const PAGE_SIZE: usize = 20;
+
+struct App {
+    stats: Vec<HotspotStats>,
+    visible: Vec<usize>,
+    sort_by: SortBy,
+    sort_order: SortOrder,
+    filter: String,
+    editing_filter: bool,
+    table_state: TableState,
+}
+
+impl App {
+    fn new(stats: Vec<HotspotStats>, sort_by: SortBy, sort_order: SortOrder) -> Self {
+        let mut app = App {
+            stats,
+            visible: vec![],
+            sort_by,
+            sort_order,
+            filter: String::new(),
+            editing_filter: false,
+            table_state: TableState::default(),
+        };
+        app.sort();
+
+        app
+    }
+
+    fn apply_filter(&mut self) {
+        self.visible = self
+            .stats
+            .iter()
+            .enumerate()
+            .filter(|(_, stat)| label(stat).contains(&self.filter))
+            .map(|(index, _)| index)
+            .collect();
+
+        let selected = match self.visible.is_empty() {
+            true => None,
+            false => Some(0),
+        };
+        self.table_state.select(selected);
+    }
+
+    fn cycle_sort(&mut self) {
+        let variants = SortBy::value_variants();
+        let current = variants
+            .iter()
+            .position(|variant| *variant == self.sort_by)
+            .unwrap_or(0);
+
+        self.sort_by = variants[(current + 1) % variants.len()];
+        self.sort_order = self.sort_by.default_order();
+        self.sort();
+    }
+
+    fn reverse_sort(&mut self) {
+        self.sort_order = self.sort_order.reverse();
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        let key = SortKey {
+            sort_by: self.sort_by,
+            order: Some(self.sort_order),
+        };
+
+        self.stats = sort_stats_by(std::mem::take(&mut self.stats), &[key]);
+        self.apply_filter();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+
+        let last = self.visible.len() as isize - 1;
+        let current = self.table_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, last);
+
+        self.table_state.select(Some(next as usize));
+    }
+
+    fn selected(&self) -> Option<&HotspotStats> {
+        self.table_state
+            .selected()
+            .and_then(|row| self.visible.get(row))
+            .map(|index| &self.stats[*index])
+    }
+
+    /// Handle a key press, returning `false` when the application should exit.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.editing_filter {
+            match code {
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Esc => {
+                    self.editing_filter = false;
+                    self.filter.clear();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => self.filter.push(c),
+                _ => return true,
+            }
+            self.apply_filter();
+
+            return true;
+        }
+
+        match code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char('s') => self.cycle_sort(),
+            KeyCode::Char('r') => self.reverse_sort(),
+            KeyCode::Char('/') => self.editing_filter = true,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.apply_filter();
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(PAGE_SIZE as isize),
+            KeyCode::PageUp => self.move_selection(-(PAGE_SIZE as isize)),
+            KeyCode::Home => self.move_selection(isize::MIN / 2),
+            KeyCode::End => self.move_selection(isize::MAX / 2),
+            _ => {}
+        }
+
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [table_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .areas(main_area);
+
+        self.draw_table(frame, table_area);
+        self.draw_detail(frame, detail_area);
+        self.draw_status(frame, status_area);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let header = Row::new(["path", "MI", "CC", "loc", "changes", "hotspot"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = self.visible.iter().map(|index| {
+            let stat = &self.stats[*index];
+
+            Row::new([
+                Cell::from(label(stat)),
+                Cell::from(format!("{:.1}", stat.maintainability_index)),
+                Cell::from(format!("{:.0}", stat.cyclomatic_complexity)),
+                Cell::from(stat.loc.to_string()),
+                Cell::from(stat.changes_count.to_string()),
+                Cell::from(format!("{:.2}", stat.hotspot_index)),
+            ])
+        });
+        let widths = [
+            Constraint::Fill(1),
+            Constraint::Length(6),
+            Constraint::Length(5),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(10),
+        ];
+        let title = format!(
+            " Hotspots ({}/{}) sorted by {} {} ",
+            self.visible.len(),
+            self.stats.len(),
+            sort_name(self.sort_by),
+            match self.sort_order {
+                SortOrder::Ascending => "↑",
+                SortOrder::Descending => "↓",
+            }
+        );
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title(title))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, area, &mut self.table_state);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let lines = match self.selected() {
+            Some(stat) => vec![
+                Line::from(label(stat)),
+                Line::from(""),
+                Line::from(format!(
+                    "Halstead volume:       {:.2}",
+                    stat.halstead_volume
+                )),
+                Line::from(format!(
+                    "Cyclomatic complexity: {:.0}",
+                    stat.cyclomatic_complexity
+                )),
+                Line::from(format!("Max nesting:           {}", stat.max_nesting)),
+                Line::from(format!("Functions:             {}", stat.nom)),
+                Line::from(format!("Longest function:      {}", stat.longest_function)),
+                Line::from(format!("Lines of code:         {}", stat.loc)),
+                Line::from(format!(
+                    "Comments:              {:.1}%",
+                    stat.comments_percentage
+                )),
+                Line::from(format!("Duplication:           {:.1}%", stat.duplication)),
+                Line::from(format!(
+                    "Maintainability index: {:.2}",
+                    stat.maintainability_index
+                )),
+                Line::from(format!("Changes:               {}", stat.changes_count)),
+                Line::from(format!("Hotspot index:         {:.2}", stat.hotspot_index)),
+            ],
+            None => vec![Line::from("No file selected")],
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Details ")),
+            area,
+        );
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        let status = match (self.editing_filter, self.filter.is_empty()) {
+            (true, _) => format!("filter: {}▏", self.filter),
+            (false, false) => format!("filter: {}  |  {HELP}", self.filter),
+            (false, true) => HELP.to_string(),
+        };
+
+        frame.render_widget(Paragraph::new(status), area);
+    }
+}
+
+/// The path of the file, followed by the function and its lines when reporting functions.
+fn label(stat: &HotspotStats) -> String {
+    match (&stat.function, stat.start_line, stat.end_line) {
+        (Some(function), Some(start_line), Some(end_line)) => {
+            format!("{}::{function} ({start_line}-{end_line})", stat.path)
+        }
+        _ => stat.path.clone(),
+    }
+}
+
+fn sort_name(sort_by: SortBy) -> String {
+    sort_by
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn run_app(terminal: &mut DefaultTerminal, mut app: App) -> Result<(), Box<dyn Error>> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !app.handle_key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Explore the hotspots in an interactive terminal table.
+pub fn run(
+    stats: Vec<HotspotStats>,
+    sort_by: SortBy,
+    sort_order: SortOrder,
+) -> Result<(), Box<dyn Error>> {
+    let mut terminal = ratatui::init();
+    let result = run_app(&mut terminal, App::new(stats, sort_by, sort_order));
+    ratatui::restore();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn app(rows: &[(&str, f64)]) -> App {
+        let stats = rows
+            .iter()
+            .map(|(path, hotspot_index)| HotspotStats {
+                path: path.to_string(),
+                hotspot_index: *hotspot_index,
+                ..Default::default()
+            })
+            .collect();
+
+        App::new(stats, SortBy::HotspotIndex, SortOrder::Descending)
+    }
+
+    fn visible_paths(app: &App) -> Vec<&str> {
+        app.visible
+            .iter()
+            .map(|index| app.stats[*index].path.as_str())
+            .collect()
+    }
+
+    #[rstest]
+    #[case("", vec!["pkg/b.py", "main.py", "pkg/a.py"], Some(0))]
+    #[case("pkg", vec!["pkg/b.py", "pkg/a.py"], Some(0))]
+    #[case("missing", vec![], None)]
+    fn test_apply_filter(
+        #[case] filter: &str,
+        #[case] expected_paths: Vec<&str>,
+        #[case] expected_selected: Option<usize>,
+    ) {
+        // ARRANGE
+        let mut app = app(&[("pkg/a.py", 1.0), ("main.py", 2.0), ("pkg/b.py", 3.0)]);
+        app.move_selection(2);
+        app.filter = filter.to_string();
+
+        // ACT
+        app.apply_filter();
+
+        // ASSERT
+        assert_eq!(visible_paths(&app), expected_paths);
+        assert_eq!(app.table_state.selected(), expected_selected);
+    }
+
+    #[test]
+    fn test_cycle_sort() {
+        // ARRANGE
+        let mut app = app(&[("b.py", 1.0), ("a.py", 2.0)]);
+        app.sort_by = *SortBy::value_variants().last().unwrap();
+
+        // ACT
+        app.cycle_sort();
+
+        // ASSERT
+        assert_eq!(app.sort_by, SortBy::value_variants()[0]);
+        assert_eq!(app.sort_order, SortOrder::Ascending);
+        assert_eq!(visible_paths(&app), ["a.py", "b.py"]);
+    }
+
+    #[test]
+    fn test_reverse_sort() {
+        // ARRANGE
+        let mut app = app(&[("a.py", 1.0), ("b.py", 3.0), ("c.py", 2.0)]);
+
+        // ACT
+        app.handle_key(KeyCode::Char('r'));
+
+        // ASSERT
+        assert_eq!(app.sort_order, SortOrder::Ascending);
+        assert_eq!(visible_paths(&app), ["a.py", "c.py", "b.py"]);
+
+        // ACT
+        app.handle_key(KeyCode::Char('r'));
+
+        // ASSERT
+        assert_eq!(app.sort_order, SortOrder::Descending);
+        assert_eq!(visible_paths(&app), ["b.py", "c.py", "a.py"]);
+    }
+
+    #[rstest]
+    #[case(0, -1, 0)]
+    #[case(0, 1, 1)]
+    #[case(2, 1, 2)]
+    #[case(1, PAGE_SIZE as isize, 2)]
+    #[case(1, -(PAGE_SIZE as isize), 0)]
+    #[case(1, isize::MAX / 2, 2)]
+    #[case(1, isize::MIN / 2, 0)]
+    fn test_move_selection(#[case] selected: usize, #[case] delta: isize, #[case] expected: usize) {
+        // ARRANGE
+        let mut app = app(&[("a.py", 1.0), ("b.py", 2.0), ("c.py", 3.0)]);
+        app.table_state.select(Some(selected));
+
+        // ACT
+        app.move_selection(delta);
+
+        // ASSERT
+        assert_eq!(app.table_state.selected(), Some(expected));
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(-1)]
+    fn test_move_selection_empty(#[case] delta: isize) {
+        // ARRANGE
+        let mut app = app(&[]);
+
+        // ACT
+        app.move_selection(delta);
+
+        // ASSERT
+        assert_eq!(app.table_state.selected(), None);
+        assert_eq!(app.selected(), None);
+    }
+}
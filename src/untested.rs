@@ -0,0 +1,81 @@
+use crate::hotspot::{risk, HotspotStats, PathType};
+
+/// Coverage below which the tests are deemed too few to protect a change.
+pub const LOW_COVERAGE: f64 = 50.0;
+
+/// The files and functions changed and complex with no or low test
+/// protection: a coverage below [`LOW_COVERAGE`], or without coverage no
+/// test file named after their module.
+///
+/// The rows without coverage get the risk of uncovered code, the changes
+/// count times the cyclomatic complexity, so all the rows can be ranked by
+/// risk. The directories are left out.
+pub fn untested_hotspots(stats: Vec<HotspotStats>) -> Vec<HotspotStats> {
+    stats
+        .into_iter()
+        .filter(|stat| stat.path_type == PathType::File)
+        .filter(|stat| match stat.coverage {
+            Some(coverage) => coverage < LOW_COVERAGE,
+            None => stat.test_loc == Some(0),
+        })
+        .map(|stat| HotspotStats {
+            risk: stat
+                .risk
+                .or_else(|| risk(stat.changes_count, stat.cyclomatic_complexity, Some(0.0))),
+            ..stat
+        })
+        .filter(|stat| stat.risk.is_some_and(|risk| risk > 0.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(path: &str, coverage: Option<f64>, test_loc: Option<u32>) -> HotspotStats {
+        HotspotStats {
+            path: path.to_string(),
+            path_type: PathType::File,
+            cyclomatic_complexity: 4.0,
+            changes_count: 3,
+            coverage,
+            risk: risk(3, 4.0, coverage),
+            test_loc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_untested_hotspots() {
+        // ARRANGE
+        let stats = vec![
+            stat("covered.py", Some(80.0), Some(0)),
+            stat("low_coverage.py", Some(25.0), Some(10)),
+            stat("tested.py", None, Some(10)),
+            stat("untested.py", None, Some(0)),
+            stat("tests/test_a.py", None, None),
+            HotspotStats {
+                changes_count: 0,
+                ..stat("unchanged.py", None, Some(0))
+            },
+            HotspotStats {
+                path_type: PathType::Directory,
+                ..stat("pkg", None, Some(0))
+            },
+        ];
+
+        // ACT
+        let actual = untested_hotspots(stats);
+
+        // ASSERT
+        let actual = actual
+            .iter()
+            .map(|stat| (stat.path.as_str(), stat.risk))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            [("low_coverage.py", Some(9.0)), ("untested.py", Some(12.0))]
+        );
+    }
+}
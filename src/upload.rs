@@ -0,0 +1,151 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Result;
+use crate::external::run_with_stdin;
+
+/// An object in a bucket of an object storage a report is uploaded to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UploadUrl {
+    /// An `s3://bucket/key` URL of Amazon S3.
+    S3(String),
+    /// A `gs://bucket/key` URL of Google Cloud Storage.
+    Gcs(String),
+}
+
+impl FromStr for UploadUrl {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let (scheme, object) = value
+            .split_once("://")
+            .ok_or_else(|| format!("{value:?} is not an s3:// or gs:// URL"))?;
+
+        let url = match scheme {
+            "s3" => UploadUrl::S3(value.to_string()),
+            "gs" => UploadUrl::Gcs(value.to_string()),
+            _ => return Err(format!("unknown scheme {scheme:?}, expected s3 or gs")),
+        };
+
+        match object.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok(url),
+            _ => Err(format!("{value:?} is not the URL of an object in a bucket")),
+        }
+    }
+}
+
+impl fmt::Display for UploadUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadUrl::S3(url) | UploadUrl::Gcs(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+impl UploadUrl {
+    /// Upload a report with the CLI of the object storage, `aws` or
+    /// `gcloud`, which must be on the `PATH`.
+    ///
+    /// The credentials are the AWS profile or the Google Cloud account to
+    /// use, the CLI picking its default ones otherwise, e.g. from the
+    /// environment of the CI job.
+    pub fn upload(
+        &self,
+        report: &[u8],
+        content_type: &str,
+        credentials: Option<&str>,
+    ) -> Result<()> {
+        let (program, args) = self.command(content_type, credentials);
+
+        run_with_stdin(program, args, report)
+    }
+
+    fn command(
+        &self,
+        content_type: &str,
+        credentials: Option<&str>,
+    ) -> (&'static str, Vec<String>) {
+        match self {
+            UploadUrl::S3(url) => {
+                let mut args = vec![
+                    "s3".to_string(),
+                    "cp".to_string(),
+                    "-".to_string(),
+                    url.clone(),
+                    "--content-type".to_string(),
+                    content_type.to_string(),
+                    "--only-show-errors".to_string(),
+                ];
+                if let Some(profile) = credentials {
+                    args.extend(["--profile".to_string(), profile.to_string()]);
+                }
+
+                ("aws", args)
+            }
+            UploadUrl::Gcs(url) => {
+                let mut args = vec![
+                    "storage".to_string(),
+                    "cp".to_string(),
+                    "-".to_string(),
+                    url.clone(),
+                    format!("--content-type={content_type}"),
+                    "--quiet".to_string(),
+                ];
+                if let Some(account) = credentials {
+                    args.push(format!("--account={account}"));
+                }
+
+                ("gcloud", args)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("s3://bucket/hotspots.html", Ok(UploadUrl::S3("s3://bucket/hotspots.html".to_string())))]
+    #[case("gs://bucket/reports/hotspots.ndjson", Ok(UploadUrl::Gcs("gs://bucket/reports/hotspots.ndjson".to_string())))]
+    #[case("s3://bucket", Err("\"s3://bucket\" is not the URL of an object in a bucket".to_string()))]
+    #[case("s3:///key", Err("\"s3:///key\" is not the URL of an object in a bucket".to_string()))]
+    #[case("https://bucket/key", Err("unknown scheme \"https\", expected s3 or gs".to_string()))]
+    #[case("ftp://bucket", Err("unknown scheme \"ftp\", expected s3 or gs".to_string()))]
+    #[case("hotspots.html", Err("\"hotspots.html\" is not an s3:// or gs:// URL".to_string()))]
+    fn test_upload_url_from_str(
+        #[case] value: &str,
+        #[case] expected: std::result::Result<UploadUrl, String>,
+    ) {
+        // ACT
+        let actual = value.parse::<UploadUrl>();
+
+        // ASSERT
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_command() {
+        // ARRANGE
+        let url = UploadUrl::Gcs("gs://bucket/hotspots.html".to_string());
+
+        // ACT
+        let (program, args) = url.command("text/html", Some("ci@example.com"));
+
+        // ASSERT
+        assert_eq!(program, "gcloud");
+        assert_eq!(
+            args,
+            [
+                "storage",
+                "cp",
+                "-",
+                "gs://bucket/hotspots.html",
+                "--content-type=text/html",
+                "--quiet",
+                "--account=ci@example.com",
+            ]
+        );
+    }
+}
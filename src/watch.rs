@@ -0,0 +1,125 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tracing::{info, warn};
+
+use tech_debt_hotspot::TechDebtHotspots;
+
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Watch the analysed directory and re-render the report whenever files change.
+///
+/// Only the modified files are parsed again, the changes count collected at
+/// startup is kept as-is since editing a file does not add to its history.
+pub fn run<F>(mut hotspots: TechDebtHotspots, render: F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(&TechDebtHotspots) -> Result<(), Box<dyn Error>>,
+{
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+
+    watcher.watch(hotspots.path(), RecursiveMode::Recursive)?;
+    info!("Watching {} for changes", hotspots.path().display());
+
+    render(&hotspots)?;
+
+    while let Some(changed_paths) = next_changes(&receiver, DEBOUNCE_TIMEOUT) {
+        if changed_paths.is_empty() {
+            continue;
+        }
+
+        // The paths outside of the analysis are skipped by the refresh
+        let changed_paths: Vec<PathBuf> = changed_paths.into_iter().collect();
+        hotspots.refresh(&changed_paths);
+        render(&hotspots)?;
+    }
+
+    Ok(())
+}
+
+/// The paths changed by the next burst of events, waiting for it to settle
+/// for `debounce`, none once the watcher stopped.
+fn next_changes(
+    receiver: &Receiver<notify::Result<Event>>,
+    debounce: Duration,
+) -> Option<BTreeSet<PathBuf>> {
+    let mut changed_paths = BTreeSet::new();
+    collect_changed_paths(receiver.recv().ok()?, &mut changed_paths);
+
+    // Wait for the burst of events of a single save to settle
+    while let Ok(result) = receiver.recv_timeout(debounce) {
+        collect_changed_paths(result, &mut changed_paths);
+    }
+
+    Some(changed_paths)
+}
+
+fn collect_changed_paths(result: notify::Result<Event>, changed_paths: &mut BTreeSet<PathBuf>) {
+    match result {
+        // Reading the files while refreshing emits access events, which must
+        // not trigger another refresh
+        Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+        Ok(event) => changed_paths.extend(event.paths),
+        Err(error) => warn!("Watch error: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn test_next_changes() {
+        // ARRANGE
+        let (sender, receiver) = mpsc::channel();
+        let debounce = Duration::from_millis(10);
+        let modify = EventKind::Modify(ModifyKind::Any);
+
+        for result in [
+            event(modify, "pkg/a.py"),
+            event(EventKind::Access(AccessKind::Any), "pkg/b.py"),
+            event(modify, "pkg/a.py"),
+            Err(notify::Error::generic("overflow")),
+            event(EventKind::Create(CreateKind::File), "pkg/c.py"),
+        ] {
+            sender.send(result).unwrap();
+        }
+
+        // ACT
+        let actual = next_changes(&receiver, debounce);
+
+        // ASSERT
+        let expected = BTreeSet::from([PathBuf::from("pkg/a.py"), PathBuf::from("pkg/c.py")]);
+
+        assert_eq!(actual, Some(expected));
+
+        // ARRANGE
+        sender
+            .send(event(EventKind::Access(AccessKind::Any), "pkg/a.py"))
+            .unwrap();
+
+        // ACT
+        let actual = next_changes(&receiver, debounce);
+
+        // ASSERT
+        assert_eq!(actual, Some(BTreeSet::new()));
+
+        // ARRANGE
+        drop(sender);
+
+        // ACT
+        let actual = next_changes(&receiver, debounce);
+
+        // ASSERT
+        assert_eq!(actual, None);
+    }
+}